@@ -12,22 +12,24 @@ use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::{env, fs};
 
-use superpattern::transform_pattern;
+use superpattern::{check_syntax, combine_patterns, transform_pattern, Combination, Epe};
 
 fn main() {
     memory();
     build_superpattern();
+    build_combinations();
 }
 
 fn build_superpattern() {
     println!("cargo:rerun-if-changed=superpattern/patterns");
+    fs::create_dir_all("superpattern/generated").unwrap();
 
     for e in fs::read_dir("superpattern/patterns").unwrap().enumerate() {
         let i = e.0;
         let entry = e.1.unwrap();
         let path = entry.path();
 
-        if path.extension().is_some_and(|e| e != "epe") {
+        if path.extension().is_none_or(|e| e != "epe") {
             continue;
         }
 
@@ -36,14 +38,14 @@ fn build_superpattern() {
             .expect("Failed to open")
             .read_to_string(&mut raw_json)
             .unwrap();
-        let pattern = json::parse(&raw_json.trim_matches(|t: char| !t.is_ascii()))
-            .map_err(|e| format!("failed to parse {:?}: {:?}", path, e))
+        let pattern = Epe::parse(&raw_json)
+            .map_err(|e| format!("failed to parse {:?}: {}", path, e))
             .unwrap();
-        let str = pattern["sources"]["main"]
-            .as_str()
-            .expect("sources.main was not a string");
 
-        let res = transform_pattern(str);
+        let res = transform_pattern(&pattern.source);
+        check_syntax(&res.transformed_pattern)
+            .map_err(|e| format!("failed to transform {:?}: {}", path, e))
+            .unwrap();
 
         _ = File::write_all(
             &mut File::create(format!("superpattern/generated/generated-{}.js", i)).unwrap(),
@@ -52,6 +54,52 @@ fn build_superpattern() {
     }
 }
 
+fn build_combinations() {
+    println!("cargo:rerun-if-changed=superpattern/combinations.txt");
+    let Ok(combinations) = fs::read_to_string("superpattern/combinations.txt") else {
+        return;
+    };
+
+    let lines = combinations
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    for (i, line) in lines.enumerate() {
+        let combination: Combination = line
+            .parse()
+            .map_err(|e| format!("invalid combination {:?}: {}", line, e))
+            .unwrap();
+
+        let patterns: Vec<_> = combination
+            .patterns
+            .iter()
+            .map(|name| {
+                let path = PathBuf::from("superpattern/patterns").join(name);
+                let raw_json = fs::read_to_string(&path)
+                    .map_err(|e| format!("failed to open {:?}: {}", path, e))
+                    .unwrap();
+                let pattern = Epe::parse(&raw_json)
+                    .map_err(|e| format!("failed to parse {:?}: {}", path, e))
+                    .unwrap();
+                transform_pattern(&pattern.source)
+            })
+            .collect();
+
+        let combined = combine_patterns(&patterns, &combination.blend_modes)
+            .map(|combined| format!("// {}\n{}", combination.name, combined))
+            .map_err(|e| format!("failed to combine {:?}: {}", combination.name, e))
+            .unwrap();
+        check_syntax(&combined)
+            .map_err(|e| format!("failed to combine {:?}: {}", combination.name, e))
+            .unwrap();
+
+        _ = File::write_all(
+            &mut File::create(format!("superpattern/generated/combined-{}.js", i)).unwrap(),
+            combined.as_bytes(),
+        );
+    }
+}
+
 fn memory() {
     // Put `memory.x` in our output directory and ensure it's
     // on the linker search path.
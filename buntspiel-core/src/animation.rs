@@ -0,0 +1,48 @@
+//! # Fallback Animations
+//!
+//! Frames of the animations shown while no preview frames arrive, shared by
+//! the firmware and the desktop simulator.
+
+use crate::frame::RGBPattern;
+
+/// How long each frame of [`WAIT_ANIMATION`] is shown (ms).
+pub const WAIT_ANIMATION_FRAME_MS: u64 = 600;
+
+/// Rotating diagonal line, shown while connecting.
+pub const WAIT_ANIMATION: [RGBPattern; 5] = [
+    // Frame 1: Main diagonal (top-left to bottom-right)
+    RGBPattern {
+        r1: "x...", // LED at (0,0)
+        r2: ".x..", // LED at (1,1)
+        r3: "..x.", // LED at (2,2)
+        r4: "...x", // LED at (3,3)
+    },
+    // Frame 2: Shifted diagonal pattern
+    RGBPattern {
+        r1: ".x..", // LED at (0,1)
+        r2: "x...", // LED at (1,0)
+        r3: "...x", // LED at (2,3)
+        r4: "..x.", // LED at (3,2)
+    },
+    // Frame 3: Anti-diagonal (top-right to bottom-left)
+    RGBPattern {
+        r1: "..x.", // LED at (0,2)
+        r2: "...x", // LED at (1,3)
+        r3: "x...", // LED at (2,0)
+        r4: ".x..", // LED at (3,1)
+    },
+    // Frame 4: Shifted anti-diagonal pattern
+    RGBPattern {
+        r1: "...x", // LED at (0,3)
+        r2: "..x.", // LED at (1,2)
+        r3: ".x..", // LED at (2,1)
+        r4: "x...", // LED at (3,0)
+    },
+    // Frame 5: Return to shifted diagonal (creates smooth loop)
+    RGBPattern {
+        r1: "..x.", // LED at (0,2)
+        r2: "...x", // LED at (1,3)
+        r3: "x...", // LED at (2,0)
+        r4: ".x..", // LED at (3,1)
+    },
+];
@@ -0,0 +1,115 @@
+//! # Reconnect Backoff
+//!
+//! Delays between reconnect attempts, shared by the WiFi and Pixelblaze
+//! tasks: the delay doubles with every failed attempt up to a cap, so a cube
+//! waiting for a switched off lighthouse doesn't drain its battery. Jitter
+//! keeps several cubes from retrying in lockstep.
+
+/// Exponential backoff with jitter and a cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Backoff {
+    /// Delay after the first failed attempt (ms)
+    initial_ms: u64,
+    /// Upper bound of the delay (ms)
+    max_ms: u64,
+    /// Share of the delay that is randomly cut off (0-100 %)
+    jitter_percent: u8,
+    /// Failed attempts since the last success
+    attempts: u32,
+}
+
+impl Backoff {
+    pub const fn new(initial_ms: u64, max_ms: u64, jitter_percent: u8) -> Self {
+        Self {
+            initial_ms,
+            max_ms,
+            jitter_percent: if jitter_percent > 100 {
+                100
+            } else {
+                jitter_percent
+            },
+            attempts: 0,
+        }
+    }
+
+    /// Delay before the next attempt after a failed one, using `random` (any
+    /// value from an RNG) for the jitter.
+    ///
+    /// The delay doubles with every call until it reaches the cap, and is
+    /// then shortened by up to the jitter share.
+    pub fn next_delay_ms(&mut self, random: u32) -> u64 {
+        let factor = 1_u64.checked_shl(self.attempts).unwrap_or(u64::MAX);
+        let delay = self.initial_ms.saturating_mul(factor).min(self.max_ms);
+        self.attempts = self.attempts.saturating_add(1);
+
+        let jitter = delay * u64::from(self.jitter_percent) / 100;
+        delay - u64::from(random) % (jitter + 1)
+    }
+
+    /// Start over with the initial delay after a successful attempt.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    /// Failed attempts since the last success.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_delay_up_to_cap() {
+        let mut backoff = Backoff::new(1_000, 60_000, 0);
+        let delays: [u64; 8] = core::array::from_fn(|_| backoff.next_delay_ms(7));
+        assert_eq!(
+            delays,
+            [1_000, 2_000, 4_000, 8_000, 16_000, 32_000, 60_000, 60_000]
+        );
+        assert_eq!(backoff.attempts(), 8);
+
+        // No overflow however long the lighthouse stays off
+        for _ in 0..100 {
+            backoff.next_delay_ms(u32::MAX);
+        }
+        assert_eq!(backoff.next_delay_ms(0), 60_000);
+    }
+
+    #[test]
+    fn jitter_shortens_delay_within_share() {
+        let mut seed = 0x2545_f491_u32;
+        for attempt in 0..10 {
+            let mut backoff = Backoff::new(2_000, 30_000, 50);
+            for _ in 0..attempt {
+                backoff.next_delay_ms(0);
+            }
+            let full = (2_000 << attempt).min(30_000);
+
+            // xorshift, like a small RNG would
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let delay = backoff.next_delay_ms(seed);
+            assert!(delay <= full && delay >= full / 2, "{} of {}", delay, full);
+        }
+
+        let mut backoff = Backoff::new(1_000, 1_000, 200);
+        assert_eq!(backoff.next_delay_ms(1_000), 0);
+    }
+
+    #[test]
+    fn resets_after_success() {
+        let mut backoff = Backoff::new(500, 10_000, 0);
+        backoff.next_delay_ms(0);
+        backoff.next_delay_ms(0);
+        assert_eq!(backoff.next_delay_ms(0), 2_000);
+
+        backoff.reset();
+        assert_eq!(backoff.attempts(), 0);
+        assert_eq!(backoff.next_delay_ms(0), 500);
+    }
+}
@@ -0,0 +1,175 @@
+//! # Color Pipeline
+//!
+//! Color correction applied to every frame before it reaches the LEDs:
+//! gamma correction, white balance, global brightness and a current limit.
+
+use crate::frame::Rgb;
+
+/// Current drawn by one LED color channel at full value (mA).
+pub const CHANNEL_CURRENT_MA: u32 = 20;
+
+/// Color correction applied to every frame before it is shown.
+pub struct ColorPipeline {
+    /// Gamma correction lookup table
+    gamma: [u8; 256],
+    /// Global brightness (0-255)
+    brightness: u8,
+    /// Per-channel scale (0-255)
+    white_balance: Rgb,
+    /// Current budget for the whole matrix (mA)
+    current_limit_ma: u32,
+    /// Scale (0-256) applied by the current limit to the last frame
+    limit: u32,
+}
+
+impl ColorPipeline {
+    /// Pipeline with the given gamma curve, brightness (0-255), white balance
+    /// (0-255 per channel) and current budget (mA).
+    pub fn new(gamma: f32, brightness: u8, white_balance: Rgb, current_limit_ma: u32) -> Self {
+        let mut lut = [0; 256];
+        for (i, value) in lut.iter_mut().enumerate() {
+            *value = (libm::powf(i as f32 / 255.0, gamma) * 255.0 + 0.5) as u8;
+        }
+        Self {
+            gamma: lut,
+            brightness,
+            white_balance,
+            current_limit_ma,
+            limit: 256,
+        }
+    }
+
+    /// Global brightness (0-255).
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Change the global brightness for all following frames.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    /// Correct a whole frame in place, scaling it down if it would draw more
+    /// than the current budget.
+    pub fn apply(&mut self, frame: &mut [Rgb]) {
+        let mut total = 0;
+        for pixel in frame.iter_mut() {
+            *pixel = self.correct_unlimited(*pixel);
+            total += u32::from(pixel.r) + u32::from(pixel.g) + u32::from(pixel.b);
+        }
+
+        let current_ma = total * CHANNEL_CURRENT_MA / 255;
+        self.limit = match current_ma > self.current_limit_ma {
+            true => self.current_limit_ma * 256 / current_ma,
+            false => 256,
+        };
+        if self.limit < 256 {
+            for pixel in frame.iter_mut() {
+                *pixel = self.limited(*pixel);
+            }
+        }
+    }
+
+    /// Correct a single pixel (e.g. key feedback), limited like the last frame.
+    pub fn correct(&self, pixel: Rgb) -> Rgb {
+        self.limited(self.correct_unlimited(pixel))
+    }
+
+    fn correct_unlimited(&self, Rgb { r, g, b }: Rgb) -> Rgb {
+        let channel = |value: u8, balance: u8| {
+            let scaled = u32::from(self.gamma[usize::from(value)])
+                * u32::from(balance)
+                * u32::from(self.brightness);
+            ((scaled + 255 * 255 / 2) / (255 * 255)) as u8
+        };
+        Rgb {
+            r: channel(r, self.white_balance.r),
+            g: channel(g, self.white_balance.g),
+            b: channel(b, self.white_balance.b),
+        }
+    }
+
+    fn limited(&self, Rgb { r, g, b }: Rgb) -> Rgb {
+        let channel = |value: u8| (u32::from(value) * self.limit / 256) as u8;
+        Rgb {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgb = Rgb {
+        r: 0xFF,
+        g: 0xFF,
+        b: 0xFF,
+    };
+
+    #[test]
+    fn applies_gamma_and_brightness() {
+        let mut pipeline = ColorPipeline::new(2.0, 0xFF, WHITE, u32::MAX);
+        let mut frame = [
+            WHITE,
+            Rgb {
+                r: 0x80,
+                g: 0,
+                b: 0,
+            },
+        ];
+        pipeline.apply(&mut frame);
+        assert_eq!(
+            frame,
+            [
+                WHITE,
+                Rgb {
+                    r: 0x40,
+                    g: 0,
+                    b: 0
+                }
+            ]
+        );
+
+        pipeline.set_brightness(0x80);
+        assert_eq!(
+            pipeline.correct(WHITE),
+            Rgb {
+                r: 0x80,
+                g: 0x80,
+                b: 0x80
+            }
+        );
+    }
+
+    #[test]
+    fn applies_white_balance() {
+        let balance = Rgb {
+            r: 0xFF,
+            g: 0x80,
+            b: 0,
+        };
+        let pipeline = ColorPipeline::new(1.0, 0xFF, balance, u32::MAX);
+        assert_eq!(pipeline.correct(WHITE), balance);
+    }
+
+    #[test]
+    fn limits_current() {
+        // 16 white pixels at full value draw 16 * 3 * 20 mA = 960 mA
+        let mut pipeline = ColorPipeline::new(1.0, 0xFF, WHITE, 480);
+        let mut frame = [WHITE; 16];
+        pipeline.apply(&mut frame);
+        assert!(frame.iter().all(|p| p.r == 0x7F));
+
+        // Single pixels are scaled like the last frame
+        assert_eq!(pipeline.correct(WHITE).g, 0x7F);
+
+        // Dark frames are not limited
+        let mut frame = [Rgb::default(); 16];
+        frame[0] = WHITE;
+        pipeline.apply(&mut frame);
+        assert_eq!(frame[0], WHITE);
+    }
+}
@@ -0,0 +1,556 @@
+//! # Persistent Configuration
+//!
+//! Settings that survive a reboot (known WiFi networks, Pixelblaze endpoints,
+//! LED brightness), kept in a flash region reserved for them.
+//!
+//! ## Format
+//! The region is an append-only log of records, each holding the complete
+//! configuration, all fields little-endian:
+//! `[magic: u32, version: u16, len: u16, sequence: u32, crc: u32, payload]`.
+//! The CRC-32 covers everything after the magic up to the end of the payload.
+//! The payload is a list of entries `[key: u8, len: u8, value]`, unknown keys
+//! are skipped. A record with another format version is ignored.
+//!
+//! ## Wear
+//! Saving appends a record behind the newest one, a sector is only erased
+//! when the log moves on to it. The sectors are used round robin, and the
+//! newest record survives a power loss during a save. Without a valid record
+//! (new device, corrupted flash, format version changed) there is no
+//! configuration and the caller falls back to its defaults.
+
+use core::str::from_utf8;
+
+use crate::endpoint::{Endpoint, EndpointList};
+use crate::networks::{KnownNetwork, KnownNetworks};
+
+/// Format version of the records, bump on incompatible payload changes.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Marks the start of a record.
+const MAGIC: u32 = 0x4643_5342; // "BSCF"
+
+/// Length of the record header.
+const HEADER_LEN: usize = 16;
+
+/// Records start at multiples of this (bytes).
+const RECORD_ALIGN: usize = 4;
+
+/// Maximum payload length, leaves room for all networks and endpoints.
+pub const MAX_PAYLOAD_LEN: usize = 1024 - HEADER_LEN;
+
+/// Payload entry keys
+const KEY_NETWORK: u8 = 1; // [ssid_len, ssid, passphrase_len, passphrase, priority]
+const KEY_ENDPOINT: u8 = 2; // Endpoint as text, `ip:port`
+const KEY_BRIGHTNESS: u8 = 3; // [brightness]
+
+/// Configuration error types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// Reading, writing or erasing the flash failed
+    Flash,
+    /// Configuration doesn't fit into a record
+    TooLarge,
+    /// Payload can't be decoded
+    Invalid,
+}
+
+/// Settings stored in flash.
+///
+/// Deliberately not `defmt::Format`, so passphrases don't end up in logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// WiFi networks the cube may join
+    pub networks: KnownNetworks,
+    /// Pixelblaze endpoints tried after the discovered controllers
+    pub endpoints: EndpointList,
+    /// Global LED brightness after startup (0-255)
+    pub brightness: u8,
+}
+
+impl Config {
+    /// Encode as record payload, returns the length written to `buf`.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, ConfigError> {
+        let mut writer = Writer { buf, len: 0 };
+
+        for network in &self.networks {
+            let ssid = network.ssid.as_bytes();
+            let passphrase = network.passphrase().unwrap_or("").as_bytes();
+            writer.entry(
+                KEY_NETWORK,
+                &[
+                    &[ssid.len() as u8],
+                    ssid,
+                    &[passphrase.len() as u8],
+                    passphrase,
+                    &[network.priority],
+                ],
+            )?;
+        }
+        for endpoint in self.endpoints.iter() {
+            writer.entry(KEY_ENDPOINT, &[endpoint.host().as_bytes()])?;
+        }
+        writer.entry(KEY_BRIGHTNESS, &[&[self.brightness]])?;
+
+        Ok(writer.len)
+    }
+
+    /// Decode a record payload. Entries with unknown keys are skipped, the
+    /// brightness is required.
+    pub fn decode(payload: &[u8]) -> Result<Self, ConfigError> {
+        let mut networks = KnownNetworks::new();
+        let mut endpoints = EndpointList::new();
+        let mut brightness = None;
+
+        let mut rest = payload;
+        while let [key, len, tail @ ..] = rest {
+            let (value, tail) = tail
+                .split_at_checked(usize::from(*len))
+                .ok_or(ConfigError::Invalid)?;
+            rest = tail;
+
+            match *key {
+                KEY_NETWORK => {
+                    let network = decode_network(value).ok_or(ConfigError::Invalid)?;
+                    networks.push(network).map_err(|_| ConfigError::Invalid)?;
+                }
+                KEY_ENDPOINT => {
+                    let endpoint = from_utf8(value)
+                        .ok()
+                        .and_then(|host| Endpoint::parse(host).ok())
+                        .ok_or(ConfigError::Invalid)?;
+                    endpoints.push(endpoint).map_err(|_| ConfigError::Invalid)?;
+                }
+                KEY_BRIGHTNESS => match value {
+                    [value] => brightness = Some(*value),
+                    _ => return Err(ConfigError::Invalid),
+                },
+                _ => {} // Written by newer firmware
+            }
+        }
+        if !rest.is_empty() {
+            return Err(ConfigError::Invalid);
+        }
+
+        Ok(Self {
+            networks,
+            endpoints,
+            brightness: brightness.ok_or(ConfigError::Invalid)?,
+        })
+    }
+}
+
+/// `[ssid_len, ssid, passphrase_len, passphrase, priority]`
+fn decode_network(value: &[u8]) -> Option<KnownNetwork> {
+    let (&ssid_len, rest) = value.split_first()?;
+    let (ssid, rest) = rest.split_at_checked(usize::from(ssid_len))?;
+    let (&passphrase_len, rest) = rest.split_first()?;
+    let (passphrase, rest) = rest.split_at_checked(usize::from(passphrase_len))?;
+    let [priority] = rest else {
+        return None;
+    };
+    KnownNetwork::new(
+        from_utf8(ssid).ok()?,
+        from_utf8(passphrase).ok()?,
+        *priority,
+    )
+    .ok()
+}
+
+/// Appends payload entries to a buffer.
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    /// Entry with a value made of the given parts.
+    fn entry(&mut self, key: u8, parts: &[&[u8]]) -> Result<(), ConfigError> {
+        let value_len: usize = parts.iter().map(|part| part.len()).sum();
+        let len = u8::try_from(value_len).map_err(|_| ConfigError::TooLarge)?;
+        self.push(&[key, len])?;
+        for part in parts {
+            self.push(part)?;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<(), ConfigError> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(ConfigError::TooLarge)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Flash region holding the configuration log, offsets relative to its
+/// start. Erased flash reads as `0xFF`.
+pub trait ConfigFlash {
+    type Error;
+
+    /// Erase sector size (bytes)
+    const SECTOR_SIZE: u32;
+
+    /// Size of the region, a multiple of [`Self::SECTOR_SIZE`] of at least two
+    /// sectors (bytes)
+    fn capacity(&self) -> u32;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Program erased bytes.
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Erase the sectors from `from` up to `to` (exclusive).
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error>;
+}
+
+/// Record header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    version: u16,
+    len: u16,
+    sequence: u32,
+    crc: u32,
+}
+
+impl Header {
+    /// `None` if the bytes aren't a record header.
+    fn decode(bytes: &[u8; HEADER_LEN]) -> Option<Self> {
+        let u32_at =
+            |n: usize| u32::from_le_bytes([bytes[n], bytes[n + 1], bytes[n + 2], bytes[n + 3]]);
+        let u16_at = |n: usize| u16::from_le_bytes([bytes[n], bytes[n + 1]]);
+        if u32_at(0) != MAGIC {
+            return None;
+        }
+        Some(Self {
+            version: u16_at(4),
+            len: u16_at(6),
+            sequence: u32_at(8),
+            crc: u32_at(12),
+        })
+    }
+
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.len.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+
+    /// CRC of the header fields and the payload.
+    fn checksum(&self, payload: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(&self.version.to_le_bytes());
+        crc.update(&self.len.to_le_bytes());
+        crc.update(&self.sequence.to_le_bytes());
+        crc.update(payload);
+        crc.finish()
+    }
+
+    /// Space the record takes in flash, including alignment padding.
+    fn record_len(&self) -> u32 {
+        (HEADER_LEN + usize::from(self.len)).next_multiple_of(RECORD_ALIGN) as u32
+    }
+}
+
+/// CRC-32 (IEEE 802.3), bitwise to keep the firmware small.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Configuration log in a flash region.
+pub struct ConfigStore<F> {
+    flash: F,
+    /// Offset of the next record
+    next: u32,
+    /// Sequence number of the newest record
+    sequence: u32,
+}
+
+impl<F: ConfigFlash> ConfigStore<F> {
+    /// Store on a flash region, [`Self::load`] must be called before saving.
+    pub fn new(flash: F) -> Self {
+        Self {
+            flash,
+            next: 0,
+            sequence: 0,
+        }
+    }
+
+    /// Newest valid configuration, `None` if there is none.
+    pub fn load(&mut self) -> Result<Option<Config>, ConfigError> {
+        let sector_size = F::SECTOR_SIZE;
+        let mut payload = [0_u8; MAX_PAYLOAD_LEN];
+        let mut newest: Option<(u32, Option<Config>)> = None;
+        self.next = 0;
+        self.sequence = 0;
+
+        for sector in (0..self.flash.capacity()).step_by(sector_size as usize) {
+            let sector_end = sector + sector_size;
+            let mut offset = sector;
+            let mut newest_here = false;
+
+            while offset + HEADER_LEN as u32 <= sector_end {
+                let mut bytes = [0_u8; HEADER_LEN];
+                self.read(offset, &mut bytes)?;
+                if bytes.iter().all(|&b| b == 0xFF) {
+                    break; // Erased, the rest of the sector is free
+                }
+                let header = Header::decode(&bytes)
+                    .filter(|h| usize::from(h.len) <= MAX_PAYLOAD_LEN)
+                    .filter(|h| offset + h.record_len() <= sector_end);
+                let Some(header) = header else {
+                    // Interrupted save or garbage, unusable until erased
+                    offset = sector_end;
+                    break;
+                };
+
+                let payload = &mut payload[..usize::from(header.len)];
+                self.read(offset + HEADER_LEN as u32, payload)?;
+                offset += header.record_len();
+                if header.checksum(payload) != header.crc {
+                    continue;
+                }
+
+                if newest
+                    .as_ref()
+                    .is_none_or(|(seq, _)| header.sequence > *seq)
+                {
+                    let config = match header.version {
+                        FORMAT_VERSION => Config::decode(payload).ok(),
+                        _ => None,
+                    };
+                    newest = Some((header.sequence, config));
+                    newest_here = true;
+                }
+            }
+
+            if newest_here {
+                self.next = offset;
+            }
+        }
+
+        Ok(newest.and_then(|(sequence, config)| {
+            self.sequence = sequence;
+            config
+        }))
+    }
+
+    /// Append the configuration as the newest record.
+    pub fn save(&mut self, config: &Config) -> Result<(), ConfigError> {
+        let sector_size = F::SECTOR_SIZE;
+        let mut record = [0xFF_u8; HEADER_LEN + MAX_PAYLOAD_LEN];
+        let len = config.encode(&mut record[HEADER_LEN..])?;
+
+        let mut header = Header {
+            version: FORMAT_VERSION,
+            len: len as u16,
+            sequence: self.sequence.wrapping_add(1),
+            crc: 0,
+        };
+        header.crc = header.checksum(&record[HEADER_LEN..HEADER_LEN + len]);
+        record[..HEADER_LEN].copy_from_slice(&header.encode());
+        let record = &record[..header.record_len() as usize];
+
+        // Move on to the next sector if the record doesn't fit anymore
+        let mut offset = self.next;
+        if offset % sector_size + record.len() as u32 > sector_size {
+            offset = offset.next_multiple_of(sector_size);
+        }
+        if offset >= self.flash.capacity() {
+            offset = 0;
+        }
+        if offset.is_multiple_of(sector_size) {
+            self.flash
+                .erase(offset, offset + sector_size)
+                .map_err(|_| ConfigError::Flash)?;
+        }
+
+        self.flash
+            .write(offset, record)
+            .map_err(|_| ConfigError::Flash)?;
+        self.next = offset + record.len() as u32;
+        self.sequence = header.sequence;
+        Ok(())
+    }
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ConfigError> {
+        self.flash
+            .read(offset, bytes)
+            .map_err(|_| ConfigError::Flash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTORS: usize = 4;
+
+    /// Flash in RAM with small sectors, counting erases.
+    struct RamFlash {
+        bytes: [u8; SECTORS * 1024],
+        erases: [u32; SECTORS],
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            Self {
+                bytes: [0xFF; SECTORS * 1024],
+                erases: [0; SECTORS],
+            }
+        }
+    }
+
+    impl ConfigFlash for &mut RamFlash {
+        type Error = ();
+        const SECTOR_SIZE: u32 = 1024;
+
+        fn capacity(&self) -> u32 {
+            self.bytes.len() as u32
+        }
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.bytes[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+            for (n, byte) in bytes.iter().enumerate() {
+                // Programming only clears bits
+                self.bytes[offset as usize + n] &= byte;
+            }
+            Ok(())
+        }
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), ()> {
+            self.bytes[from as usize..to as usize].fill(0xFF);
+            for sector in from / Self::SECTOR_SIZE..to / Self::SECTOR_SIZE {
+                self.erases[sector as usize] += 1;
+            }
+            Ok(())
+        }
+    }
+
+    fn config(brightness: u8) -> Config {
+        Config {
+            networks: [
+                KnownNetwork::new("Testturm2", "12345678", 3),
+                KnownNetwork::new("Pixelblaze_Leuchtturm", "", 2),
+            ]
+            .into_iter()
+            .map(Result::unwrap)
+            .collect(),
+            endpoints: EndpointList::parse("192.168.4.1:81, 10.0.0.42").unwrap(),
+            brightness,
+        }
+    }
+
+    #[test]
+    fn encodes_configuration() {
+        let mut buf = [0_u8; MAX_PAYLOAD_LEN];
+        let len = config(0x40).encode(&mut buf).unwrap();
+        assert_eq!(Config::decode(&buf[..len]), Ok(config(0x40)));
+
+        // Entries of newer firmware are skipped
+        let mut newer = [0_u8; MAX_PAYLOAD_LEN];
+        newer[..3].copy_from_slice(&[42, 1, 0]);
+        newer[3..3 + len].copy_from_slice(&buf[..len]);
+        assert_eq!(Config::decode(&newer[..3 + len]), Ok(config(0x40)));
+
+        assert_eq!(Config::decode(&buf[..len - 1]), Err(ConfigError::Invalid));
+        assert_eq!(Config::decode(&[]), Err(ConfigError::Invalid));
+        assert_eq!(
+            config(0x40).encode(&mut buf[..20]),
+            Err(ConfigError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn loads_newest_valid_record() {
+        let mut flash = RamFlash::new();
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(None));
+        store.save(&config(1)).unwrap();
+        let second = store.next as usize;
+        store.save(&config(2)).unwrap();
+
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(Some(config(2))));
+
+        // Corrupted newest record, the previous one is used
+        flash.bytes[second + HEADER_LEN] ^= 0x01;
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(Some(config(1))));
+
+        // Saving continues behind the corrupted record
+        store.save(&config(3)).unwrap();
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(Some(config(3))));
+    }
+
+    #[test]
+    fn ignores_other_format_versions() {
+        let mut flash = RamFlash::new();
+        let mut store = ConfigStore::new(&mut flash);
+        store.load().unwrap();
+        store.save(&config(1)).unwrap();
+
+        // Rewrite the header as a future format version
+        let mut header = Header::decode(flash.bytes[..HEADER_LEN].try_into().unwrap()).unwrap();
+        let len = usize::from(header.len);
+        header.version = FORMAT_VERSION + 1;
+        header.crc = header.checksum(&flash.bytes[HEADER_LEN..HEADER_LEN + len]);
+        flash.bytes[..HEADER_LEN].copy_from_slice(&header.encode());
+
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(None));
+        // Saving continues after it and wins
+        store.save(&config(2)).unwrap();
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(Some(config(2))));
+    }
+
+    #[test]
+    fn spreads_erases_over_sectors() {
+        let mut flash = RamFlash::new();
+        for brightness in 0..=200 {
+            // Reboot between saves
+            let mut store = ConfigStore::new(&mut flash);
+            store.load().unwrap();
+            store.save(&config(brightness)).unwrap();
+        }
+
+        let mut store = ConfigStore::new(&mut flash);
+        assert_eq!(store.load(), Ok(Some(config(200))));
+        let (min, max) = (flash.erases.iter().min(), flash.erases.iter().max());
+        assert!(max.unwrap() - min.unwrap() <= 1, "{:?}", flash.erases);
+        // Several records per erase
+        assert!(flash.erases.iter().sum::<u32>() < 100, "{:?}", flash.erases);
+    }
+}
@@ -0,0 +1,266 @@
+//! # Pixelblaze Discovery
+//!
+//! Pixelblaze controllers broadcast a beacon on UDP port 1889 about once a
+//! second (used for time sync between controllers and by Firestorm). The
+//! controllers heard from become the endpoints the client tries first, so the
+//! cube finds the lighthouse on any network.
+//!
+//! ## Format
+//! Beacons are 12 bytes, all fields little-endian `u32`:
+//! `[packet_type = 42, sender_id, sender_time]`. Other packet types on the
+//! port (e.g. time sync replies, type 43) are ignored.
+
+use core::net::{IpAddr, SocketAddr};
+
+use heapless::Vec;
+
+use crate::endpoint::{Endpoint, EndpointList, DEFAULT_PORT, MAX_ENDPOINTS};
+
+/// UDP port of the Pixelblaze beacons.
+pub const DISCOVERY_PORT: u16 = 1889;
+
+/// Packet type of a beacon.
+const BEACON_PACKET: u32 = 42;
+
+/// Length of a beacon packet.
+pub const BEACON_LEN: usize = 12;
+
+/// Maximum number of controllers remembered, leaving room for a fallback
+/// endpoint in the [`EndpointList`].
+pub const MAX_CONTROLLERS: usize = MAX_ENDPOINTS - 1;
+
+/// Controllers not heard from for this long are forgotten (ms).
+pub const CONTROLLER_TIMEOUT_MS: u64 = 30_000;
+
+/// Pixelblaze beacon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Beacon {
+    /// Controller ID (chip ID of the Pixelblaze)
+    pub sender_id: u32,
+    /// Controller clock (ms)
+    pub sender_time: u32,
+}
+
+impl Beacon {
+    /// Decode a UDP packet, `None` if it isn't a beacon.
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        let field = |n: usize| {
+            let bytes = packet.get(n * 4..n * 4 + 4)?;
+            Some(u32::from_le_bytes(bytes.try_into().ok()?))
+        };
+        if packet.len() != BEACON_LEN || field(0)? != BEACON_PACKET {
+            return None;
+        }
+        Some(Self {
+            sender_id: field(1)?,
+            sender_time: field(2)?,
+        })
+    }
+
+    pub fn encode(&self) -> [u8; BEACON_LEN] {
+        let mut packet = [0; BEACON_LEN];
+        packet[0..4].copy_from_slice(&BEACON_PACKET.to_le_bytes());
+        packet[4..8].copy_from_slice(&self.sender_id.to_le_bytes());
+        packet[8..12].copy_from_slice(&self.sender_time.to_le_bytes());
+        packet
+    }
+}
+
+/// Pixelblaze controller heard on the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Controller {
+    /// Controller ID from its beacons
+    pub id: u32,
+    /// Address the beacons came from
+    pub ip: IpAddr,
+    /// Time of the last beacon (ms)
+    pub last_seen_ms: u64,
+}
+
+impl Controller {
+    /// WebSocket endpoint of the controller.
+    pub fn endpoint(&self) -> Endpoint {
+        Endpoint {
+            addr: SocketAddr::new(self.ip, DEFAULT_PORT),
+        }
+    }
+}
+
+/// What a beacon told about its controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Discovery {
+    /// Controller not known before
+    New,
+    /// Known controller with a new address
+    Moved,
+    /// Known controller at the same address
+    Seen,
+}
+
+/// Controllers heard recently, in the order they were discovered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Controllers {
+    controllers: Vec<Controller, MAX_CONTROLLERS>,
+}
+
+impl Controllers {
+    /// Record a beacon received from `ip` at `now_ms`. When the table is
+    /// full, the controller heard from the longest time ago is replaced.
+    pub fn record(&mut self, beacon: &Beacon, ip: IpAddr, now_ms: u64) -> Discovery {
+        let heard = Controller {
+            id: beacon.sender_id,
+            ip,
+            last_seen_ms: now_ms,
+        };
+
+        if let Some(controller) = self.controllers.iter_mut().find(|c| c.id == heard.id) {
+            let moved = controller.ip != ip;
+            *controller = heard;
+            return match moved {
+                true => Discovery::Moved,
+                false => Discovery::Seen,
+            };
+        }
+
+        if self.controllers.is_full() {
+            let oldest = self
+                .controllers
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, c)| c.last_seen_ms)
+                .map(|(n, _)| n);
+            if let Some(n) = oldest {
+                self.controllers.remove(n);
+            }
+        }
+        _ = self.controllers.push(heard);
+        Discovery::New
+    }
+
+    /// Forget controllers not heard from within [`CONTROLLER_TIMEOUT_MS`].
+    /// Returns whether any were forgotten.
+    pub fn expire(&mut self, now_ms: u64) -> bool {
+        let before = self.controllers.len();
+        self.controllers
+            .retain(|c| now_ms.saturating_sub(c.last_seen_ms) < CONTROLLER_TIMEOUT_MS);
+        self.controllers.len() != before
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Controller> {
+        self.controllers.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty()
+    }
+
+    /// Endpoints of the controllers followed by the `fallback` endpoints.
+    pub fn endpoints(&self, fallback: &[Endpoint]) -> EndpointList {
+        self.controllers
+            .iter()
+            .map(Controller::endpoint)
+            .chain(fallback.iter().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::*;
+    use crate::endpoint::ACCESS_POINT;
+
+    const LIGHTHOUSE: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 42));
+
+    fn beacon(sender_id: u32) -> Beacon {
+        Beacon {
+            sender_id,
+            sender_time: 81730,
+        }
+    }
+
+    #[test]
+    fn decodes_beacons() {
+        let packet = [42, 0, 0, 0, 0x9C, 0x72, 0x69, 0x00, 0x42, 0x3F, 0x01, 0x00];
+        assert_eq!(
+            Beacon::decode(&packet),
+            Some(Beacon {
+                sender_id: 6910620,
+                sender_time: 81730,
+            })
+        );
+        assert_eq!(Beacon::decode(&beacon(1).encode()), Some(beacon(1)));
+
+        // Time sync packets and truncated beacons are ignored
+        let mut time_sync = packet;
+        time_sync[0] = 43;
+        assert_eq!(Beacon::decode(&time_sync), None);
+        assert_eq!(Beacon::decode(&packet[..8]), None);
+        assert_eq!(Beacon::decode(&[]), None);
+    }
+
+    #[test]
+    fn records_controllers() {
+        let mut controllers = Controllers::default();
+        assert_eq!(
+            controllers.record(&beacon(1), LIGHTHOUSE, 0),
+            Discovery::New
+        );
+        assert_eq!(
+            controllers.record(&beacon(1), LIGHTHOUSE, 1000),
+            Discovery::Seen
+        );
+        let moved = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 43));
+        assert_eq!(
+            controllers.record(&beacon(1), moved, 2000),
+            Discovery::Moved
+        );
+        assert_eq!(
+            controllers.iter().next(),
+            Some(&Controller {
+                id: 1,
+                ip: moved,
+                last_seen_ms: 2000,
+            })
+        );
+
+        // A full table replaces the controller heard from the longest time ago
+        for id in 2..=MAX_CONTROLLERS as u32 + 1 {
+            controllers.record(&beacon(id), LIGHTHOUSE, 2000 + u64::from(id));
+        }
+        let ids: Vec<u32, MAX_CONTROLLERS> = controllers.iter().map(|c| c.id).collect();
+        assert_eq!(ids, [2, 3, 4]);
+    }
+
+    #[test]
+    fn forgets_silent_controllers() {
+        let mut controllers = Controllers::default();
+        controllers.record(&beacon(1), LIGHTHOUSE, 0);
+        controllers.record(&beacon(2), LIGHTHOUSE, 10_000);
+
+        assert!(!controllers.expire(CONTROLLER_TIMEOUT_MS - 1));
+        assert!(controllers.expire(CONTROLLER_TIMEOUT_MS));
+        assert_eq!(controllers.iter().map(|c| c.id).next(), Some(2));
+        assert!(controllers.expire(CONTROLLER_TIMEOUT_MS + 10_000));
+        assert!(controllers.is_empty());
+    }
+
+    #[test]
+    fn tries_discovered_controllers_before_fallback() {
+        let mut controllers = Controllers::default();
+        assert_eq!(
+            controllers.endpoints(&[ACCESS_POINT]).iter().next(),
+            Some(&ACCESS_POINT)
+        );
+
+        controllers.record(&beacon(1), LIGHTHOUSE, 0);
+        // The access point itself sends beacons too
+        controllers.record(&beacon(2), ACCESS_POINT.addr.ip(), 0);
+        let endpoints = controllers.endpoints(&[ACCESS_POINT]);
+        let hosts: Vec<_, MAX_ENDPOINTS> = endpoints.iter().map(|e| e.host()).collect();
+        assert_eq!(hosts, ["10.0.0.42:81", "192.168.4.1:81"]);
+    }
+}
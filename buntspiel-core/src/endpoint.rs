@@ -0,0 +1,226 @@
+//! # Pixelblaze Endpoints
+//!
+//! Addresses the Pixelblaze WebSocket is tried at, e.g. its own access point
+//! (`192.168.4.1:81`) first and the address on the festival network after.
+//!
+//! An endpoint is written as `ip` or `ip:port` (IPv6 as `[ip]:port`), a list
+//! separates endpoints with commas: `192.168.4.1:81, 10.0.0.42`. The HTTP
+//! `Host` and `Origin` headers of the WebSocket upgrade are derived from the
+//! endpoint.
+
+use core::fmt::{self, Write};
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use heapless::{String, Vec};
+
+/// WebSocket port of Pixelblaze, used when an endpoint has none.
+pub const DEFAULT_PORT: u16 = 81;
+
+/// Maximum number of endpoints in an [`EndpointList`].
+pub const MAX_ENDPOINTS: usize = 4;
+
+/// Longest `Host` header: `[` IPv6 `]:` port.
+pub const MAX_HOST_LEN: usize = 47;
+
+/// Longest `Origin` header: `http://[` IPv6 `]`.
+pub const MAX_ORIGIN_LEN: usize = 48;
+
+/// Pixelblaze access point, reachable when the cube joined its network.
+pub const ACCESS_POINT: Endpoint = Endpoint {
+    addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 4, 1)), DEFAULT_PORT),
+};
+
+/// Endpoint parse error types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EndpointError {
+    /// Not an IP address with optional port
+    Invalid,
+    /// More than [`MAX_ENDPOINTS`] endpoints
+    TooMany,
+}
+
+/// Address of a Pixelblaze WebSocket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    pub addr: SocketAddr,
+}
+
+impl Endpoint {
+    pub const fn new(ip: IpAddr, port: u16) -> Self {
+        Self {
+            addr: SocketAddr::new(ip, port),
+        }
+    }
+
+    /// Parse `ip` or `ip:port`, surrounding whitespace is ignored.
+    pub fn parse(s: &str) -> Result<Self, EndpointError> {
+        let s = s.trim();
+        if let Ok(addr) = s.parse::<SocketAddr>() {
+            return Ok(Self { addr });
+        }
+        match s.parse::<IpAddr>() {
+            Ok(ip) => Ok(Self::new(ip, DEFAULT_PORT)),
+            Err(_) => Err(EndpointError::Invalid),
+        }
+    }
+
+    /// `Host` header of the WebSocket upgrade request (`ip:port`).
+    pub fn host(&self) -> String<MAX_HOST_LEN> {
+        let mut host = String::new();
+        // Fits by construction of MAX_HOST_LEN
+        _ = write!(host, "{}", self.addr);
+        host
+    }
+
+    /// `Origin` header of the WebSocket upgrade request, the web UI
+    /// Pixelblaze serves on port 80.
+    pub fn origin(&self) -> String<MAX_ORIGIN_LEN> {
+        let mut origin = String::new();
+        _ = match self.addr.ip() {
+            IpAddr::V4(ip) => write!(origin, "http://{}", ip),
+            IpAddr::V6(ip) => write!(origin, "http://[{}]", ip),
+        };
+        origin
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.addr)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Endpoint {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.host().as_str())
+    }
+}
+
+/// Endpoints tried in order until one accepts the connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointList {
+    endpoints: Vec<Endpoint, MAX_ENDPOINTS>,
+}
+
+impl EndpointList {
+    pub const fn new() -> Self {
+        Self {
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Parse a comma separated list of endpoints, empty entries are skipped.
+    pub fn parse(list: &str) -> Result<Self, EndpointError> {
+        let mut endpoints = Self::new();
+        for entry in list.split(',').filter(|entry| !entry.trim().is_empty()) {
+            endpoints.push(Endpoint::parse(entry)?)?;
+        }
+        Ok(endpoints)
+    }
+
+    /// Append an endpoint, tried after the ones already in the list.
+    /// Duplicates are ignored.
+    pub fn push(&mut self, endpoint: Endpoint) -> Result<(), EndpointError> {
+        if self.endpoints.contains(&endpoint) {
+            return Ok(());
+        }
+        self.endpoints
+            .push(endpoint)
+            .map_err(|_| EndpointError::TooMany)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Endpoint> {
+        self.endpoints.iter()
+    }
+
+    pub fn as_slice(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+}
+
+/// Collect endpoints in order, duplicates and endpoints beyond
+/// [`MAX_ENDPOINTS`] are dropped.
+impl FromIterator<Endpoint> for EndpointList {
+    fn from_iter<I: IntoIterator<Item = Endpoint>>(iter: I) -> Self {
+        let mut endpoints = Self::new();
+        for endpoint in iter {
+            _ = endpoints.push(endpoint);
+        }
+        endpoints
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for EndpointList {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.endpoints.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::*;
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(Endpoint::parse("192.168.4.1:81"), Ok(ACCESS_POINT));
+        assert_eq!(Endpoint::parse(" 192.168.4.1 "), Ok(ACCESS_POINT));
+        assert_eq!(
+            Endpoint::parse("[fe80::1]:8081"),
+            Ok(Endpoint::new(
+                IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+                8081
+            ))
+        );
+        assert_eq!(
+            Endpoint::parse("pixelblaze.local"),
+            Err(EndpointError::Invalid)
+        );
+        assert_eq!(Endpoint::parse("10.0.0.1:x"), Err(EndpointError::Invalid));
+    }
+
+    #[test]
+    fn derives_headers() {
+        assert_eq!(ACCESS_POINT.host().as_str(), "192.168.4.1:81");
+        assert_eq!(ACCESS_POINT.origin().as_str(), "http://192.168.4.1");
+
+        let longest = Endpoint::parse("[ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff]:65535").unwrap();
+        assert_eq!(
+            longest.host().as_str(),
+            "[ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff]:65535"
+        );
+        assert_eq!(
+            longest.origin().as_str(),
+            "http://[ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff]"
+        );
+    }
+
+    #[test]
+    fn parses_fallback_list_in_order() {
+        let list = EndpointList::parse("192.168.4.1:81, 10.0.0.42,,192.168.4.1").unwrap();
+        let hosts: Vec<_, MAX_ENDPOINTS> = list.iter().map(|e| e.host()).collect();
+        assert_eq!(hosts, ["192.168.4.1:81", "10.0.0.42:81"]);
+
+        assert_eq!(EndpointList::parse(""), Ok(EndpointList::new()));
+        assert_eq!(
+            EndpointList::parse("10.0.0.1,nope"),
+            Err(EndpointError::Invalid)
+        );
+        assert_eq!(
+            EndpointList::parse("10.0.0.1,10.0.0.2,10.0.0.3,10.0.0.4,10.0.0.5"),
+            Err(EndpointError::TooMany)
+        );
+    }
+}
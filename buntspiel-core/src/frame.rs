@@ -0,0 +1,173 @@
+//! # Matrix Frames
+//!
+//! RGB pixels and frames of the whole (tiled) NeoTrellis matrix, plus the
+//! ASCII-art patterns used by the fallback animations.
+
+use crate::layout::{MATRIX_COLUMNS, NEOTRELLIS_PIXELS, TILE_PIXELS, TILE_SIZE};
+
+/// RGB color value for a single LED pixel (0-255).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rgb {
+    /// Red component (0-255)
+    pub r: u8,
+    /// Green component (0-255)
+    pub g: u8,
+    /// Blue component (0-255)
+    pub b: u8,
+}
+
+/// Complete frame of RGB data for the entire matrix (row-major order).
+pub type Frame = [Rgb; NEOTRELLIS_PIXELS];
+
+/// ASCII-art 4x4 LED pattern. 'w'/'x' = white, 'r' = red, 'g' = green, 'b' = blue, others = off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RGBPattern {
+    /// First row (top) of the 4x4 matrix
+    pub r1: &'static str,
+    /// Second row of the 4x4 matrix
+    pub r2: &'static str,
+    /// Third row of the 4x4 matrix
+    pub r3: &'static str,
+    /// Fourth row (bottom) of the 4x4 matrix
+    pub r4: &'static str,
+}
+
+/// Convert ASCII pattern to linear RGB array.
+impl From<RGBPattern> for Frame {
+    fn from(val: RGBPattern) -> Self {
+        // Character-to-RGB color mapping function
+        let to_rgb = |p: char| {
+            match p {
+                'w' | 'x' => Rgb {
+                    r: 0xFF,
+                    g: 0xFF,
+                    b: 0xFF,
+                }, // White/bright
+                'r' => Rgb {
+                    r: 0xFF,
+                    g: 0,
+                    b: 0,
+                }, // Red
+                'g' => Rgb {
+                    r: 0,
+                    g: 0xFF,
+                    b: 0,
+                }, // Green
+                'b' => Rgb {
+                    r: 0,
+                    g: 0,
+                    b: 0xFF,
+                }, // Blue
+                _ => Rgb { r: 0, g: 0, b: 0 }, // Off/black for any other character
+            }
+        };
+
+        let mut tile = [Rgb::default(); TILE_PIXELS];
+
+        // Convert 4x4 ASCII pattern to linear RGB array
+        // Chain all rows together and convert each character to RGB
+        for (i, pixel) in val
+            .r1
+            .chars()
+            .take(TILE_SIZE) // Ensure exactly 4 characters per row
+            .map(to_rgb)
+            .chain(val.r2.chars().take(TILE_SIZE).map(to_rgb))
+            .chain(val.r3.chars().take(TILE_SIZE).map(to_rgb))
+            .chain(val.r4.chars().take(TILE_SIZE).map(to_rgb))
+            .enumerate()
+        {
+            tile[i] = pixel
+        }
+
+        // Repeat the pattern on every board of a tiled matrix
+        let mut frame = [Rgb::default(); NEOTRELLIS_PIXELS];
+        for (i, pixel) in frame.iter_mut().enumerate() {
+            let (x, y) = (i % MATRIX_COLUMNS, i / MATRIX_COLUMNS);
+            *pixel = tile[(y % TILE_SIZE) * TILE_SIZE + x % TILE_SIZE];
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_ascii_pattern() {
+        let frame: Frame = RGBPattern {
+            r1: "xr..",
+            r2: ".g..",
+            r3: "..b.",
+            r4: "w..?",
+        }
+        .into();
+
+        let at = |x: usize, y: usize| frame[y * MATRIX_COLUMNS + x];
+        let white = Rgb {
+            r: 0xFF,
+            g: 0xFF,
+            b: 0xFF,
+        };
+        assert_eq!(at(0, 0), white);
+        assert_eq!(
+            at(1, 0),
+            Rgb {
+                r: 0xFF,
+                g: 0,
+                b: 0
+            }
+        );
+        assert_eq!(
+            at(1, 1),
+            Rgb {
+                r: 0,
+                g: 0xFF,
+                b: 0
+            }
+        );
+        assert_eq!(
+            at(2, 2),
+            Rgb {
+                r: 0,
+                g: 0,
+                b: 0xFF
+            }
+        );
+        assert_eq!(at(0, 3), white);
+        // Unknown characters are off
+        assert_eq!(at(3, 3), Rgb::default());
+    }
+
+    #[test]
+    fn ignores_extra_and_missing_characters() {
+        let frame: Frame = RGBPattern {
+            r1: "xxxxx",
+            r2: "",
+            r3: "",
+            r4: "",
+        }
+        .into();
+
+        assert!(frame[..TILE_SIZE].iter().all(|p| p.r == 0xFF));
+        assert_eq!(frame[MATRIX_COLUMNS], Rgb::default());
+    }
+
+    #[test]
+    fn repeats_pattern_on_every_tile() {
+        let frame: Frame = RGBPattern {
+            r1: "r...",
+            r2: "....",
+            r3: "....",
+            r4: "....",
+        }
+        .into();
+
+        for (i, pixel) in frame.iter().enumerate() {
+            let (x, y) = (i % MATRIX_COLUMNS, i / MATRIX_COLUMNS);
+            let red = x % TILE_SIZE == 0 && y % TILE_SIZE == 0;
+            assert_eq!(pixel.r == 0xFF, red, "pixel ({}, {})", x, y);
+        }
+    }
+}
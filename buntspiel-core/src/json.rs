@@ -0,0 +1,503 @@
+//! # Minimal JSON Reader
+//!
+//! Allocation-free JSON scanner for the small text messages Pixelblaze sends
+//! over the WebSocket. Values borrow from the input buffer; objects and arrays
+//! are only walked when asked for, so unknown or deeply nested fields are
+//! skipped without being decoded.
+//!
+//! [`write_str`] covers the other direction for the few commands we send.
+
+use core::fmt::{self, Write};
+use core::str::FromStr;
+use heapless::String;
+
+/// JSON decoding errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum JsonError {
+    /// Input ended in the middle of a value
+    UnexpectedEnd,
+    /// Unexpected character at the given byte offset
+    UnexpectedChar(usize),
+    /// Number literal could not be parsed
+    InvalidNumber,
+    /// Invalid escape sequence inside a string
+    InvalidEscape,
+    /// Decoded string does not fit into the target buffer
+    Overflow,
+}
+
+/// A decoded JSON value borrowing from the input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Bool(bool),
+    Number(f32),
+    Str(JsonStr<'a>),
+    Object(Object<'a>),
+    Array(Array<'a>),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<JsonStr<'a>> {
+        match self {
+            Value::Str(s) => Some(*s),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<Object<'a>> {
+        match self {
+            Value::Object(o) => Some(*o),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<Array<'a>> {
+        match self {
+            Value::Array(a) => Some(*a),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a complete JSON document (trailing whitespace allowed).
+pub fn parse(src: &str) -> Result<Value<'_>, JsonError> {
+    let mut cursor = Cursor { src, pos: 0 };
+    let value = cursor.parse_value()?;
+    cursor.skip_ws();
+    match cursor.peek() {
+        None => Ok(value),
+        Some(_) => Err(JsonError::UnexpectedChar(cursor.pos)),
+    }
+}
+
+/// Write `s` as a quoted JSON string, escaping as needed.
+pub fn write_str(out: &mut impl Write, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// JSON string as it appears in the input (without quotes, escapes intact).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonStr<'a> {
+    raw: &'a str,
+}
+
+impl<'a> JsonStr<'a> {
+    /// Raw string contents. Equal to the decoded value if it has no escapes.
+    pub fn raw(self) -> &'a str {
+        self.raw
+    }
+
+    /// Iterate over the decoded characters.
+    pub fn chars(self) -> Unescape<'a> {
+        Unescape {
+            chars: self.raw.chars(),
+        }
+    }
+
+    /// Compare the decoded value against `other`.
+    pub fn eq_str(self, other: &str) -> bool {
+        let mut expected = other.chars();
+        for c in self.chars() {
+            match (c, expected.next()) {
+                (Ok(c), Some(e)) if c == e => {}
+                _ => return false,
+            }
+        }
+        expected.next().is_none()
+    }
+
+    /// Decode into a fixed-capacity string, failing if it doesn't fit.
+    pub fn to_string<const N: usize>(self) -> Result<String<N>, JsonError> {
+        let mut out = String::new();
+        for c in self.chars() {
+            out.push(c?).map_err(|_| JsonError::Overflow)?;
+        }
+        Ok(out)
+    }
+
+    /// Decode into a fixed-capacity string, cutting off whatever doesn't fit.
+    pub fn to_string_truncated<const N: usize>(self) -> Result<String<N>, JsonError> {
+        let mut out = String::new();
+        for c in self.chars() {
+            if out.push(c?).is_err() {
+                break;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Iterator decoding JSON string escapes.
+pub struct Unescape<'a> {
+    chars: core::str::Chars<'a>,
+}
+
+impl Unescape<'_> {
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or(JsonError::InvalidEscape)?;
+            value = (value << 4) | digit;
+        }
+        Ok(value)
+    }
+
+    fn escape(&mut self) -> Result<char, JsonError> {
+        Ok(match self.chars.next().ok_or(JsonError::InvalidEscape)? {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{08}',
+            'f' => '\u{0C}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = self.hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    // Surrogate pair: expect a second \uXXXX for the low half
+                    if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                        return Err(JsonError::InvalidEscape);
+                    }
+                    let low = self.hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(JsonError::InvalidEscape);
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or(JsonError::InvalidEscape)?
+            }
+            _ => return Err(JsonError::InvalidEscape),
+        })
+    }
+}
+
+impl Iterator for Unescape<'_> {
+    type Item = Result<char, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.chars.next()? {
+            '\\' => Some(self.escape()),
+            c => Some(Ok(c)),
+        }
+    }
+}
+
+/// JSON object, decoded lazily member by member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Object<'a> {
+    /// Source including the surrounding braces
+    src: &'a str,
+}
+
+impl<'a> Object<'a> {
+    /// Iterate over `(key, value)` members in document order.
+    pub fn members(&self) -> Members<'a> {
+        Members {
+            cursor: Cursor {
+                src: self.src,
+                pos: 1,
+            },
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Look up a member by key (first match wins).
+    pub fn get(&self, key: &str) -> Option<Value<'a>> {
+        self.members()
+            .map_while(Result::ok)
+            .find(|(k, _)| k.eq_str(key))
+            .map(|(_, v)| v)
+    }
+}
+
+/// Iterator over the members of an [`Object`].
+pub struct Members<'a> {
+    cursor: Cursor<'a>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Members<'a> {
+    fn next_member(&mut self) -> Result<Option<(JsonStr<'a>, Value<'a>)>, JsonError> {
+        let c = &mut self.cursor;
+        c.skip_ws();
+        if c.peek() == Some(b'}') {
+            return Ok(None);
+        }
+        if !self.first {
+            c.expect(b',')?;
+            c.skip_ws();
+        }
+        self.first = false;
+
+        let key = c.parse_string()?;
+        c.skip_ws();
+        c.expect(b':')?;
+        let value = c.parse_value()?;
+        Ok(Some((key, value)))
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = Result<(JsonStr<'a>, Value<'a>), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let member = self.next_member().transpose();
+        self.done = !matches!(member, Some(Ok(_)));
+        member
+    }
+}
+
+/// JSON array, decoded lazily element by element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Array<'a> {
+    /// Source including the surrounding brackets
+    src: &'a str,
+}
+
+impl<'a> Array<'a> {
+    /// Iterate over the elements in document order.
+    pub fn elements(&self) -> Elements<'a> {
+        Elements {
+            cursor: Cursor {
+                src: self.src,
+                pos: 1,
+            },
+            first: true,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the elements of an [`Array`].
+pub struct Elements<'a> {
+    cursor: Cursor<'a>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Elements<'a> {
+    fn next_element(&mut self) -> Result<Option<Value<'a>>, JsonError> {
+        let c = &mut self.cursor;
+        c.skip_ws();
+        if c.peek() == Some(b']') {
+            return Ok(None);
+        }
+        if !self.first {
+            c.expect(b',')?;
+        }
+        self.first = false;
+        c.parse_value().map(Some)
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Result<Value<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let element = self.next_element().transpose();
+        self.done = !matches!(element, Some(Ok(_)));
+        element
+    }
+}
+
+/// Byte cursor over the input.
+struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(b) if b == byte => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(JsonError::UnexpectedChar(self.pos)),
+            None => Err(JsonError::UnexpectedEnd),
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> Result<(), JsonError> {
+        if self.src[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else if literal.starts_with(&self.src[self.pos..]) {
+            Err(JsonError::UnexpectedEnd)
+        } else {
+            Err(JsonError::UnexpectedChar(self.pos))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value<'a>, JsonError> {
+        self.skip_ws();
+        match self.peek().ok_or(JsonError::UnexpectedEnd)? {
+            b'{' => Ok(Value::Object(Object {
+                src: self.skip_container(b'{', b'}')?,
+            })),
+            b'[' => Ok(Value::Array(Array {
+                src: self.skip_container(b'[', b']')?,
+            })),
+            b'"' => self.parse_string().map(Value::Str),
+            b't' => self.expect_literal("true").map(|_| Value::Bool(true)),
+            b'f' => self.expect_literal("false").map(|_| Value::Bool(false)),
+            b'n' => self.expect_literal("null").map(|_| Value::Null),
+            b'-' | b'0'..=b'9' => self.parse_number().map(Value::Number),
+            _ => Err(JsonError::UnexpectedChar(self.pos)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<JsonStr<'a>, JsonError> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        let bytes = self.src.as_bytes();
+        loop {
+            match bytes.get(self.pos).ok_or(JsonError::UnexpectedEnd)? {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        let raw = self
+            .src
+            .get(start..self.pos)
+            .ok_or(JsonError::UnexpectedEnd)?;
+        self.pos += 1; // Closing quote
+        Ok(JsonStr { raw })
+    }
+
+    fn parse_number(&mut self) -> Result<f32, JsonError> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        f32::from_str(&self.src[start..self.pos]).map_err(|_| JsonError::InvalidNumber)
+    }
+
+    /// Skip a balanced object or array, returning its source slice.
+    fn skip_container(&mut self, open: u8, close: u8) -> Result<&'a str, JsonError> {
+        let start = self.pos;
+        let mut depth = 0_usize;
+        loop {
+            match self.peek().ok_or(JsonError::UnexpectedEnd)? {
+                b'"' => {
+                    self.parse_string()?;
+                    continue;
+                }
+                b if b == open => depth += 1,
+                b if b == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return Ok(&self.src[start..self.pos]);
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_objects_lazily() {
+        let value = parse(r#" {"a": {"b": [1, 2, {"c": "}"}]}, "d": -1.5e1} "#).unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(object.get("d").and_then(|v| v.as_f32()), Some(-15.0));
+
+        let a = object.get("a").and_then(|v| v.as_object()).unwrap();
+        let b = a.get("b").and_then(|v| v.as_array()).unwrap();
+        assert_eq!(b.elements().count(), 3);
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        let value = parse(r#""Tab\there \"quoted\" ü 😀""#).unwrap();
+        let s = value.as_str().unwrap();
+        assert!(s.eq_str("Tab\there \"quoted\" ü 😀"));
+        assert_eq!(s.to_string::<4>(), Err(JsonError::Overflow));
+        assert_eq!(s.to_string_truncated::<4>().unwrap().as_str(), "Tab\t");
+    }
+
+    #[test]
+    fn escapes_written_strings() {
+        let mut out: String<64> = String::new();
+        write_str(&mut out, "say \"hi\"\\\n\u{1}").unwrap();
+        assert_eq!(out.as_str(), r#""say \"hi\"\\\n\u0001""#);
+
+        let value = parse(&out).unwrap();
+        assert!(value.as_str().unwrap().eq_str("say \"hi\"\\\n\u{1}"));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(parse(r#"{"a":1"#), Err(JsonError::UnexpectedEnd));
+        assert_eq!(parse("tru"), Err(JsonError::UnexpectedEnd));
+        assert_eq!(parse("1 2"), Err(JsonError::UnexpectedChar(2)));
+
+        let object = parse(r#"{"a":1,}"#).unwrap().as_object().unwrap();
+        let members: heapless::Vec<_, 4> = object.members().collect();
+        assert!(members[0].is_ok());
+        assert_eq!(members[1], Err(JsonError::UnexpectedChar(7)));
+    }
+}
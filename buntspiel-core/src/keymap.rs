@@ -0,0 +1,237 @@
+//! # VJ Key Mapping
+//!
+//! Assigns an action to every key of the NeoTrellis matrix. Keys are
+//! numbered row-major over the whole matrix, top-left first.
+//!
+//! Holding [`SHIFT_KEY`] while pressing another key triggers that key's
+//! entry in [`SHIFTED_KEYMAP`] instead, so a single 4x4 board reaches every
+//! action. [`Keypad`] turns button events into actions accordingly.
+
+/// Button event reported by the NeoTrellis keypad (key index in row-major order).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ButtonEvent {
+    /// Key went down
+    Pressed(u8),
+    /// Key went up
+    Released(u8),
+}
+
+/// Pixelblaze action triggered by a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyAction {
+    /// Key does nothing
+    None,
+    /// Switch to the N-th pattern of the Pixelblaze program list
+    SelectPattern(u8),
+    /// Switch to the pattern after the active one
+    NextPattern,
+    /// Switch to the pattern before the active one
+    PreviousPattern,
+    /// Raise the Pixelblaze brightness by one step
+    BrightnessUp,
+    /// Lower the Pixelblaze brightness by one step
+    BrightnessDown,
+    /// Switch to the next preview mapping strategy (handled locally)
+    NextPreviewMapping,
+    /// Raise the brightness of the matrix itself by one step
+    BrightenMatrix,
+    /// Lower the brightness of the matrix itself by one step
+    DimMatrix,
+}
+
+/// Key mapping (row-major over the whole matrix, top-left first).
+///
+/// On a single board the top three rows select the first twelve stored
+/// patterns and the bottom row holds previous/next and brightness controls.
+/// Tiled layouts additionally get preview mapping and matrix brightness keys
+/// right after those, on a single board they are shifted actions. Keys
+/// beyond the map do nothing.
+pub const KEYMAP: [KeyAction; 19] = [
+    KeyAction::SelectPattern(0),
+    KeyAction::SelectPattern(1),
+    KeyAction::SelectPattern(2),
+    KeyAction::SelectPattern(3),
+    KeyAction::SelectPattern(4),
+    KeyAction::SelectPattern(5),
+    KeyAction::SelectPattern(6),
+    KeyAction::SelectPattern(7),
+    KeyAction::SelectPattern(8),
+    KeyAction::SelectPattern(9),
+    KeyAction::SelectPattern(10),
+    KeyAction::SelectPattern(11),
+    KeyAction::PreviousPattern,
+    KeyAction::NextPattern,
+    KeyAction::BrightnessDown,
+    KeyAction::BrightnessUp,
+    KeyAction::NextPreviewMapping,
+    KeyAction::DimMatrix,
+    KeyAction::BrightenMatrix,
+];
+
+/// Key held as modifier for [`SHIFTED_KEYMAP`] (previous pattern, bottom left
+/// of the first board). Its own action triggers on release, if no other key
+/// was pressed meanwhile.
+pub const SHIFT_KEY: u8 = 12;
+
+/// Actions of keys pressed while [`SHIFT_KEY`] is held, `None` for keys
+/// keeping their usual action.
+pub const SHIFTED_KEYMAP: [Option<KeyAction>; 16] = [
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(KeyAction::NextPreviewMapping),
+    Some(KeyAction::DimMatrix),
+    Some(KeyAction::BrightenMatrix),
+];
+
+impl KeyAction {
+    /// Action of the given key.
+    pub fn of_key(key: u8) -> Self {
+        KEYMAP
+            .get(usize::from(key))
+            .copied()
+            .unwrap_or(KeyAction::None)
+    }
+
+    /// Action of the given key while [`SHIFT_KEY`] is held.
+    pub fn of_shifted_key(key: u8) -> Self {
+        SHIFTED_KEYMAP
+            .get(usize::from(key))
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| Self::of_key(key))
+    }
+}
+
+/// Keypad state turning button events into actions, see [`SHIFT_KEY`].
+#[derive(Debug, Default)]
+pub struct Keypad {
+    /// Whether the shift key is held down
+    shift_held: bool,
+    /// Whether another key was pressed while the shift key was held
+    shift_used: bool,
+}
+
+impl Keypad {
+    /// Action triggered by a button event, [`KeyAction::None`] for most
+    /// releases.
+    pub fn event(&mut self, event: ButtonEvent) -> KeyAction {
+        match event {
+            ButtonEvent::Pressed(SHIFT_KEY) => {
+                self.shift_held = true;
+                self.shift_used = false;
+                KeyAction::None
+            }
+            ButtonEvent::Released(SHIFT_KEY) => {
+                let tapped = self.shift_held && !self.shift_used;
+                self.shift_held = false;
+                if tapped {
+                    KeyAction::of_key(SHIFT_KEY)
+                } else {
+                    KeyAction::None
+                }
+            }
+            ButtonEvent::Pressed(key) if self.shift_held => {
+                self.shift_used = true;
+                KeyAction::of_shifted_key(key)
+            }
+            ButtonEvent::Pressed(key) => KeyAction::of_key(key),
+            ButtonEvent::Released(_) => KeyAction::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::NEOTRELLIS_PIXELS;
+
+    /// Index of an action's variant, fails to compile for new variants.
+    fn variant(action: KeyAction) -> usize {
+        match action {
+            KeyAction::None => 0,
+            KeyAction::SelectPattern(_) => 1,
+            KeyAction::NextPattern => 2,
+            KeyAction::PreviousPattern => 3,
+            KeyAction::BrightnessUp => 4,
+            KeyAction::BrightnessDown => 5,
+            KeyAction::NextPreviewMapping => 6,
+            KeyAction::BrightenMatrix => 7,
+            KeyAction::DimMatrix => 8,
+        }
+    }
+
+    #[test]
+    fn maps_keys_to_actions() {
+        assert_eq!(KeyAction::of_key(0), KeyAction::SelectPattern(0));
+        assert_eq!(KeyAction::of_key(11), KeyAction::SelectPattern(11));
+        assert_eq!(KeyAction::of_key(13), KeyAction::NextPattern);
+        assert_eq!(KeyAction::of_key(200), KeyAction::None);
+        assert_eq!(KeyAction::of_shifted_key(13), KeyAction::NextPreviewMapping);
+        assert_eq!(KeyAction::of_shifted_key(0), KeyAction::SelectPattern(0));
+        assert_eq!(KeyAction::of_shifted_key(200), KeyAction::None);
+    }
+
+    #[test]
+    fn reaches_every_action_on_the_matrix() {
+        let mut reached = [false; 9];
+        for key in 0..NEOTRELLIS_PIXELS as u8 {
+            // Tap the key, then tap it while holding the shift key
+            let mut keypad = Keypad::default();
+            let events = [
+                ButtonEvent::Pressed(key),
+                ButtonEvent::Released(key),
+                ButtonEvent::Pressed(SHIFT_KEY),
+                ButtonEvent::Pressed(key),
+                ButtonEvent::Released(key),
+                ButtonEvent::Released(SHIFT_KEY),
+            ];
+            for event in events {
+                reached[variant(keypad.event(event))] = true;
+            }
+        }
+        assert_eq!(reached, [true; 9]);
+    }
+
+    #[test]
+    fn shift_key_acts_on_release_unless_used() {
+        let mut keypad = Keypad::default();
+        assert_eq!(
+            keypad.event(ButtonEvent::Pressed(SHIFT_KEY)),
+            KeyAction::None
+        );
+        assert_eq!(
+            keypad.event(ButtonEvent::Released(SHIFT_KEY)),
+            KeyAction::PreviousPattern
+        );
+
+        keypad.event(ButtonEvent::Pressed(SHIFT_KEY));
+        assert_eq!(
+            keypad.event(ButtonEvent::Pressed(13)),
+            KeyAction::NextPreviewMapping
+        );
+        assert_eq!(keypad.event(ButtonEvent::Released(13)), KeyAction::None);
+        assert_eq!(
+            keypad.event(ButtonEvent::Released(SHIFT_KEY)),
+            KeyAction::None
+        );
+
+        // Shift released, back to the usual actions
+        assert_eq!(
+            keypad.event(ButtonEvent::Pressed(13)),
+            KeyAction::NextPattern
+        );
+    }
+}
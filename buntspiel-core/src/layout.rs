@@ -0,0 +1,246 @@
+//! # NeoTrellis Tile Layout
+//!
+//! Arrangement of NeoTrellis 4x4 boards into a single logical LED matrix
+//! with unified pixel and button coordinates.
+//!
+//! ## Layouts
+//! The tile layout is selected at build time (columns x rows):
+//! - default: a single 4x4 board at 0x2E
+//! - `tiles-4x8`: two boards stacked vertically
+//! - `tiles-8x8`: 2x2 boards
+//! - `tiles-16x8`: 4x2 boards
+//!
+//! Boards are addressed via their A0-A4 jumpers (0x2E + offset). Pixels and
+//! keys are numbered row-major over the whole matrix, top-left first.
+
+use core::ops::Range;
+
+use crate::frame::Rgb;
+
+/// Width and height of a single NeoTrellis board
+pub const TILE_SIZE: usize = 4;
+
+/// Maximum number of boards on the bus
+pub const MAX_TILES: usize = 8;
+
+/// Number of pixels on a single board
+pub const TILE_PIXELS: usize = TILE_SIZE * TILE_SIZE;
+
+/// Most pixels written per buffer write (the seesaw accepts 32 bytes)
+pub const MAX_RUN: usize = 8;
+
+/// Orientation of a board within the matrix (clockwise).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rotation {
+    /// Upright
+    R0,
+    /// Turned a quarter clockwise
+    R90,
+    /// Upside down
+    R180,
+    /// Turned three quarters clockwise
+    R270,
+}
+
+/// Placement of a single NeoTrellis board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Tile {
+    /// Seesaw I2C address of the board
+    pub addr: u8,
+    /// Column of the board in the matrix (in tiles)
+    pub x: u8,
+    /// Row of the board in the matrix (in tiles)
+    pub y: u8,
+    /// Orientation of the board
+    pub rotation: Rotation,
+}
+
+impl Tile {
+    /// Board at tile column `x` and row `y` of the matrix.
+    pub const fn new(addr: u8, x: u8, y: u8, rotation: Rotation) -> Self {
+        Self {
+            addr,
+            x,
+            y,
+            rotation,
+        }
+    }
+
+    /// Logical matrix index of the board-local pixel/key at (`x`, `y`).
+    pub fn to_logical(&self, x: u8, y: u8) -> usize {
+        let (x, y) = (usize::from(x), usize::from(y));
+        let last = TILE_SIZE - 1;
+        let (rx, ry) = match self.rotation {
+            Rotation::R0 => (x, y),
+            Rotation::R90 => (last - y, x),
+            Rotation::R180 => (last - x, last - y),
+            Rotation::R270 => (y, last - x),
+        };
+        let column = usize::from(self.x) * TILE_SIZE + rx;
+        let row = usize::from(self.y) * TILE_SIZE + ry;
+        row * MATRIX_COLUMNS + column
+    }
+}
+
+#[cfg(not(any(feature = "tiles-4x8", feature = "tiles-8x8", feature = "tiles-16x8")))]
+mod tiles {
+    use super::{Rotation::*, Tile};
+
+    pub const MATRIX_COLUMNS: usize = 4;
+    pub const MATRIX_ROWS: usize = 4;
+    pub const TILES: &[Tile] = &[Tile::new(0x2E, 0, 0, R0)];
+}
+
+#[cfg(feature = "tiles-4x8")]
+mod tiles {
+    use super::{Rotation::*, Tile};
+
+    pub const MATRIX_COLUMNS: usize = 4;
+    pub const MATRIX_ROWS: usize = 8;
+    pub const TILES: &[Tile] = &[Tile::new(0x2E, 0, 0, R0), Tile::new(0x2F, 0, 1, R0)];
+}
+
+#[cfg(feature = "tiles-8x8")]
+mod tiles {
+    use super::{Rotation::*, Tile};
+
+    pub const MATRIX_COLUMNS: usize = 8;
+    pub const MATRIX_ROWS: usize = 8;
+    pub const TILES: &[Tile] = &[
+        Tile::new(0x2E, 0, 0, R0),
+        Tile::new(0x2F, 1, 0, R0),
+        Tile::new(0x30, 0, 1, R0),
+        Tile::new(0x31, 1, 1, R0),
+    ];
+}
+
+#[cfg(feature = "tiles-16x8")]
+mod tiles {
+    use super::{Rotation::*, Tile};
+
+    pub const MATRIX_COLUMNS: usize = 16;
+    pub const MATRIX_ROWS: usize = 8;
+    pub const TILES: &[Tile] = &[
+        Tile::new(0x2E, 0, 0, R0),
+        Tile::new(0x2F, 1, 0, R0),
+        Tile::new(0x30, 2, 0, R0),
+        Tile::new(0x31, 3, 0, R0),
+        Tile::new(0x32, 0, 1, R0),
+        Tile::new(0x33, 1, 1, R0),
+        Tile::new(0x34, 2, 1, R0),
+        Tile::new(0x35, 3, 1, R0),
+    ];
+}
+
+#[cfg(any(
+    all(feature = "tiles-4x8", feature = "tiles-8x8"),
+    all(feature = "tiles-4x8", feature = "tiles-16x8"),
+    all(feature = "tiles-8x8", feature = "tiles-16x8"),
+))]
+compile_error!("Only one `tiles-*` layout feature can be enabled");
+
+pub use tiles::{MATRIX_COLUMNS, MATRIX_ROWS, TILES};
+
+/// Number of RGB LEDs (and keys) in the whole matrix
+pub const NEOTRELLIS_PIXELS: usize = MATRIX_COLUMNS * MATRIX_ROWS;
+
+/// Ranges of pixels that differ between `shown` and `next`, split into runs
+/// of at most [`MAX_RUN`] pixels. All pixels differ if nothing is shown yet.
+pub fn changed_runs<'a>(
+    shown: Option<&'a [Rgb; TILE_PIXELS]>,
+    next: &'a [Rgb; TILE_PIXELS],
+) -> impl Iterator<Item = Range<usize>> + 'a {
+    let changed = move |n: usize| !matches!(shown, Some(shown) if shown[n] == next[n]);
+    let mut n = 0;
+    core::iter::from_fn(move || {
+        while n < TILE_PIXELS && !changed(n) {
+            n += 1;
+        }
+        let start = n;
+        while n < TILE_PIXELS && n - start < MAX_RUN && changed(n) {
+            n += 1;
+        }
+        (start < n).then_some(start..n)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_rotated_tiles() {
+        // Corners of a board at the top left of the matrix
+        let corners = [(0, 0), (3, 0), (3, 3), (0, 3)];
+        let logical = |rotation| {
+            let tile = Tile::new(0x2E, 0, 0, rotation);
+            corners.map(|(x, y)| tile.to_logical(x, y))
+        };
+        let (c, l) = (MATRIX_COLUMNS, TILE_SIZE - 1);
+        let top_left = 0;
+        let top_right = l;
+        let bottom_right = l * c + l;
+        let bottom_left = l * c;
+
+        assert_eq!(
+            logical(Rotation::R0),
+            [top_left, top_right, bottom_right, bottom_left]
+        );
+        assert_eq!(
+            logical(Rotation::R90),
+            [top_right, bottom_right, bottom_left, top_left]
+        );
+        assert_eq!(
+            logical(Rotation::R180),
+            [bottom_right, bottom_left, top_left, top_right]
+        );
+        assert_eq!(
+            logical(Rotation::R270),
+            [bottom_left, top_left, top_right, bottom_right]
+        );
+    }
+
+    #[test]
+    fn finds_changed_runs() {
+        let red = Rgb {
+            r: 0xFF,
+            g: 0,
+            b: 0,
+        };
+        let shown = [Rgb::default(); TILE_PIXELS];
+        let mut next = shown;
+        for n in [1, 2, 3, 7, 9, 10, 11, 12, 13, 14, 15] {
+            next[n] = red;
+        }
+
+        let mut runs = changed_runs(Some(&shown), &next);
+        assert_eq!(runs.next(), Some(1..4));
+        assert_eq!(runs.next(), Some(7..8));
+        assert_eq!(runs.next(), Some(9..16));
+        assert_eq!(runs.next(), None);
+
+        // Identical frames need no writes
+        assert_eq!(changed_runs(Some(&next), &next).next(), None);
+
+        // Unknown buffers are written completely, in runs of MAX_RUN
+        let mut runs = changed_runs(None, &shown);
+        assert_eq!(runs.next(), Some(0..MAX_RUN));
+        assert_eq!(runs.next(), Some(MAX_RUN..2 * MAX_RUN));
+        assert_eq!(runs.next(), None);
+    }
+
+    #[test]
+    fn layout_covers_matrix_exactly_once() {
+        let mut covered = [false; MATRIX_COLUMNS * MATRIX_ROWS];
+        for tile in TILES {
+            for n in 0..TILE_PIXELS as u8 {
+                let index = tile.to_logical(n % TILE_SIZE as u8, n / TILE_SIZE as u8);
+                assert!(!covered[index], "pixel {} mapped twice", index);
+                covered[index] = true;
+            }
+        }
+        assert!(covered.iter().all(|c| *c));
+    }
+}
@@ -0,0 +1,36 @@
+//! # Buntspiel Core
+//!
+//! Hardware independent parts of the Buntspiel companion cube firmware:
+//! Pixelblaze protocol decoding, the Pixelblaze state model, client session
+//! and endpoints, preview mapping, color correction and the NeoTrellis tile
+//! layout.
+//!
+//! The crate is `no_std` and builds for both the Pico W
+//! (`thumbv6m-none-eabi`) and the development machine, where the test suite
+//! runs with a plain `cargo test` in this directory.
+//!
+//! ## Features
+//! - `defmt`: derive `defmt::Format` for logging on the device
+//! - `tiles-4x8`, `tiles-8x8`, `tiles-16x8`: NeoTrellis tile layout (see
+//!   [`layout`])
+
+#![no_std]
+
+pub mod animation; // Fallback animation frames
+pub mod backoff; // Exponential reconnect backoff with jitter
+pub mod color; // Gamma, brightness, white balance and current limit
+pub mod config; // Persistent configuration log in flash
+pub mod discovery; // Pixelblaze UDP beacons and the controllers heard
+pub mod endpoint; // Pixelblaze addresses and fallback lists
+pub mod frame; // RGB pixels, matrix frames and ASCII-art patterns
+pub mod json; // Minimal allocation-free JSON reader
+pub mod keymap; // NeoTrellis keys to VJ actions
+pub mod layout; // Tiled NeoTrellis boards as one logical matrix
+pub mod networks; // Known WiFi networks and scan-based selection
+pub mod pixel_map; // Projection of the lighthouse pixel map onto the matrix
+pub mod pixelblaze_state; // Typed Pixelblaze configuration decoded from JSON messages
+pub mod preview; // Mapping of Pixelblaze preview pixels onto the matrix
+pub mod program_list; // Decoder for the Pixelblaze pattern list
+pub mod protocol; // Pixelblaze binary message types and command encoding
+pub mod provisioning; // Setup form of the provisioning access point
+pub mod session; // Pixelblaze client logic shared by firmware and simulator
@@ -0,0 +1,184 @@
+//! # Known WiFi Networks
+//!
+//! WiFi networks the cube may join (festival WiFi, the lighthouse's
+//! Pixelblaze access point, home) and the order to try them in after a scan:
+//! 1. Known networks seen in the scan, strongest signal first. Networks of
+//!    equal signal are tried by priority (highest first).
+//! 2. Known networks not seen (hidden or out of range), by priority.
+
+use core::cmp::Reverse;
+
+use heapless::{String, Vec};
+
+/// Maximum number of known networks.
+pub const MAX_NETWORKS: usize = 4;
+
+/// Maximum SSID length (bytes).
+pub const MAX_SSID_LEN: usize = 32;
+
+/// Maximum WPA2 passphrase length (64 for a raw hex key).
+pub const MAX_PASSPHRASE_LEN: usize = 64;
+
+/// Minimum WPA2 passphrase length.
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Known networks, in the order they were configured.
+pub type KnownNetworks = Vec<KnownNetwork, MAX_NETWORKS>;
+
+/// Network configuration error types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NetworkError {
+    /// SSID empty or longer than [`MAX_SSID_LEN`]
+    InvalidSsid,
+    /// Passphrase neither empty (open network) nor 8-64 characters
+    InvalidPassphrase,
+}
+
+/// WiFi network the cube may join.
+///
+/// Deliberately not `defmt::Format`, so passphrases don't end up in logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownNetwork {
+    pub ssid: String<MAX_SSID_LEN>,
+    /// WPA2 passphrase, empty for open networks
+    passphrase: String<MAX_PASSPHRASE_LEN>,
+    /// Networks with higher priority are tried first among equally strong
+    /// ones and those not seen in a scan
+    pub priority: u8,
+}
+
+impl KnownNetwork {
+    /// Network with a WPA2 passphrase, or an open one if `passphrase` is empty.
+    pub fn new(ssid: &str, passphrase: &str, priority: u8) -> Result<Self, NetworkError> {
+        if ssid.is_empty() {
+            return Err(NetworkError::InvalidSsid);
+        }
+        let ssid = String::try_from(ssid).map_err(|_| NetworkError::InvalidSsid)?;
+
+        let valid_passphrase = passphrase.is_empty() || passphrase.len() >= MIN_PASSPHRASE_LEN;
+        let passphrase = String::try_from(passphrase)
+            .ok()
+            .filter(|_| valid_passphrase)
+            .ok_or(NetworkError::InvalidPassphrase)?;
+
+        Ok(Self {
+            ssid,
+            passphrase,
+            priority,
+        })
+    }
+
+    /// WPA2 passphrase, `None` for open networks.
+    pub fn passphrase(&self) -> Option<&str> {
+        match self.passphrase.is_empty() {
+            true => None,
+            false => Some(&self.passphrase),
+        }
+    }
+}
+
+/// Strongest signal of each known network seen in a scan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanResults {
+    /// RSSI (dBm) by index of the known network
+    rssi: [Option<i16>; MAX_NETWORKS],
+}
+
+impl ScanResults {
+    /// Record a scanned access point. Unknown networks are ignored, a network
+    /// seen through several access points keeps the strongest signal.
+    pub fn record(&mut self, known: &[KnownNetwork], ssid: &[u8], rssi: i16) {
+        for (n, network) in known.iter().enumerate().take(MAX_NETWORKS) {
+            if network.ssid.as_bytes() == ssid {
+                let best = self.rssi[n].get_or_insert(rssi);
+                *best = (*best).max(rssi);
+            }
+        }
+    }
+
+    /// Strongest signal of the known network with the given index, `None` if
+    /// it wasn't seen.
+    pub fn rssi(&self, index: usize) -> Option<i16> {
+        self.rssi.get(index).copied().flatten()
+    }
+
+    /// Indices of the known networks in the order to try them.
+    pub fn connection_order(&self, known: &[KnownNetwork]) -> Vec<usize, MAX_NETWORKS> {
+        let mut order: Vec<usize, MAX_NETWORKS> = (0..known.len().min(MAX_NETWORKS)).collect();
+        // Configuration order breaks remaining ties
+        order.sort_unstable_by_key(|&n| {
+            let rssi = self.rssi(n);
+            (rssi.is_none(), Reverse(rssi), Reverse(known[n].priority), n)
+        });
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> KnownNetworks {
+        [
+            KnownNetwork::new("Zuhause", "correct horse", 1),
+            KnownNetwork::new("Pixelblaze_Leuchtturm", "", 2),
+            KnownNetwork::new("Fusion-Festival", "12345678", 3),
+            KnownNetwork::new("Fusion-Crew", "87654321", 3),
+        ]
+        .into_iter()
+        .map(Result::unwrap)
+        .collect()
+    }
+
+    #[test]
+    fn validates_networks() {
+        let open = KnownNetwork::new("Pixelblaze_Leuchtturm", "", 0).unwrap();
+        assert_eq!(open.passphrase(), None);
+        let wpa2 = KnownNetwork::new("Testturm2", "12345678", 0).unwrap();
+        assert_eq!(wpa2.passphrase(), Some("12345678"));
+
+        assert_eq!(
+            KnownNetwork::new("", "12345678", 0),
+            Err(NetworkError::InvalidSsid)
+        );
+        assert_eq!(
+            KnownNetwork::new("an-ssid-of-thirty-three-bytes-xyz", "", 0),
+            Err(NetworkError::InvalidSsid)
+        );
+        assert_eq!(
+            KnownNetwork::new("Testturm2", "1234567", 0),
+            Err(NetworkError::InvalidPassphrase)
+        );
+    }
+
+    #[test]
+    fn prefers_signal_then_priority() {
+        let known = known();
+        let mut scan = ScanResults::default();
+        scan.record(&known, b"Zuhause", -40);
+        scan.record(&known, b"Fusion-Festival", -80);
+        scan.record(&known, b"Fusion-Crew", -75);
+        // Second access point of the same network
+        scan.record(&known, b"Fusion-Festival", -60);
+        scan.record(&known, b"Nachbar", -30);
+
+        assert_eq!(scan.rssi(2), Some(-60));
+        // Lighthouse AP not seen, tried last
+        assert_eq!(scan.connection_order(&known), [0, 2, 3, 1]);
+
+        // Equally strong, the higher priority wins
+        let mut scan = ScanResults::default();
+        scan.record(&known, b"Zuhause", -60);
+        scan.record(&known, b"Fusion-Festival", -60);
+        assert_eq!(scan.connection_order(&known), [2, 0, 3, 1]);
+    }
+
+    #[test]
+    fn falls_back_to_unseen_networks_by_priority() {
+        let known = known();
+        let scan = ScanResults::default();
+        assert_eq!(scan.connection_order(&known), [2, 3, 1, 0]);
+        assert_eq!(scan.connection_order(&known[..1]), [0]);
+    }
+}
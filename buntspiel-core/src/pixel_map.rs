@@ -0,0 +1,354 @@
+//! # Pixelblaze Pixel Map Projection
+//!
+//! Projects the lighthouse's LED coordinates onto the matrix, so 2D/3D
+//! patterns keep their shape on the cube (e.g. a pattern rotating around the
+//! lighthouse rotates across the matrix columns).
+//!
+//! ## Format
+//! Pixel maps are read in the binary format Pixelblaze stores them in
+//! (`http://<pixelblaze>/pixelmap.dat`):
+//! `[version: u32, dimensions: u32, data_size: u32, ...coordinates]`
+//! (little endian), followed by one coordinate per dimension and pixel,
+//! 8 bit for version 1 and 16 bit (little endian) for version 2.
+
+use core::f32::consts::PI;
+
+use heapless::Vec;
+
+/// Largest pixel map supported
+pub const MAX_MAP_PIXELS: usize = 1024;
+
+/// Largest matrix supported (16x8 tiles)
+pub const MAX_MATRIX_PIXELS: usize = 128;
+
+/// Length of the pixel map header
+const HEADER_LEN: usize = 12;
+
+/// Pixel map decoding errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PixelMapError {
+    /// Header or coordinate data is malformed
+    Invalid,
+    /// Unknown pixel map version
+    Unsupported(u32),
+    /// More pixels or matrix cells than supported
+    TooLarge,
+}
+
+/// Coordinate axis of a pixel map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// How pixel coordinates are flattened onto the matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Projection {
+    /// Use x and y as matrix column and row (2D maps, front view of 3D maps)
+    Planar,
+    /// Unwrap a cylinder around the `vertical` axis: the angle around the
+    /// axis becomes the column, the height (increasing upwards) the row
+    Cylindrical { vertical: Axis },
+}
+
+/// Decoded view of a binary pixel map.
+struct PixelMap<'a> {
+    /// Number of coordinates per pixel (1-3)
+    dimensions: usize,
+    /// Bytes per coordinate (1 or 2)
+    width: usize,
+    /// Coordinate data
+    data: &'a [u8],
+    /// Lowest and highest raw value per axis, for normalization
+    bounds: [(u16, u16); 3],
+}
+
+impl<'a> PixelMap<'a> {
+    fn decode(data: &'a [u8]) -> Result<Self, PixelMapError> {
+        let header = data.get(..HEADER_LEN).ok_or(PixelMapError::Invalid)?;
+        let word = |n: usize| {
+            let bytes = [
+                header[n * 4],
+                header[n * 4 + 1],
+                header[n * 4 + 2],
+                header[n * 4 + 3],
+            ];
+            u32::from_le_bytes(bytes)
+        };
+        let (version, dimensions, size) = (word(0), word(1) as usize, word(2) as usize);
+
+        let width = match version {
+            1 => 1,
+            2 => 2,
+            v => return Err(PixelMapError::Unsupported(v)),
+        };
+        if !(1..=3).contains(&dimensions) {
+            return Err(PixelMapError::Invalid);
+        }
+        let data = data
+            .get(HEADER_LEN..HEADER_LEN.saturating_add(size))
+            .filter(|data| data.len() % (dimensions * width) == 0)
+            .ok_or(PixelMapError::Invalid)?;
+        if data.len() / (dimensions * width) > MAX_MAP_PIXELS {
+            return Err(PixelMapError::TooLarge);
+        }
+
+        let mut map = Self {
+            dimensions,
+            width,
+            data,
+            bounds: [(u16::MAX, 0); 3],
+        };
+        for pixel in 0..map.len() {
+            for axis in 0..dimensions {
+                let value = map.raw(pixel, axis);
+                let (low, high) = &mut map.bounds[axis];
+                *low = (*low).min(value);
+                *high = (*high).max(value);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Number of pixels in the map.
+    fn len(&self) -> usize {
+        self.data.len() / (self.dimensions * self.width)
+    }
+
+    fn raw(&self, pixel: usize, axis: usize) -> u16 {
+        let offset = (pixel * self.dimensions + axis) * self.width;
+        match self.width {
+            1 => self.data[offset].into(),
+            _ => u16::from_le_bytes([self.data[offset], self.data[offset + 1]]),
+        }
+    }
+
+    /// Coordinate scaled to 0.0-1.0 over the extent of the map. Missing axes
+    /// and axes without extent are centered.
+    fn coordinate(&self, pixel: usize, axis: usize) -> f32 {
+        let (low, high) = self.bounds[axis];
+        if axis >= self.dimensions || high <= low {
+            return 0.5;
+        }
+        f32::from(self.raw(pixel, axis) - low) / f32::from(high - low)
+    }
+}
+
+impl Projection {
+    /// Position of a pixel on the unit square (column, row).
+    fn project(self, map: &PixelMap<'_>, pixel: usize) -> (f32, f32) {
+        let coordinate = |axis: usize| map.coordinate(pixel, axis);
+        match self {
+            Projection::Planar => (coordinate(0), coordinate(1)),
+            Projection::Cylindrical { vertical } => {
+                let v = vertical.index();
+                let (a, b) = ((v + 1) % 3, (v + 2) % 3);
+                let angle = libm::atan2f(coordinate(b) - 0.5, coordinate(a) - 0.5);
+                let u = angle / (2.0 * PI) + 0.5;
+                // Half a turn in both directions meets at the first column
+                (if u >= 1.0 { u - 1.0 } else { u }, 1.0 - coordinate(v))
+            }
+        }
+    }
+
+    /// Distance between two projected positions (squared).
+    fn distance(self, (u1, v1): (f32, f32), (u2, v2): (f32, f32)) -> f32 {
+        let mut du = libm::fabsf(u1 - u2);
+        if let Projection::Cylindrical { .. } = self {
+            // Columns wrap around the cylinder
+            du = du.min(1.0 - du);
+        }
+        du * du + (v1 - v2) * (v1 - v2)
+    }
+}
+
+/// Strip pixels shown on each matrix pixel, precomputed from a pixel map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionTable {
+    /// Strip pixel indices, grouped by matrix pixel
+    sources: Vec<u16, { MAX_MAP_PIXELS + MAX_MATRIX_PIXELS }>,
+    /// End of each matrix pixel's group in `sources`
+    ends: Vec<u16, MAX_MATRIX_PIXELS>,
+}
+
+impl ProjectionTable {
+    /// Project a binary pixel map onto a `columns` x `rows` matrix.
+    ///
+    /// Every matrix pixel averages the strip pixels projected onto it, or
+    /// shows the closest strip pixel if none are.
+    pub fn build(
+        pixel_map: &[u8],
+        projection: Projection,
+        columns: usize,
+        rows: usize,
+    ) -> Result<Self, PixelMapError> {
+        let map = PixelMap::decode(pixel_map)?;
+        if columns * rows > MAX_MATRIX_PIXELS {
+            return Err(PixelMapError::TooLarge);
+        }
+
+        // Matrix pixel of every strip pixel
+        let cell = |(u, v): (f32, f32)| {
+            let column = ((u * columns as f32) as usize).min(columns.saturating_sub(1));
+            let row = ((v * rows as f32) as usize).min(rows.saturating_sub(1));
+            row * columns + column
+        };
+        let mut cells: Vec<u16, MAX_MAP_PIXELS> = Vec::new();
+        for pixel in 0..map.len() {
+            _ = cells.push(cell(projection.project(&map, pixel)) as u16);
+        }
+
+        let mut table = Self {
+            sources: Vec::new(),
+            ends: Vec::new(),
+        };
+        for n in 0..columns * rows {
+            let start = table.sources.len();
+            for (pixel, _) in cells
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| usize::from(**c) == n)
+            {
+                _ = table.sources.push(pixel as u16);
+            }
+
+            if table.sources.len() == start {
+                let center = (
+                    ((n % columns) as f32 + 0.5) / columns as f32,
+                    ((n / columns) as f32 + 0.5) / rows as f32,
+                );
+                let closest = (0..map.len()).min_by(|a, b| {
+                    let a = projection.distance(center, projection.project(&map, *a));
+                    let b = projection.distance(center, projection.project(&map, *b));
+                    a.total_cmp(&b)
+                });
+                if let Some(pixel) = closest {
+                    _ = table.sources.push(pixel as u16);
+                }
+            }
+            _ = table.ends.push(table.sources.len() as u16);
+        }
+        Ok(table)
+    }
+
+    /// Strip pixels shown on the given matrix pixel.
+    pub fn sources(&self, n: usize) -> &[u16] {
+        let start = match n.checked_sub(1) {
+            Some(previous) => self.ends.get(previous).copied().unwrap_or_default(),
+            None => 0,
+        };
+        let end = self.ends.get(n).copied().unwrap_or(start);
+        &self.sources[usize::from(start)..usize::from(end)]
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ProjectionTable {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "ProjectionTable({} pixels)", self.ends.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Version 2 pixel map with the given 16 bit coordinates.
+    fn pixel_map<const N: usize>(dimensions: u32, coordinates: &[u16]) -> Vec<u8, N> {
+        let mut data = Vec::new();
+        let size = coordinates.len() as u32 * 2;
+        for word in [2, dimensions, size] {
+            data.extend_from_slice(&u32::to_le_bytes(word)).unwrap();
+        }
+        for c in coordinates {
+            data.extend_from_slice(&c.to_le_bytes()).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn projects_planar_map() {
+        // 2x2 grid of pixels, the last one also at the bottom right
+        let map = pixel_map::<64>(2, &[0, 0, 100, 0, 0, 100, 100, 100, 90, 90]);
+        let table = ProjectionTable::build(&map, Projection::Planar, 2, 2).unwrap();
+
+        assert_eq!(table.sources(0), &[0]);
+        assert_eq!(table.sources(1), &[1]);
+        assert_eq!(table.sources(2), &[2]);
+        assert_eq!(table.sources(3), &[3, 4]);
+        assert_eq!(table.sources(4), &[] as &[u16]);
+    }
+
+    #[test]
+    fn unwraps_cylinder() {
+        // Two rings of four pixels around the z axis, top ring first
+        let ring = |z| [100, 50, z, 50, 100, z, 0, 50, z, 50, 0, z];
+        let mut coordinates: Vec<u16, 24> = Vec::new();
+        coordinates.extend_from_slice(&ring(100)).unwrap();
+        coordinates.extend_from_slice(&ring(0)).unwrap();
+        let map = pixel_map::<64>(3, &coordinates);
+
+        let projection = Projection::Cylindrical { vertical: Axis::Z };
+        let table = ProjectionTable::build(&map, projection, 4, 2).unwrap();
+
+        // Going around the lighthouse moves along the top row of the matrix
+        let top: [&[u16]; 4] = core::array::from_fn(|n| table.sources(n));
+        assert_eq!(top, [&[2][..], &[3], &[0], &[1]]);
+        let bottom: [&[u16]; 4] = core::array::from_fn(|n| table.sources(4 + n));
+        assert_eq!(bottom, [&[6][..], &[7], &[4], &[5]]);
+    }
+
+    #[test]
+    fn fills_empty_cells_with_closest_pixel() {
+        let map = pixel_map::<64>(2, &[0, 0, 100, 100]);
+        let table = ProjectionTable::build(&map, Projection::Planar, 2, 2).unwrap();
+
+        assert_eq!(table.sources(0), &[0]);
+        assert_eq!(table.sources(3), &[1]);
+        assert_eq!(table.sources(1).len(), 1);
+        assert_eq!(table.sources(2).len(), 1);
+    }
+
+    #[test]
+    fn decodes_8bit_maps() {
+        let map = [1, 0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 255, 255];
+        let table = ProjectionTable::build(&map, Projection::Planar, 2, 1).unwrap();
+        assert_eq!(table.sources(0), &[0]);
+        assert_eq!(table.sources(1), &[1]);
+    }
+
+    #[test]
+    fn rejects_malformed_maps() {
+        let build = |map: &[u8]| ProjectionTable::build(map, Projection::Planar, 4, 4);
+        assert_eq!(build(&[2, 0, 0]), Err(PixelMapError::Invalid));
+        assert_eq!(
+            build(&[3, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]),
+            Err(PixelMapError::Unsupported(3))
+        );
+        // Data size beyond the end of the map
+        assert_eq!(
+            build(&[1, 0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0]),
+            Err(PixelMapError::Invalid)
+        );
+        // Incomplete pixel
+        assert_eq!(
+            build(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0]),
+            Err(PixelMapError::Invalid)
+        );
+    }
+}
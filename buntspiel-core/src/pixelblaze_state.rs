@@ -0,0 +1,262 @@
+//! # Pixelblaze State Model
+//!
+//! Typed view of the Pixelblaze configuration, decoded from the JSON text
+//! messages the controller sends in reply to `getConfig` and on its own
+//! whenever the active program, sequencer or brightness changes.
+//!
+//! Pixelblaze splits its configuration over several messages, so every
+//! message only updates the fields it contains:
+//! - `{"name":"...","ver":"3.40","pixelCount":300,"brightness":0.5,...}`
+//! - `{"activeProgram":{"name":"...","activeProgramId":"...","controls":{}},"sequencerMode":2,"runSequencer":true}`
+//! - `{"fps":59.8,"vmerr":0,"mem":10238,...}`
+
+use heapless::String;
+
+use crate::json::{self, JsonError, JsonStr, Object, Value};
+
+/// Length of a Pixelblaze pattern ID (e.g. `kuJfFyCSkCKNasyNE`)
+pub const PATTERN_ID_LEN: usize = 17;
+/// Maximum stored length of a pattern name (longer names are truncated)
+pub const PATTERN_NAME_LEN: usize = 50;
+
+pub type PatternId = String<PATTERN_ID_LEN>;
+pub type PatternName = String<PATTERN_NAME_LEN>;
+
+/// Pixelblaze sequencer (playlist) modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SequencerMode {
+    /// Sequencer disabled, the active pattern runs until changed
+    Off,
+    /// Cycle through all patterns in random order
+    ShuffleAll,
+    /// Cycle through the configured playlist
+    Playlist,
+    /// Mode not known to this firmware
+    Unknown(u8),
+}
+
+impl From<u8> for SequencerMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::ShuffleAll,
+            2 => Self::Playlist,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// The program currently running on the Pixelblaze.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActiveProgram {
+    /// Pattern ID (`None` while running unsaved code from the editor)
+    pub id: Option<PatternId>,
+    /// Human readable pattern name
+    pub name: PatternName,
+}
+
+/// Last known Pixelblaze state. Fields stay `None` until reported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PixelblazeState {
+    /// Device name as configured in the Pixelblaze settings
+    pub name: Option<String<32>>,
+    /// Firmware version (e.g. "3.40")
+    pub version: Option<String<8>>,
+    /// Number of LEDs driven by the controller
+    pub pixel_count: Option<u16>,
+    /// Global brightness (0.0-1.0)
+    pub brightness: Option<f32>,
+    /// Sequencer mode
+    pub sequencer_mode: Option<SequencerMode>,
+    /// Whether the sequencer is currently advancing patterns
+    pub run_sequencer: Option<bool>,
+    /// Currently running program
+    pub active_program: Option<ActiveProgram>,
+    /// Render rate reported in the periodic status message
+    pub fps: Option<f32>,
+}
+
+/// Which parts of the state a message updated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateUpdate {
+    /// Device configuration (name, version, pixel count, brightness)
+    pub config: bool,
+    /// Active program
+    pub active_program: bool,
+    /// Sequencer mode or state
+    pub sequencer: bool,
+    /// Periodic status (fps)
+    pub status: bool,
+}
+
+impl StateUpdate {
+    /// True if the message contained nothing we track.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl PixelblazeState {
+    /// Apply a JSON text message from Pixelblaze.
+    ///
+    /// Unknown fields and non-object messages are ignored. The state is left
+    /// untouched if the message is malformed.
+    pub fn apply(&mut self, message: &str) -> Result<StateUpdate, JsonError> {
+        let Value::Object(object) = json::parse(message)? else {
+            return Ok(StateUpdate::default());
+        };
+
+        let mut next = self.clone();
+        let mut update = StateUpdate::default();
+
+        for member in object.members() {
+            let (key, value) = member?;
+            // Pixelblaze keys never contain escapes, so compare them raw
+            match key.raw() {
+                "name" => {
+                    next.name = value
+                        .as_str()
+                        .map(|s| s.to_string_truncated())
+                        .transpose()?;
+                    update.config = true;
+                }
+                "ver" => {
+                    next.version = value
+                        .as_str()
+                        .map(|s| s.to_string_truncated())
+                        .transpose()?;
+                    update.config = true;
+                }
+                "pixelCount" => {
+                    next.pixel_count = value.as_f32().map(|n| n as u16);
+                    update.config = true;
+                }
+                "brightness" => {
+                    next.brightness = value.as_f32();
+                    update.config = true;
+                }
+                "sequencerMode" => {
+                    next.sequencer_mode = value.as_f32().map(|n| SequencerMode::from(n as u8));
+                    update.sequencer = true;
+                }
+                "runSequencer" => {
+                    next.run_sequencer = value.as_bool();
+                    update.sequencer = true;
+                }
+                "activeProgram" => {
+                    next.active_program =
+                        value.as_object().map(parse_active_program).transpose()?;
+                    update.active_program = true;
+                }
+                "fps" => {
+                    next.fps = value.as_f32();
+                    update.status = true;
+                }
+                _ => {}
+            }
+        }
+
+        *self = next;
+        Ok(update)
+    }
+}
+
+/// Decode the `activeProgram` object.
+fn parse_active_program(object: Object<'_>) -> Result<ActiveProgram, JsonError> {
+    let mut program = ActiveProgram::default();
+    for member in object.members() {
+        let (key, value) = member?;
+        match key.raw() {
+            "name" => {
+                if let Some(name) = value.as_str() {
+                    program.name = name.to_string_truncated()?;
+                }
+            }
+            "activeProgramId" => {
+                program.id = value
+                    .as_str()
+                    .filter(|id| !id.raw().is_empty())
+                    .map(|id: JsonStr<'_>| id.to_string())
+                    .transpose()?;
+            }
+            _ => {}
+        }
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured from a Pixelblaze 3 running v3.40
+    const CONFIG: &str = r#"{"name":"Leuchtturm","brandName":"","pixelCount":300,"brightness":0.6,"maxBrightness":100,"colorOrder":"GRB","dataSpeed":3500000,"ledType":2,"sequenceTimer":15,"transitionDuration":0,"sequencerMode":0,"runSequencer":false,"simpleUiMode":false,"learningUiMode":false,"discoveryEnable":false,"timezone":"","autoOffEnable":false,"autoOffStart":"00:00","autoOffEnd":"00:00","cpuSpeed":240,"networkPowerSave":false,"mapperFit":0,"leaderId":0,"nodeId":0,"soundSrc":0,"accelSrc":0,"lightSrc":0,"analogSrc":0,"exp":0,"ver":"3.40","chipId":6910364}"#;
+    const ACTIVE_PROGRAM: &str = r#"{"activeProgram":{"name":"Editor: blink fade","activeProgramId":"kuJfFyCSkCKNasyNE","controls":{"sliderSpeed":0.5}},"sequencerMode":2,"runSequencer":true}"#;
+    const NO_PROGRAM: &str = r#"{"activeProgram":{"name":"","activeProgramId":null,"controls":{}},"sequencerMode":2,"runSequencer":true}"#;
+    const STATUS: &str = r#"{"fps":59.7,"vmerr":0,"vmerrpc":-1,"mem":10238,"exp":0,"renderType":2,"uptime":81730,"storageUsed":798720,"storageSize":1378241,"rr0":1,"rr1":0,"rebootCounter":0}"#;
+
+    #[test]
+    fn applies_config_reply() {
+        let mut state = PixelblazeState::default();
+        let update = state.apply(CONFIG).unwrap();
+
+        assert!(update.config && update.sequencer && !update.active_program);
+        assert_eq!(state.name.as_deref(), Some("Leuchtturm"));
+        assert_eq!(state.version.as_deref(), Some("3.40"));
+        assert_eq!(state.pixel_count, Some(300));
+        assert_eq!(state.brightness, Some(0.6));
+        assert_eq!(state.sequencer_mode, Some(SequencerMode::Off));
+        assert_eq!(state.run_sequencer, Some(false));
+    }
+
+    #[test]
+    fn applies_active_program() {
+        let mut state = PixelblazeState::default();
+        state.apply(CONFIG).unwrap();
+        let update = state.apply(ACTIVE_PROGRAM).unwrap();
+
+        assert!(update.active_program && update.sequencer && !update.config);
+        let program = state.active_program.clone().unwrap();
+        assert_eq!(program.id.as_deref(), Some("kuJfFyCSkCKNasyNE"));
+        assert_eq!(program.name.as_str(), "Editor: blink fade");
+        assert_eq!(state.sequencer_mode, Some(SequencerMode::Playlist));
+        // Fields from earlier messages are kept
+        assert_eq!(state.pixel_count, Some(300));
+
+        state.apply(NO_PROGRAM).unwrap();
+        assert_eq!(state.active_program.unwrap().id, None);
+    }
+
+    #[test]
+    fn applies_status() {
+        let mut state = PixelblazeState::default();
+        let update = state.apply(STATUS).unwrap();
+        assert_eq!(
+            update,
+            StateUpdate {
+                status: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(state.fps, Some(59.7));
+    }
+
+    #[test]
+    fn malformed_message_leaves_state_untouched() {
+        let mut state = PixelblazeState::default();
+        state.apply(ACTIVE_PROGRAM).unwrap();
+        let before = state.clone();
+
+        assert!(state
+            .apply(r#"{"brightness":0.1,"activeProgram":{"name":"#)
+            .is_err());
+        assert!(state
+            .apply(r#"{"activeProgram":{"activeProgramId":"waytoolongforapatternid"}}"#)
+            .is_err());
+        assert_eq!(state, before);
+
+        assert!(state.apply("[1,2,3]").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! # Preview Pixel Mapping
+//!
+//! Reduces a Pixelblaze preview frame (one RGB triplet per lighthouse LED,
+//! usually hundreds) to the pixels of the NeoTrellis matrix.
+//!
+//! ## Strategies
+//! - **FirstN**: the first pixels of the strip, one to one
+//! - **Sampled**: evenly spaced pixels over the whole strip
+//! - **Averaged**: the average of evenly sized buckets of the strip
+//! - **IndexTable**: an explicit strip index for every matrix pixel
+//! - **Projected**: the strip pixels at each matrix pixel's position in the
+//!   lighthouse's pixel map (see [`crate::pixel_map`])
+
+use crate::frame::Rgb;
+use crate::pixel_map::ProjectionTable;
+
+/// How preview pixels are mapped onto the matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mapping {
+    /// Show the first pixels of the strip
+    FirstN,
+    /// Show evenly spaced pixels of the strip
+    Sampled,
+    /// Show the average color of evenly sized parts of the strip
+    Averaged,
+    /// Show the strip pixel listed for each matrix pixel
+    IndexTable(&'static [u16]),
+    /// Show the average color of the strip pixels projected onto each
+    /// matrix pixel
+    Projected(&'static ProjectionTable),
+}
+
+impl Mapping {
+    /// The strategy after this one, for cycling through all of them.
+    ///
+    /// Index table and projection are skipped if not configured.
+    pub fn next(
+        self,
+        index_table: Option<&'static [u16]>,
+        projection: Option<&'static ProjectionTable>,
+    ) -> Self {
+        let index_table = index_table.map(Mapping::IndexTable);
+        let projected = projection.map(Mapping::Projected);
+        match self {
+            Mapping::FirstN => Mapping::Sampled,
+            Mapping::Sampled => Mapping::Averaged,
+            Mapping::Averaged => index_table.or(projected).unwrap_or(Mapping::FirstN),
+            Mapping::IndexTable(_) => projected.unwrap_or(Mapping::FirstN),
+            Mapping::Projected(_) => Mapping::FirstN,
+        }
+    }
+
+    /// Map the RGB triplets of a preview frame onto `out`.
+    ///
+    /// `rgb` holds the frame without its message type byte. Trailing bytes of
+    /// an incomplete triplet are ignored, pixels without a source stay dark.
+    pub fn map(self, rgb: &[u8], out: &mut [Rgb]) {
+        let strip = Strip(rgb);
+        let (n, m) = (strip.len(), out.len());
+
+        for (i, pixel) in out.iter_mut().enumerate() {
+            let source = match self {
+                Mapping::FirstN => strip.get(i),
+                Mapping::Sampled => strip.get((2 * i + 1) * n / (2 * m)),
+                Mapping::Averaged => {
+                    let (start, end) = (i * n / m, (i + 1) * n / m);
+                    if start < end {
+                        strip.average(start..end)
+                    } else {
+                        // Strip shorter than the matrix, repeat pixels
+                        strip.get(start)
+                    }
+                }
+                Mapping::IndexTable(table) => {
+                    table.get(i).and_then(|&index| strip.get(index.into()))
+                }
+                Mapping::Projected(table) => {
+                    strip.average(table.sources(i).iter().map(|&index| index.into()))
+                }
+            };
+            *pixel = source.unwrap_or_default();
+        }
+    }
+}
+
+/// RGB triplets of a preview frame.
+struct Strip<'a>(&'a [u8]);
+
+impl Strip<'_> {
+    fn len(&self) -> usize {
+        self.0.len() / 3
+    }
+
+    fn get(&self, index: usize) -> Option<Rgb> {
+        let [r, g, b] = *self.0.get(index * 3..index * 3 + 3)? else {
+            return None;
+        };
+        Some(Rgb { r, g, b })
+    }
+
+    /// Average of the given pixels, `None` if there are none or any is missing.
+    fn average(&self, indices: impl Iterator<Item = usize>) -> Option<Rgb> {
+        let (mut r, mut g, mut b, mut count) = (0_u32, 0_u32, 0_u32, 0_u32);
+        for index in indices {
+            let pixel = self.get(index)?;
+            r += u32::from(pixel.r);
+            g += u32::from(pixel.g);
+            b += u32::from(pixel.b);
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(Rgb {
+            r: (r / count) as u8,
+            g: (g / count) as u8,
+            b: (b / count) as u8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strip of `n` pixels whose red channel is the pixel index.
+    fn strip<const N: usize>() -> [u8; N] {
+        let mut rgb = [0; N];
+        for (i, triplet) in rgb.chunks_mut(3).enumerate() {
+            triplet[0] = i as u8;
+            triplet[2] = 0xFF;
+        }
+        rgb
+    }
+
+    fn reds(mapping: Mapping, rgb: &[u8]) -> [u8; 4] {
+        let mut out = [Rgb::default(); 4];
+        mapping.map(rgb, &mut out);
+        out.map(|p| p.r)
+    }
+
+    #[test]
+    fn maps_first_pixels() {
+        assert_eq!(reds(Mapping::FirstN, &strip::<{ 3 * 100 }>()), [0, 1, 2, 3]);
+        // Short strips leave the rest of the matrix dark
+        assert_eq!(reds(Mapping::FirstN, &strip::<{ 3 * 2 }>()), [0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn samples_whole_strip() {
+        assert_eq!(
+            reds(Mapping::Sampled, &strip::<{ 3 * 100 }>()),
+            [12, 37, 62, 87]
+        );
+        assert_eq!(reds(Mapping::Sampled, &strip::<{ 3 * 2 }>()), [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn averages_buckets() {
+        assert_eq!(
+            reds(Mapping::Averaged, &strip::<{ 3 * 100 }>()),
+            [12, 37, 62, 87]
+        );
+        assert_eq!(reds(Mapping::Averaged, &strip::<{ 3 * 2 }>()), [0, 0, 1, 1]);
+
+        let mut out = [Rgb::default(); 4];
+        Mapping::Averaged.map(&strip::<{ 3 * 100 }>(), &mut out);
+        assert!(out.iter().all(|p| p.b == 0xFF));
+    }
+
+    #[test]
+    fn looks_up_index_table() {
+        let mapping = Mapping::IndexTable(&[99, 0, 500]);
+        assert_eq!(reds(mapping, &strip::<{ 3 * 100 }>()), [99, 0, 0, 0]);
+    }
+
+    #[test]
+    fn cycles_through_strategies() {
+        let mut mapping = Mapping::FirstN;
+        for expected in [Mapping::Sampled, Mapping::Averaged, Mapping::FirstN] {
+            mapping = mapping.next(None, None);
+            assert_eq!(mapping, expected);
+        }
+
+        // Configured index tables join the cycle
+        const TABLE: &[u16] = &[1, 2, 3];
+        let mapping = Mapping::Averaged.next(Some(TABLE), None);
+        assert_eq!(mapping, Mapping::IndexTable(TABLE));
+        assert_eq!(mapping.next(Some(TABLE), None), Mapping::FirstN);
+    }
+}
@@ -0,0 +1,290 @@
+//! # Pixelblaze Program List
+//!
+//! Streaming decoder for the pattern list Pixelblaze sends in reply to
+//! `{"listPrograms":true}`.
+//!
+//! ## Format
+//! The list arrives as one or more binary frames of type 7 (`GetProgramList`):
+//! `[7, flags, ...text]`, where `flags` marks the first (`1`), a middle (`2`)
+//! and the last (`4`) frame of the list. The text is a sequence of
+//! `<id>\t<name>\n` lines which may be split at any byte across frames.
+
+use core::str::from_utf8;
+
+use heapless::{String, Vec};
+
+use crate::pixelblaze_state::{PatternId, PatternName};
+
+/// Maximum number of patterns kept (further entries are counted but dropped)
+pub const MAX_PROGRAMS: usize = 64;
+
+/// Longest line buffered across frames (id + tab + name in UTF-8)
+const MAX_LINE: usize = 128;
+
+/// Frame flag: first frame of a program list
+const FRAME_FIRST: u8 = 1;
+/// Frame flag: frame somewhere in the middle of a program list
+const FRAME_MIDDLE: u8 = 2;
+/// Frame flag: last frame of a program list
+const FRAME_LAST: u8 = 4;
+
+/// A pattern stored on the Pixelblaze.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramEntry {
+    /// Pattern ID used by `setActivePattern`
+    pub id: PatternId,
+    /// Human readable pattern name (truncated if too long)
+    pub name: PatternName,
+}
+
+/// Bounded list of the patterns stored on the Pixelblaze.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramList {
+    /// Patterns in the order Pixelblaze reported them
+    pub programs: Vec<ProgramEntry, MAX_PROGRAMS>,
+    /// Number of patterns dropped because the list was full
+    pub dropped: usize,
+}
+
+impl ProgramList {
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ProgramEntry> {
+        self.programs.get(index)
+    }
+
+    /// Position of the pattern with the given ID.
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.programs.iter().position(|p| p.id.as_str() == id)
+    }
+
+    /// Pattern following `id`, wrapping around. Starts at the first pattern
+    /// if `id` is unknown.
+    pub fn next_after(&self, id: Option<&str>) -> Option<&ProgramEntry> {
+        let next = match id.and_then(|id| self.position(id)) {
+            Some(n) => (n + 1) % self.len().max(1),
+            None => 0,
+        };
+        self.get(next)
+    }
+
+    /// Pattern preceding `id`, wrapping around. Starts at the last pattern
+    /// if `id` is unknown.
+    pub fn previous_before(&self, id: Option<&str>) -> Option<&ProgramEntry> {
+        let previous = match id.and_then(|id| self.position(id)) {
+            Some(0) | None => self.len().checked_sub(1)?,
+            Some(n) => n - 1,
+        };
+        self.get(previous)
+    }
+}
+
+/// Program list decoding errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProgramListError {
+    /// Frame is too short or not a program list frame
+    Invalid,
+    /// Continuation frame without a preceding first frame
+    UnexpectedFrame,
+}
+
+/// Incremental decoder assembling a [`ProgramList`] from type-7 frames.
+#[derive(Default)]
+pub struct ProgramListParser {
+    /// List being assembled
+    list: ProgramList,
+    /// Bytes of a line split across frames
+    line: Vec<u8, MAX_LINE>,
+    /// Whether a first frame has been seen
+    in_progress: bool,
+}
+
+impl ProgramListParser {
+    /// Feed a complete binary frame (including the message type byte).
+    ///
+    /// Returns the finished list once the last frame has been processed.
+    pub fn feed(&mut self, frame: &[u8]) -> Result<Option<ProgramList>, ProgramListError> {
+        let [7, flags, text @ ..] = frame else {
+            return Err(ProgramListError::Invalid);
+        };
+
+        if flags & FRAME_FIRST != 0 {
+            // A new list always starts from scratch
+            *self = Self::default();
+            self.in_progress = true;
+        } else if flags & (FRAME_MIDDLE | FRAME_LAST) == 0 {
+            return Err(ProgramListError::Invalid);
+        } else if !self.in_progress {
+            return Err(ProgramListError::UnexpectedFrame);
+        }
+
+        for &byte in text {
+            if byte == b'\n' {
+                self.finish_line();
+            } else {
+                // Overlong lines are cut off, only the name suffers
+                _ = self.line.push(byte);
+            }
+        }
+
+        if flags & FRAME_LAST != 0 {
+            // The last line is not always newline terminated
+            self.finish_line();
+            let list = core::mem::take(&mut self.list);
+            *self = Self::default();
+            return Ok(Some(list));
+        }
+
+        Ok(None)
+    }
+
+    /// Decode the buffered `<id>\t<name>` line into a list entry.
+    fn finish_line(&mut self) {
+        let line = core::mem::take(&mut self.line);
+        let text = match from_utf8(&line) {
+            Ok(text) => text,
+            // Truncation may have split a multi-byte character
+            Err(e) => from_utf8(&line[..e.valid_up_to()]).unwrap_or_default(),
+        };
+
+        let Some((id, name)) = text.trim_end_matches('\r').split_once('\t') else {
+            return;
+        };
+        let Ok(id) = PatternId::try_from(id) else {
+            return;
+        };
+
+        let entry = ProgramEntry {
+            id,
+            name: truncated(name),
+        };
+        if self.list.programs.push(entry).is_err() {
+            self.list.dropped += 1;
+        }
+    }
+}
+
+/// Copy a string into a fixed-capacity string, truncating at a char boundary.
+fn truncated<const N: usize>(s: &str) -> String<N> {
+    let mut out = String::new();
+    for c in s.chars() {
+        if out.push(c).is_err() {
+            break;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame<const N: usize>(flags: u8, text: &str) -> Vec<u8, N> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&[7, flags]).unwrap();
+        frame.extend_from_slice(text.as_bytes()).unwrap();
+        frame
+    }
+
+    #[test]
+    fn parses_single_frame_list() {
+        let mut parser = ProgramListParser::default();
+        let list = parser
+            .feed(&frame::<128>(
+                FRAME_FIRST | FRAME_LAST,
+                "kuJfFyCSkCKNasyNE\tblink fade\nWt2xjGTu3oD9zdn6S\t# Leuchtturm bunt\n",
+            ))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(0).unwrap().id.as_str(), "kuJfFyCSkCKNasyNE");
+        assert_eq!(list.get(1).unwrap().name.as_str(), "# Leuchtturm bunt");
+        assert_eq!(list.position("Wt2xjGTu3oD9zdn6S"), Some(1));
+    }
+
+    #[test]
+    fn joins_lines_split_across_frames() {
+        let mut parser = ProgramListParser::default();
+        assert_eq!(
+            parser.feed(&frame::<64>(FRAME_FIRST, "kuJfFyCSkCKNasyNE\tbli")),
+            Ok(None)
+        );
+        assert_eq!(
+            parser.feed(&frame::<64>(FRAME_MIDDLE, "nk fade\nWt2xjGTu3")),
+            Ok(None)
+        );
+        let list = parser
+            .feed(&frame::<64>(FRAME_LAST, "oD9zdn6S\tRegenbogen"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(0).unwrap().name.as_str(), "blink fade");
+        assert_eq!(list.get(1).unwrap().id.as_str(), "Wt2xjGTu3oD9zdn6S");
+        assert_eq!(list.get(1).unwrap().name.as_str(), "Regenbogen");
+    }
+
+    #[test]
+    fn steps_through_list_with_wraparound() {
+        let mut parser = ProgramListParser::default();
+        let list = parser
+            .feed(&frame::<128>(
+                FRAME_FIRST | FRAME_LAST,
+                "aaaaaaaaaaaaaaaaa\tA\nbbbbbbbbbbbbbbbbb\tB\nccccccccccccccccc\tC\n",
+            ))
+            .unwrap()
+            .unwrap();
+
+        let next = |id| list.next_after(id).map(|p| p.name.as_str());
+        let previous = |id| list.previous_before(id).map(|p| p.name.as_str());
+        assert_eq!(next(Some("aaaaaaaaaaaaaaaaa")), Some("B"));
+        assert_eq!(next(Some("ccccccccccccccccc")), Some("A"));
+        assert_eq!(next(None), Some("A"));
+        assert_eq!(previous(Some("aaaaaaaaaaaaaaaaa")), Some("C"));
+        assert_eq!(previous(Some("bbbbbbbbbbbbbbbbb")), Some("A"));
+        assert_eq!(previous(Some("unknown")), Some("C"));
+
+        let empty = ProgramList::default();
+        assert!(empty.next_after(None).is_none());
+        assert!(empty.previous_before(None).is_none());
+    }
+
+    #[test]
+    fn rejects_unexpected_frames() {
+        let mut parser = ProgramListParser::default();
+        assert_eq!(parser.feed(&[7]), Err(ProgramListError::Invalid));
+        assert_eq!(parser.feed(&[5, 1, 0]), Err(ProgramListError::Invalid));
+        assert_eq!(
+            parser.feed(&frame::<16>(FRAME_LAST, "")),
+            Err(ProgramListError::UnexpectedFrame)
+        );
+    }
+
+    #[test]
+    fn bounds_list_and_names() {
+        let mut parser = ProgramListParser::default();
+        parser.feed(&frame::<8>(FRAME_FIRST, "")).unwrap();
+        for i in 0..MAX_PROGRAMS + 3 {
+            let mut line: String<160> = String::new();
+            core::fmt::write(&mut line, format_args!("{:017}\t", i)).unwrap();
+            for _ in 0..70 {
+                line.push('ä').unwrap();
+            }
+            line.push('\n').unwrap();
+            parser.feed(&frame::<200>(FRAME_MIDDLE, &line)).unwrap();
+        }
+        let list = parser.feed(&frame::<8>(FRAME_LAST, "")).unwrap().unwrap();
+
+        assert_eq!(list.len(), MAX_PROGRAMS);
+        assert_eq!(list.dropped, 3);
+        assert_eq!(list.get(0).unwrap().name.chars().count(), 25);
+    }
+}
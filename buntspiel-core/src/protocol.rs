@@ -0,0 +1,216 @@
+//! # Pixelblaze Protocol
+//!
+//! Binary message types of the Pixelblaze WebSocket protocol, preview frame
+//! decoding and the JSON commands sent to Pixelblaze.
+//!
+//! ## Messages
+//! - **Text**: JSON commands for config/control.
+//! - **Binary**: `[message_type: u8, ...data]`, e.g. preview frames
+//!   `[5, r1, g1, b1, r2, g2, b2, ...]` with one RGB triplet per LED.
+
+use core::fmt::{self, Write};
+
+use heapless::{LinearMap, String};
+
+use crate::frame::{Frame, Rgb};
+use crate::json;
+use crate::layout::NEOTRELLIS_PIXELS;
+use crate::preview::Mapping;
+
+/// Maximum number of pattern controls sent along with a pattern switch
+pub const MAX_PATTERN_CONTROLS: usize = 4;
+
+/// Pattern control values by name (e.g. `sliderSpeed` -> 0.5)
+pub type PatternControls = LinearMap<String<24>, f32, MAX_PATTERN_CONTROLS>;
+
+/// Pixelblaze binary message types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PixelblazeMessageType {
+    // Messages from Web UI to Pixelblaze
+    /// Upload pattern source code to Pixelblaze
+    PutSourceCode,
+    /// Upload compiled bytecode to Pixelblaze
+    PutByteCode,
+    /// Send preview image data
+    PreviewImage,
+    /// Request list of available patterns
+    GetProgramList,
+    /// Upload pixel mapping configuration
+    PutPixelMap,
+
+    // Messages from Pixelblaze to Web UI
+    /// **Most important**: Real-time RGB frame data for LED preview
+    /// Format: [5, r1, g1, b1, r2, g2, b2, ...]
+    PreviewFrame,
+    /// Response with pattern source code
+    GetSourceCode,
+
+    // Bidirectional messages
+    /// LED strip expander configuration
+    ExpanderConfig,
+
+    /// Unknown or unsupported message type
+    Unknown(u8),
+}
+
+impl From<u8> for PixelblazeMessageType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::PutSourceCode,
+            3 => Self::PutByteCode,
+            4 => Self::PreviewImage,
+            5 => Self::PreviewFrame,
+            6 => Self::GetSourceCode,
+            7 => Self::GetProgramList,
+            8 => Self::PutPixelMap,
+            9 => Self::ExpanderConfig,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Error types for preview frame parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PreviewFrameErr {
+    /// Frame data is invalid or corrupted
+    Invalid,
+}
+
+/// Preview frame mapped onto the NeoTrellis matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewFrame(pub Frame);
+
+impl PreviewFrame {
+    /// Convert a Pixelblaze binary preview frame to a matrix frame, reducing
+    /// the whole strip to the matrix with `mapping`.
+    pub fn decode(value: &[u8], mapping: Mapping) -> Result<Self, PreviewFrameErr> {
+        // Validate minimum frame size and message type
+        if value.len() < 4
+            || PixelblazeMessageType::from(value[0]) != PixelblazeMessageType::PreviewFrame
+        {
+            return Err(PreviewFrameErr::Invalid);
+        }
+
+        // RGB data starts after the message type byte
+        let rgb_bytes = value.len() - 1;
+
+        // Ensure we have complete RGB triplets (no partial pixels)
+        if !rgb_bytes.is_multiple_of(3) {
+            return Err(PreviewFrameErr::Invalid);
+        }
+
+        // Reduce the whole strip to the matrix (skip message type byte)
+        let mut preview_frame = [Rgb::default(); NEOTRELLIS_PIXELS];
+        mapping.map(&value[1..], &mut preview_frame);
+
+        Ok(PreviewFrame(preview_frame))
+    }
+}
+
+/// Request the Pixelblaze configuration (answered with several text messages)
+pub const GET_CONFIG: &str = r#"{"getConfig":true}"#;
+
+/// Request the program list (answered with type-7 binary frames)
+pub const LIST_PROGRAMS: &str = r#"{"listPrograms":true}"#;
+
+/// Subscribe to preview frames and status messages
+pub const SEND_UPDATES: &str = r#"{"sendUpdates":true}"#;
+
+/// Resume rendering, e.g. after a pattern switch
+pub const UNPAUSE: &str = r#"{"pause":false}"#;
+
+/// Encode a `setActivePattern` command for the given pattern ID.
+pub fn encode_set_active_pattern(id: &str) -> Result<String<48>, fmt::Error> {
+    let mut message = String::new();
+    message.write_str(r#"{"setActivePattern":"#)?;
+    json::write_str(&mut message, id)?;
+    message.write_char('}')?;
+    Ok(message)
+}
+
+/// Encode a `setControls` command for the given control values.
+pub fn encode_set_controls(controls: &PatternControls) -> Result<String<256>, fmt::Error> {
+    let mut message = String::new();
+    message.write_str(r#"{"setControls":{"#)?;
+    for (n, (name, value)) in controls.iter().enumerate() {
+        if n > 0 {
+            message.write_char(',')?;
+        }
+        json::write_str(&mut message, name)?;
+        write!(message, ":{}", value)?;
+    }
+    message.write_str("}}")?;
+    Ok(message)
+}
+
+/// Encode a brightness command (0.0-1.0) that isn't persisted on Pixelblaze,
+/// which saves its flash from wear.
+pub fn encode_brightness(brightness: f32) -> Result<String<48>, fmt::Error> {
+    let mut message = String::new();
+    write!(message, r#"{{"brightness":{},"save":false}}"#, brightness)?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_message_types() {
+        assert_eq!(
+            PixelblazeMessageType::from(5),
+            PixelblazeMessageType::PreviewFrame
+        );
+        assert_eq!(
+            PixelblazeMessageType::from(7),
+            PixelblazeMessageType::GetProgramList
+        );
+        assert_eq!(
+            PixelblazeMessageType::from(2),
+            PixelblazeMessageType::Unknown(2)
+        );
+    }
+
+    #[test]
+    fn decodes_preview_frame() {
+        let PreviewFrame(frame) =
+            PreviewFrame::decode(&[5, 1, 2, 3, 4, 5, 6], Mapping::FirstN).unwrap();
+        assert_eq!(frame[0], Rgb { r: 1, g: 2, b: 3 });
+        assert_eq!(frame[1], Rgb { r: 4, g: 5, b: 6 });
+        assert!(frame[2..].iter().all(|p| *p == Rgb::default()));
+    }
+
+    #[test]
+    fn rejects_invalid_preview_frames() {
+        for payload in [&[5][..], &[5, 1, 2], &[7, 1, 2, 3], &[5, 1, 2, 3, 4]] {
+            assert_eq!(
+                PreviewFrame::decode(payload, Mapping::FirstN),
+                Err(PreviewFrameErr::Invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn encodes_commands() {
+        assert_eq!(
+            encode_set_active_pattern("GZm9WWMmrEGhaP5sr").unwrap(),
+            r#"{"setActivePattern":"GZm9WWMmrEGhaP5sr"}"#
+        );
+        assert_eq!(
+            encode_brightness(0.5).unwrap(),
+            r#"{"brightness":0.5,"save":false}"#
+        );
+
+        let mut controls = PatternControls::new();
+        controls
+            .insert("sliderSpeed".try_into().unwrap(), 0.25)
+            .unwrap();
+        controls.insert("hue\"".try_into().unwrap(), 1.0).unwrap();
+        assert_eq!(
+            encode_set_controls(&controls).unwrap(),
+            r#"{"setControls":{"sliderSpeed":0.25,"hue\"":1}}"#
+        );
+    }
+}
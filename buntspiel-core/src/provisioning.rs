@@ -0,0 +1,219 @@
+//! # Setup Form
+//!
+//! Decodes the form of the setup page the cube serves from its own access
+//! point when no known network is reachable. The form is posted as
+//! `application/x-www-form-urlencoded` with the fields:
+//! - `ssid`, `passphrase`: network to join, tried before all others
+//! - `pixelblaze`: Pixelblaze endpoint list (see [`crate::endpoint`]), empty
+//!   to keep the current one
+//! - `brightness`: LED brightness (0-255), empty to keep the current one
+//!
+//! Unknown fields are ignored.
+
+use heapless::String;
+
+use crate::config::Config;
+use crate::endpoint::{EndpointError, EndpointList};
+use crate::networks::{KnownNetwork, NetworkError, MAX_PASSPHRASE_LEN};
+
+/// Longest decoded field value, a passphrase.
+const MAX_VALUE_LEN: usize = MAX_PASSPHRASE_LEN;
+
+/// Setup form error types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SetupError {
+    /// Not a URL encoded form, or a value too long
+    Malformed,
+    /// SSID or passphrase invalid
+    Network(NetworkError),
+    /// Pixelblaze endpoints invalid
+    Endpoints(EndpointError),
+    /// Brightness not a number from 0 to 255
+    Brightness,
+}
+
+/// Apply a posted setup form to the current configuration.
+///
+/// The entered network gets a priority above all known networks and replaces
+/// a known network with the same SSID. If the list is full, the network with
+/// the lowest priority is forgotten.
+pub fn apply_setup_form(body: &[u8], current: &Config) -> Result<Config, SetupError> {
+    let body = core::str::from_utf8(body).map_err(|_| SetupError::Malformed)?;
+    let mut ssid = None;
+    let mut passphrase = None;
+    let mut pixelblaze = None;
+    let mut brightness = None;
+
+    for field in body.split('&').filter(|field| !field.is_empty()) {
+        let (name, value) = field.split_once('=').unwrap_or((field, ""));
+        let value = url_decode(value)?;
+        match name {
+            "ssid" => ssid = Some(value),
+            "passphrase" => passphrase = Some(value),
+            "pixelblaze" => pixelblaze = Some(value),
+            "brightness" => brightness = Some(value),
+            _ => {}
+        }
+    }
+
+    let mut config = current.clone();
+
+    let ssid = ssid.unwrap_or_default();
+    let ssid = ssid.trim();
+    let passphrase = passphrase.unwrap_or_default();
+    let priority = config
+        .networks
+        .iter()
+        .filter(|n| n.ssid != ssid)
+        .map(|n| n.priority)
+        .max()
+        .map_or(0, |max| max.saturating_add(1));
+    let network = KnownNetwork::new(ssid, &passphrase, priority).map_err(SetupError::Network)?;
+
+    config.networks.retain(|n| n.ssid != network.ssid);
+    if config.networks.is_full() {
+        let lowest = config
+            .networks
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, n)| n.priority)
+            .map(|(n, _)| n);
+        if let Some(n) = lowest {
+            config.networks.remove(n);
+        }
+    }
+    _ = config.networks.push(network);
+
+    if let Some(list) = pixelblaze.filter(|list| !list.trim().is_empty()) {
+        config.endpoints = EndpointList::parse(&list).map_err(SetupError::Endpoints)?;
+    }
+
+    if let Some(value) = brightness.filter(|value| !value.trim().is_empty()) {
+        config.brightness = value.trim().parse().map_err(|_| SetupError::Brightness)?;
+    }
+
+    Ok(config)
+}
+
+/// Decode `+` and `%XX` escapes of a form value.
+fn url_decode(value: &str) -> Result<String<MAX_VALUE_LEN>, SetupError> {
+    let mut bytes = heapless::Vec::<u8, MAX_VALUE_LEN>::new();
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        let decoded = match byte {
+            b'+' => b' ',
+            b'%' => {
+                let mut hex = || {
+                    input
+                        .next()
+                        .and_then(|digit| char::from(digit).to_digit(16))
+                        .ok_or(SetupError::Malformed)
+                };
+                (hex()? * 16 + hex()?) as u8
+            }
+            byte => byte,
+        };
+        bytes.push(decoded).map_err(|_| SetupError::Malformed)?;
+    }
+    String::from_utf8(bytes).map_err(|_| SetupError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::ACCESS_POINT;
+    use crate::networks::KnownNetworks;
+
+    fn current() -> Config {
+        Config {
+            networks: [
+                KnownNetwork::new("Testturm2", "12345678", 3),
+                KnownNetwork::new("Pixelblaze_Leuchtturm", "", 2),
+            ]
+            .into_iter()
+            .map(Result::unwrap)
+            .collect(),
+            endpoints: [ACCESS_POINT].into_iter().collect(),
+            brightness: 0x40,
+        }
+    }
+
+    fn ssids(networks: &KnownNetworks) -> heapless::Vec<(&str, u8), 4> {
+        networks
+            .iter()
+            .map(|n| (n.ssid.as_str(), n.priority))
+            .collect()
+    }
+
+    #[test]
+    fn applies_setup_form() {
+        let config = apply_setup_form(
+            b"ssid=Fusion+Crew&passphrase=p%C3%A4ss%26word%3D1&pixelblaze=10.0.0.42%2C+192.168.4.1&brightness=128",
+            &current(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            ssids(&config.networks),
+            [
+                ("Testturm2", 3),
+                ("Pixelblaze_Leuchtturm", 2),
+                ("Fusion Crew", 4)
+            ]
+        );
+        assert_eq!(config.networks[2].passphrase(), Some("päss&word=1"));
+        assert_eq!(
+            config.endpoints,
+            EndpointList::parse("10.0.0.42, 192.168.4.1").unwrap()
+        );
+        assert_eq!(config.brightness, 128);
+    }
+
+    #[test]
+    fn replaces_known_networks() {
+        // Same SSID: new passphrase, tried first, endpoints and brightness kept
+        let config = apply_setup_form(
+            b"ssid=Testturm2&passphrase=87654321&pixelblaze=&brightness=&submit=Save",
+            &current(),
+        )
+        .unwrap();
+        assert_eq!(
+            ssids(&config.networks),
+            [("Pixelblaze_Leuchtturm", 2), ("Testturm2", 3)]
+        );
+        assert_eq!(config.networks[1].passphrase(), Some("87654321"));
+        assert_eq!(config.endpoints, current().endpoints);
+        assert_eq!(config.brightness, 0x40);
+
+        // Full list: the lowest priority network is forgotten
+        let mut config = current();
+        for ssid in [&b"ssid=a"[..], b"ssid=b", b"ssid=c"] {
+            config = apply_setup_form(ssid, &config).unwrap();
+        }
+        assert_eq!(
+            ssids(&config.networks),
+            [("Testturm2", 3), ("a", 4), ("b", 5), ("c", 6)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_forms() {
+        let apply = |body: &[u8]| apply_setup_form(body, &current());
+        assert_eq!(
+            apply(b"passphrase=12345678"),
+            Err(SetupError::Network(NetworkError::InvalidSsid))
+        );
+        assert_eq!(
+            apply(b"ssid=x&passphrase=short"),
+            Err(SetupError::Network(NetworkError::InvalidPassphrase))
+        );
+        assert_eq!(
+            apply(b"ssid=x&pixelblaze=pixelblaze.local"),
+            Err(SetupError::Endpoints(EndpointError::Invalid))
+        );
+        assert_eq!(apply(b"ssid=x&brightness=256"), Err(SetupError::Brightness));
+        assert_eq!(apply(b"ssid=%4"), Err(SetupError::Malformed));
+        assert_eq!(apply(b"ssid=%FF"), Err(SetupError::Malformed));
+    }
+}
@@ -0,0 +1,473 @@
+//! # Pixelblaze Session
+//!
+//! Transport independent part of the Pixelblaze WebSocket client, shared by
+//! the firmware and the desktop simulator. The clients only move messages
+//! over their sockets and decide when to ask; the session tracks what
+//! Pixelblaze reported and what to send next:
+//! - which stored pattern to start when Pixelblaze is idle after connecting
+//! - which pattern a pattern key selects and whether Pixelblaze switched to it
+//! - the brightness after a brightness key
+//! - whether preview frames stopped and need a new subscription
+//!
+//! Messages from Pixelblaze go through [`Session::receive`], which dispatches
+//! them by type and leaves only logging and display to the client.
+
+use crate::frame::Frame;
+use crate::json::JsonError;
+use crate::pixelblaze_state::{PatternId, PixelblazeState, StateUpdate};
+use crate::preview::Mapping;
+use crate::program_list::{ProgramList, ProgramListError, ProgramListParser};
+use crate::protocol::{PixelblazeMessageType, PreviewFrame, PreviewFrameErr};
+
+/// When to start the first stored pattern if Pixelblaze isn't running one
+/// (ms after connecting), leaves time for the config and program list.
+pub const IDLE_CHECK_AFTER_MS: u64 = 1_500;
+
+/// Interval of the frame rate health check (ms).
+pub const MONITOR_INTERVAL_MS: u64 = 10_000;
+
+/// How long to wait for Pixelblaze to confirm a pattern switch (ms).
+pub const PATTERN_SWITCH_TIMEOUT_MS: u64 = 3_000;
+
+/// Pattern picked from the program list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PatternChoice {
+    /// The N-th pattern of the program list
+    Index(usize),
+    /// The pattern after the active one, wrapping around
+    Next,
+    /// The pattern before the active one, wrapping around
+    Previous,
+}
+
+/// Outcome of a pattern switch, see [`Session::switching_to`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternSwitch {
+    /// Pixelblaze reported the requested pattern as active
+    Confirmed(PatternId),
+    /// Pixelblaze kept running another pattern or didn't answer in time
+    Failed(PatternId),
+}
+
+/// Pattern switch waiting for Pixelblaze to report the pattern as active.
+struct PendingSwitch {
+    /// Requested pattern
+    id: PatternId,
+    /// When to give up on the switch (ms, client clock)
+    deadline_ms: u64,
+    /// Whether Pixelblaze reported the pattern as active since
+    confirmed: bool,
+}
+
+/// WebSocket message from Pixelblaze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message<'a> {
+    /// JSON text message
+    Text(&'a str),
+    /// Binary message, `[message_type: u8, ...data]`
+    Binary(&'a [u8]),
+}
+
+/// What a message from Pixelblaze changed.
+///
+/// Large on tiled layouts (a 16x8 frame is 384 bytes), which is fine: it's
+/// matched right after [`Session::receive`] and never stored, and the frame
+/// is moved on to the display anyway. Boxing it would need an allocator the
+/// firmware doesn't have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Received {
+    /// Preview frame mapped onto the matrix
+    Frame(Frame),
+    /// Text message applied to [`Session::state`]
+    State(StateUpdate),
+    /// Program list complete, now in [`Session::programs`]
+    ProgramList,
+    /// Frame of a program list, more frames to come
+    ProgramListPart,
+    /// Binary message of a type nothing handles
+    Unhandled(PixelblazeMessageType),
+}
+
+/// Malformed messages, skipped without changing the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReceiveError {
+    /// Binary message without message type
+    Empty,
+    /// Text message isn't valid JSON
+    Text(JsonError),
+    /// Preview frame with partial pixels or too short
+    PreviewFrame(PreviewFrameErr),
+    /// Program list frame out of order or invalid
+    ProgramList(ProgramListError),
+}
+
+/// Outcome of a frame rate health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HealthCheck {
+    /// Preview frames received since the previous check
+    pub frames: u64,
+    /// Whether to send `sendUpdates` again, no frames arrived
+    pub resubscribe: bool,
+}
+
+/// State of one Pixelblaze connection.
+#[derive(Default)]
+pub struct Session {
+    /// Last known Pixelblaze configuration and active pattern
+    pub state: PixelblazeState,
+    /// Patterns stored on the Pixelblaze (empty until the list arrived)
+    pub programs: ProgramList,
+    /// Decoder for the multi-frame program list
+    program_list_parser: ProgramListParser,
+    /// Total preview frames received since connecting
+    received_frames: u64,
+    /// Preview frames received at the previous health check
+    checked_frames: u64,
+    /// Pattern switch sent but not confirmed yet
+    pending_switch: Option<PendingSwitch>,
+}
+
+impl Session {
+    /// Process a message from Pixelblaze, mapping preview frames onto the
+    /// matrix with `mapping`.
+    pub fn receive(
+        &mut self,
+        message: Message<'_>,
+        mapping: Mapping,
+    ) -> Result<Received, ReceiveError> {
+        let payload = match message {
+            Message::Text(text) => {
+                let update = self.state.apply(text).map_err(ReceiveError::Text)?;
+                if update.active_program {
+                    self.confirm_switch();
+                }
+                return Ok(Received::State(update));
+            }
+            Message::Binary(payload) => payload,
+        };
+        let &message_type = payload.first().ok_or(ReceiveError::Empty)?;
+        match PixelblazeMessageType::from(message_type) {
+            PixelblazeMessageType::PreviewFrame => {
+                let PreviewFrame(frame) =
+                    PreviewFrame::decode(payload, mapping).map_err(ReceiveError::PreviewFrame)?;
+                self.received_frames += 1;
+                Ok(Received::Frame(frame))
+            }
+            PixelblazeMessageType::GetProgramList => {
+                let list = self
+                    .program_list_parser
+                    .feed(payload)
+                    .map_err(ReceiveError::ProgramList)?;
+                match list {
+                    Some(list) => {
+                        self.programs = list;
+                        Ok(Received::ProgramList)
+                    }
+                    None => Ok(Received::ProgramListPart),
+                }
+            }
+            t => Ok(Received::Unhandled(t)),
+        }
+    }
+
+    /// Total preview frames received since connecting.
+    pub fn received_frames(&self) -> u64 {
+        self.received_frames
+    }
+
+    /// ID of the pattern currently running on Pixelblaze (if known).
+    pub fn active_pattern_id(&self) -> Option<PatternId> {
+        let program = self.state.active_program.as_ref()?;
+        program.id.clone()
+    }
+
+    /// The first stored pattern if Pixelblaze isn't running one, e.g. after
+    /// `{"activeProgram":{"name":"","activeProgramId":null,"controls":{}},...}`.
+    pub fn idle_pattern(&self) -> Option<PatternId> {
+        match self.active_pattern_id() {
+            Some(_) => None,
+            None => self.programs.get(0).map(|p| p.id.clone()),
+        }
+    }
+
+    /// ID of the chosen pattern, `None` if the program list doesn't have it
+    /// (e.g. not received yet).
+    pub fn pattern(&self, choice: PatternChoice) -> Option<PatternId> {
+        let active = self.active_pattern_id();
+        let program = match choice {
+            PatternChoice::Index(index) => self.programs.get(index),
+            PatternChoice::Next => self.programs.next_after(active.as_deref()),
+            PatternChoice::Previous => self.programs.previous_before(active.as_deref()),
+        };
+        program.map(|p| p.id.clone())
+    }
+
+    /// Note a `setActivePattern` just sent at `now_ms`, replacing any switch
+    /// still pending.
+    ///
+    /// Reports of other patterns (e.g. the previous one) don't fail the
+    /// switch, only the timeout does.
+    pub fn switching_to(&mut self, id: PatternId, now_ms: u64) {
+        self.pending_switch = Some(PendingSwitch {
+            id,
+            deadline_ms: now_ms + PATTERN_SWITCH_TIMEOUT_MS,
+            confirmed: false,
+        });
+    }
+
+    /// Outcome of the pending pattern switch once known, checked after
+    /// received messages. `None` while Pixelblaze has time left to confirm.
+    pub fn pattern_switch(&mut self, now_ms: u64) -> Option<PatternSwitch> {
+        let pending = self.pending_switch.as_ref()?;
+        if !pending.confirmed && now_ms < pending.deadline_ms {
+            return None;
+        }
+        let pending = self.pending_switch.take()?;
+        Some(if pending.confirmed {
+            PatternSwitch::Confirmed(pending.id)
+        } else {
+            PatternSwitch::Failed(pending.id)
+        })
+    }
+
+    /// Mark the pending switch confirmed if the active pattern is the
+    /// requested one.
+    fn confirm_switch(&mut self) {
+        let active = self.active_pattern_id();
+        if let Some(pending) = &mut self.pending_switch {
+            pending.confirmed |= active.as_deref() == Some(pending.id.as_str());
+        }
+    }
+
+    /// Change the brightness by `delta` (0.0-1.0 scale) and return the new
+    /// one to send.
+    ///
+    /// Pixelblaze doesn't echo brightness changes, so they are tracked here.
+    /// Until Pixelblaze reported one, full brightness is assumed.
+    pub fn adjust_brightness(&mut self, delta: f32) -> f32 {
+        let brightness = (self.state.brightness.unwrap_or(1.0) + delta).clamp(0.0, 1.0);
+        self.state.brightness = Some(brightness);
+        brightness
+    }
+
+    /// Check the frame rate, every [`MONITOR_INTERVAL_MS`].
+    pub fn check_frame_rate(&mut self) -> HealthCheck {
+        let frames = self.received_frames - self.checked_frames;
+        self.checked_frames = self.received_frames;
+        HealthCheck {
+            frames,
+            resubscribe: frames == 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Rgb;
+
+    const PROGRAM_LIST: &[u8] =
+        b"\x07\x05kuJfFyCSkCKNasyNE\tblink fade\nWt2xjGTu3oD9zdn6S\trainbow\n";
+
+    const RAINBOW: &str =
+        r#"{"activeProgram":{"name":"rainbow","activeProgramId":"Wt2xjGTu3oD9zdn6S"}}"#;
+
+    fn receive(session: &mut Session, message: Message<'_>) -> Result<Received, ReceiveError> {
+        session.receive(message, Mapping::FirstN)
+    }
+
+    fn session() -> Session {
+        let mut session = Session::default();
+        let list = receive(&mut session, Message::Binary(PROGRAM_LIST));
+        assert_eq!(list, Ok(Received::ProgramList));
+        session
+    }
+
+    #[test]
+    fn dispatches_messages() {
+        let mut session = Session::default();
+
+        let Ok(Received::Frame(frame)) = receive(&mut session, Message::Binary(&[5, 1, 2, 3]))
+        else {
+            panic!("preview frame not decoded");
+        };
+        assert_eq!(frame[0], Rgb { r: 1, g: 2, b: 3 });
+        assert_eq!(session.received_frames(), 1);
+
+        let update = receive(&mut session, Message::Text(RAINBOW));
+        assert!(matches!(update, Ok(Received::State(u)) if u.active_program));
+        assert_eq!(
+            session.active_pattern_id().as_deref(),
+            Some("Wt2xjGTu3oD9zdn6S")
+        );
+
+        let first = b"\x07\x01kuJfFyCSkCKNasyNE\tblink";
+        let last = b"\x07\x04 fade\n";
+        let part = receive(&mut session, Message::Binary(first));
+        assert_eq!(part, Ok(Received::ProgramListPart));
+        assert!(session.programs.is_empty());
+        let list = receive(&mut session, Message::Binary(last));
+        assert_eq!(list, Ok(Received::ProgramList));
+        assert_eq!(session.programs.get(0).unwrap().name, "blink fade");
+
+        assert_eq!(
+            receive(&mut session, Message::Binary(&[9, 0])),
+            Ok(Received::Unhandled(PixelblazeMessageType::ExpanderConfig))
+        );
+    }
+
+    #[test]
+    fn skips_malformed_messages() {
+        let mut session = session();
+        receive(&mut session, Message::Text(RAINBOW)).unwrap();
+
+        let malformed = [
+            (Message::Binary(&[]), ReceiveError::Empty),
+            (
+                Message::Text("{\"brightness\":"),
+                ReceiveError::Text(JsonError::UnexpectedEnd),
+            ),
+            (
+                Message::Binary(&[5, 1, 2, 3, 4]),
+                ReceiveError::PreviewFrame(PreviewFrameErr::Invalid),
+            ),
+            (
+                Message::Binary(&[7, 4, b'x']),
+                ReceiveError::ProgramList(ProgramListError::UnexpectedFrame),
+            ),
+            (
+                Message::Binary(&[7]),
+                ReceiveError::ProgramList(ProgramListError::Invalid),
+            ),
+        ];
+        for (message, error) in malformed {
+            assert_eq!(receive(&mut session, message), Err(error), "{:?}", message);
+        }
+
+        // Nothing changed, the session keeps working
+        assert_eq!(session.programs.len(), 2);
+        assert_eq!(session.received_frames(), 0);
+        assert_eq!(
+            session.active_pattern_id().as_deref(),
+            Some("Wt2xjGTu3oD9zdn6S")
+        );
+        let frame = receive(&mut session, Message::Binary(&[5, 1, 2, 3]));
+        assert!(matches!(frame, Ok(Received::Frame(_))));
+    }
+
+    #[test]
+    fn starts_first_pattern_when_idle() {
+        let mut session = Session::default();
+        // Program list not received yet
+        assert_eq!(session.idle_pattern(), None);
+
+        session = self::session();
+        assert_eq!(session.idle_pattern().as_deref(), Some("kuJfFyCSkCKNasyNE"));
+        let idle = r#"{"activeProgram":{"name":"","activeProgramId":null}}"#;
+        receive(&mut session, Message::Text(idle)).unwrap();
+        assert_eq!(session.idle_pattern().as_deref(), Some("kuJfFyCSkCKNasyNE"));
+
+        receive(&mut session, Message::Text(RAINBOW)).unwrap();
+        assert_eq!(session.idle_pattern(), None);
+    }
+
+    #[test]
+    fn chooses_patterns_from_program_list() {
+        let mut session = session();
+        assert_eq!(
+            session.pattern(PatternChoice::Index(1)).as_deref(),
+            Some("Wt2xjGTu3oD9zdn6S")
+        );
+        assert_eq!(session.pattern(PatternChoice::Index(2)), None);
+        // Nothing active, start at either end
+        assert_eq!(
+            session.pattern(PatternChoice::Next).as_deref(),
+            Some("kuJfFyCSkCKNasyNE")
+        );
+        assert_eq!(
+            session.pattern(PatternChoice::Previous).as_deref(),
+            Some("Wt2xjGTu3oD9zdn6S")
+        );
+
+        receive(&mut session, Message::Text(RAINBOW)).unwrap();
+        assert_eq!(
+            session.pattern(PatternChoice::Next).as_deref(),
+            Some("kuJfFyCSkCKNasyNE")
+        );
+        assert_eq!(
+            session.pattern(PatternChoice::Previous).as_deref(),
+            Some("kuJfFyCSkCKNasyNE")
+        );
+        assert_eq!(Session::default().pattern(PatternChoice::Next), None);
+    }
+
+    #[test]
+    fn confirms_pattern_switch() {
+        let mut session = session();
+        let rainbow = PatternId::try_from("Wt2xjGTu3oD9zdn6S").unwrap();
+        let blink =
+            r#"{"activeProgram":{"name":"blink fade","activeProgramId":"kuJfFyCSkCKNasyNE"}}"#;
+        assert_eq!(session.pattern_switch(0), None);
+
+        session.switching_to(rainbow.clone(), 1_000);
+        // Still reporting the previous pattern
+        receive(&mut session, Message::Text(blink)).unwrap();
+        assert_eq!(session.pattern_switch(1_100), None);
+        receive(&mut session, Message::Text(RAINBOW)).unwrap();
+        assert_eq!(
+            session.pattern_switch(1_200),
+            Some(PatternSwitch::Confirmed(rainbow.clone()))
+        );
+        assert_eq!(session.pattern_switch(5_000), None);
+
+        session.switching_to(rainbow.clone(), 10_000);
+        receive(&mut session, Message::Text(blink)).unwrap();
+        assert_eq!(
+            session.pattern_switch(10_000 + PATTERN_SWITCH_TIMEOUT_MS - 1),
+            None
+        );
+        assert_eq!(
+            session.pattern_switch(10_000 + PATTERN_SWITCH_TIMEOUT_MS),
+            Some(PatternSwitch::Failed(rainbow))
+        );
+    }
+
+    #[test]
+    fn tracks_brightness() {
+        let mut session = Session::default();
+        assert_eq!(session.adjust_brightness(-0.25), 0.75);
+        assert_eq!(session.adjust_brightness(0.5), 1.0);
+
+        receive(&mut session, Message::Text(r#"{"brightness":0.1}"#)).unwrap();
+        assert_eq!(session.adjust_brightness(-0.25), 0.0);
+        assert_eq!(session.state.brightness, Some(0.0));
+    }
+
+    #[test]
+    fn resubscribes_when_frames_stop() {
+        let mut session = Session::default();
+        for _ in 0..3 {
+            receive(&mut session, Message::Binary(&[5, 1, 2, 3])).unwrap();
+        }
+        assert_eq!(session.received_frames(), 3);
+        assert_eq!(
+            session.check_frame_rate(),
+            HealthCheck {
+                frames: 3,
+                resubscribe: false
+            }
+        );
+        assert_eq!(
+            session.check_frame_rate(),
+            HealthCheck {
+                frames: 0,
+                resubscribe: true
+            }
+        );
+    }
+}
@@ -0,0 +1,702 @@
+//! # Pixelblaze WebSocket Client (std)
+//!
+//! Host counterpart of the firmware's Pixelblaze client: the same protocol
+//! handling from `buntspiel_core` on top of `std` networking and a blocking
+//! `tungstenite` WebSocket.
+//!
+//! The client runs on its own thread. It receives [`Command`]s from the
+//! terminal UI and reports back with [`Event`]s.
+
+use std::fmt;
+use std::io;
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use buntspiel_core::backoff::Backoff;
+use buntspiel_core::endpoint::{Endpoint, EndpointList};
+use buntspiel_core::frame::Frame;
+use buntspiel_core::pixelblaze_state::PatternId;
+use buntspiel_core::preview::Mapping;
+use buntspiel_core::protocol::{
+    encode_brightness, encode_set_active_pattern, GET_CONFIG, LIST_PROGRAMS, SEND_UPDATES, UNPAUSE,
+};
+use buntspiel_core::session::{
+    self, PatternChoice, PatternSwitch, ReceiveError, Received, Session, IDLE_CHECK_AFTER_MS,
+    MONITOR_INTERVAL_MS,
+};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::HeaderValue;
+use tungstenite::{Message, WebSocket};
+
+/// How long to wait for an endpoint before trying the next one.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay between connection attempts, same as the firmware.
+const RECONNECT_BACKOFF: Backoff = Backoff::new(1_000, 60_000, 25);
+
+/// How long a read may block before pending commands are sent.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Commands from the terminal UI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Command {
+    /// Switch to the N-th pattern of the program list
+    SelectPattern(usize),
+    /// Switch to the pattern after the active one in the program list
+    NextPattern,
+    /// Switch to the pattern before the active one in the program list
+    PreviousPattern,
+    /// Change the global Pixelblaze brightness by the given amount (0.0-1.0 scale)
+    AdjustBrightness(f32),
+    /// Map following preview frames with the given strategy
+    SetMapping(Mapping),
+    /// Close the WebSocket connection gracefully
+    Close,
+}
+
+/// Updates for the terminal UI.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Event {
+    /// WebSocket connection established at the endpoint
+    Connected(Endpoint),
+    /// Connection lost, reconnecting
+    Disconnected,
+    /// Preview frame mapped onto the matrix
+    Frame(Box<Frame>),
+    /// Name of the pattern Pixelblaze reports as active
+    ActivePattern(String),
+    /// Log message
+    Log(String),
+}
+
+/// Pixelblaze client error types
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// Network or WebSocket error
+    WebSocket(tungstenite::Error),
+    /// Command didn't fit its message buffer
+    Encode,
+    /// Connection was closed (graceful or by remote)
+    Close,
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(e: tungstenite::Error) -> Self {
+        match e {
+            tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+                Error::Close
+            }
+            e => Error::WebSocket(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::WebSocket(e.into())
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Self {
+        Error::Encode
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::WebSocket(e) => write!(f, "{}", e),
+            Error::Encode => f.write_str("command too long"),
+            Error::Close => f.write_str("connection closed"),
+        }
+    }
+}
+
+/// Connect to Pixelblaze at the first endpoint accepting the connection
+/// until the UI quits, reconnecting after failures.
+pub(crate) fn run(
+    endpoints: &EndpointList,
+    mut mapping: Mapping,
+    commands: Receiver<Command>,
+    events: Sender<Event>,
+) {
+    let mut backoff = RECONNECT_BACKOFF;
+    loop {
+        for endpoint in endpoints.iter() {
+            log(
+                &events,
+                format!(
+                    "pixelblaze: 🔌 Attempting connection to Pixelblaze at {}...",
+                    endpoint
+                ),
+            );
+            match PixelStreamer::connect(endpoint, mapping) {
+                Ok(mut pixel_streamer) => {
+                    log(
+                        &events,
+                        "pixelblaze: ✅ WebSocket connection established!".into(),
+                    );
+                    _ = events.send(Event::Connected(*endpoint));
+                    backoff.reset();
+
+                    let result = pixel_streamer.communicate(&commands, &events);
+                    mapping = pixel_streamer.mapping;
+                    match result {
+                        Ok(()) | Err(Error::Close) => {
+                            log(&events, "pixelblaze: 🔌 Connection terminated".into())
+                        }
+                        Err(e) => log(
+                            &events,
+                            format!("pixelblaze: ❌ WebSocket communication failed: {}", e),
+                        ),
+                    }
+                    _ = events.send(Event::Disconnected);
+                    break;
+                }
+                Err(e) => log(
+                    &events,
+                    format!(
+                        "pixelblaze: ❌ Failed to establish WebSocket connection to {}: {}",
+                        endpoint, e
+                    ),
+                ),
+            }
+        }
+
+        // Wait longer after every failed attempt in a row
+        let delay = backoff.next_delay_ms(random());
+        let reconnecting = format!("pixelblaze: 🔄 Reconnecting in {} ms...", delay);
+        // Nobody is listening anymore once the UI quit
+        if events.send(Event::Log(reconnecting)).is_err() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(delay));
+    }
+}
+
+/// WebSocket client for a single Pixelblaze connection.
+pub(crate) struct PixelStreamer {
+    /// Established WebSocket
+    socket: WebSocket<TcpStream>,
+    /// Pixelblaze state, program list and frame statistics
+    session: Session,
+    /// Strategy used for new preview frames
+    mapping: Mapping,
+    /// When the connection was established, the session's clock starts here
+    connected: Instant,
+}
+
+impl PixelStreamer {
+    /// Establish WebSocket connection to Pixelblaze.
+    pub(crate) fn connect(endpoint: &Endpoint, mapping: Mapping) -> Result<Self, Error> {
+        let stream = TcpStream::connect_timeout(&endpoint.addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+
+        // Pixelblaze expects the origin of its own web UI
+        let mut request = format!("ws://{}/", endpoint.host()).into_client_request()?;
+        if let Ok(origin) = HeaderValue::from_str(&endpoint.origin()) {
+            request.headers_mut().insert("Origin", origin);
+        }
+        let (socket, _) = tungstenite::client(request, stream).map_err(|e| match e {
+            tungstenite::HandshakeError::Failure(e) => Error::from(e),
+            // Read timed out
+            tungstenite::HandshakeError::Interrupted(_) => {
+                Error::from(io::Error::from(io::ErrorKind::TimedOut))
+            }
+        })?;
+
+        // Wake up regularly to send pending commands
+        socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+        Ok(PixelStreamer {
+            socket,
+            session: Session::default(),
+            mapping,
+            connected: Instant::now(),
+        })
+    }
+
+    /// Main WebSocket communication loop.
+    ///
+    /// Returns when the connection is closed or fails.
+    pub(crate) fn communicate(
+        &mut self,
+        commands: &Receiver<Command>,
+        events: &Sender<Event>,
+    ) -> Result<(), Error> {
+        // Clear any stale commands from previous connections
+        while commands.try_recv().is_ok() {}
+
+        self.send_text(GET_CONFIG, events)?;
+        self.send_text(LIST_PROGRAMS, events)?;
+
+        let mut idle_checked = false;
+        let mut last_monitor = Instant::now();
+
+        loop {
+            while let Ok(command) = commands.try_recv() {
+                self.handle_command(command, events)?;
+            }
+
+            // Start the first stored pattern if Pixelblaze isn't running one
+            if !idle_checked && self.now_ms() >= IDLE_CHECK_AFTER_MS {
+                idle_checked = true;
+                if let Some(id) = self.session.idle_pattern() {
+                    log(
+                        events,
+                        format!("pixelblaze: 💤 No active pattern, starting {}", id),
+                    );
+                    self.set_active_pattern(&id, events)?;
+                }
+            }
+
+            // Health check: if frame rate is too low, resubscribe
+            if last_monitor.elapsed() >= Duration::from_millis(MONITOR_INTERVAL_MS) {
+                last_monitor = Instant::now();
+                if self.session.check_frame_rate().resubscribe {
+                    log(
+                        events,
+                        "pixelblaze: ⚠️  Low frame rate (resubscribing)".into(),
+                    );
+                    self.send_text(SEND_UPDATES, events)?;
+                }
+            }
+
+            match self.socket.read() {
+                Ok(Message::Text(text)) => {
+                    self.handle_message(session::Message::Text(text.as_str()), events)
+                }
+                Ok(Message::Binary(payload)) => {
+                    self.handle_message(session::Message::Binary(&payload), events)
+                }
+                // Pongs are queued by tungstenite, flushed with the next write
+                Ok(Message::Ping(_)) => self.socket.flush()?,
+                Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
+                Ok(Message::Close(_)) => {
+                    log(events, "pixelblaze: 👋 Server closing connection".into());
+                    // Send the close reply queued by tungstenite
+                    _ = self.socket.flush();
+                    return Err(Error::Close);
+                }
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(e.into()),
+            }
+            self.check_pattern_switch(events);
+        }
+    }
+
+    /// Send a command from the terminal UI.
+    fn handle_command(&mut self, command: Command, events: &Sender<Event>) -> Result<(), Error> {
+        let choice = match command {
+            Command::SelectPattern(index) => PatternChoice::Index(index),
+            Command::NextPattern => PatternChoice::Next,
+            Command::PreviousPattern => PatternChoice::Previous,
+            Command::AdjustBrightness(delta) => {
+                let brightness = self.session.adjust_brightness(delta);
+                log(
+                    events,
+                    format!("pixelblaze: 🔆 Setting brightness to {}", brightness),
+                );
+                return self.send_text(&encode_brightness(brightness)?, events);
+            }
+            Command::SetMapping(mapping) => {
+                self.mapping = mapping;
+                return Ok(());
+            }
+            Command::Close => {
+                log(events, "pixelblaze: 👋 Sending close frame".into());
+                self.socket.close(None)?;
+                self.socket.flush()?;
+                return Err(Error::Close);
+            }
+        };
+
+        match self.session.pattern(choice) {
+            Some(id) => self.set_active_pattern(&id, events),
+            None => {
+                log(
+                    events,
+                    "pixelblaze: ⚠️  Pattern not in program list (not received yet?)".into(),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Switch Pixelblaze to a pattern. The switch is confirmed by the
+    /// `activeProgram` message that follows.
+    fn set_active_pattern(&mut self, id: &PatternId, events: &Sender<Event>) -> Result<(), Error> {
+        log(
+            events,
+            format!("pixelblaze: 🎨 Setting active pattern {}", id),
+        );
+        self.session.switching_to(id.clone(), self.now_ms());
+        self.send_text(&encode_set_active_pattern(id)?, events)?;
+        self.send_text(UNPAUSE, events)?;
+        self.send_text(GET_CONFIG, events)
+    }
+
+    /// Log the outcome of a pattern switch once Pixelblaze confirmed it or
+    /// the time is up.
+    fn check_pattern_switch(&mut self, events: &Sender<Event>) {
+        match self.session.pattern_switch(self.now_ms()) {
+            Some(PatternSwitch::Confirmed(id)) => log(
+                events,
+                format!("pixelblaze: ✅ Pattern switch to {} confirmed", id),
+            ),
+            Some(PatternSwitch::Failed(id)) => log(
+                events,
+                format!(
+                    "pixelblaze: ❌ Pattern switch to {} failed, still running {:?}",
+                    id,
+                    self.session.active_pattern_id().as_deref(),
+                ),
+            ),
+            None => {}
+        }
+    }
+
+    /// Milliseconds since connecting.
+    fn now_ms(&self) -> u64 {
+        self.connected.elapsed().as_millis() as u64
+    }
+
+    /// Process a message from Pixelblaze.
+    fn handle_message(&mut self, message: session::Message<'_>, events: &Sender<Event>) {
+        match self.session.receive(message, self.mapping) {
+            Ok(Received::Frame(frame)) => _ = events.send(Event::Frame(Box::new(frame))),
+            // High-frequency FPS status messages are applied silently
+            Ok(Received::State(update))
+                if update.status && !update.config && !update.active_program => {}
+            Ok(Received::State(update)) if update.active_program => {
+                let name = match &self.session.state.active_program {
+                    Some(program) => program.name.to_string(),
+                    None => String::new(),
+                };
+                log(events, format!("pixelblaze: 🎨 Active pattern: '{}'", name));
+                _ = events.send(Event::ActivePattern(name));
+            }
+            Ok(Received::State(update)) if update.config => log(
+                events,
+                format!(
+                    "pixelblaze: ⚙️  Config: name={:?} ver={:?} pixels={:?} brightness={:?}",
+                    self.session.state.name.as_deref(),
+                    self.session.state.version.as_deref(),
+                    self.session.state.pixel_count,
+                    self.session.state.brightness,
+                ),
+            ),
+            Ok(Received::ProgramList) => log(
+                events,
+                format!(
+                    "pixelblaze: 📚 Received program list with {} patterns ({} dropped)",
+                    self.session.programs.len(),
+                    self.session.programs.dropped,
+                ),
+            ),
+            Ok(Received::State(_) | Received::ProgramListPart | Received::Unhandled(_)) => {}
+            Err(ReceiveError::Empty) => log(events, "pixelblaze: ⚠️  Empty binary message".into()),
+            Err(ReceiveError::Text(e)) => log(
+                events,
+                format!("pixelblaze: ⚠️  Failed to parse text message ({:?})", e),
+            ),
+            Err(ReceiveError::PreviewFrame(e)) => log(
+                events,
+                format!("pixelblaze: ❌ Failed to parse preview frame: {:?}", e),
+            ),
+            Err(ReceiveError::ProgramList(e)) => log(
+                events,
+                format!("pixelblaze: ⚠️  Invalid program list frame: {:?}", e),
+            ),
+        }
+    }
+
+    /// Send a text message to Pixelblaze.
+    fn send_text(&mut self, message: &str, events: &Sender<Event>) -> Result<(), Error> {
+        log(events, format!("pixelblaze: 📤 Sending: {}", message));
+        self.socket.send(Message::text(message))?;
+        Ok(())
+    }
+}
+
+/// Random number for the reconnect jitter.
+fn random() -> u32 {
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+    now.map(|t| t.subsec_nanos()).unwrap_or_default()
+}
+
+/// Report a log message to the terminal UI.
+fn log(events: &Sender<Event>, message: String) {
+    _ = events.send(Event::Log(message));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    use buntspiel_core::frame::Rgb;
+    use buntspiel_core::layout::NEOTRELLIS_PIXELS;
+
+    use super::*;
+    use crate::mock_pixelblaze::{MockPixelblaze, Step, PATTERNS};
+
+    const RED: Rgb = Rgb {
+        r: 0xFF,
+        g: 0,
+        b: 0,
+    };
+    const GREEN: Rgb = Rgb {
+        r: 0,
+        g: 0xFF,
+        b: 0,
+    };
+
+    /// How long to wait for an event from the client.
+    const EVENT_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// Run one connection against the mock until it ends.
+    fn communicate(mock: &MockPixelblaze) -> (Result<(), Error>, Vec<Event>) {
+        let mut pixel_streamer = PixelStreamer::connect(&mock.endpoint(), Mapping::FirstN).unwrap();
+        let (_commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let result = pixel_streamer.communicate(&command_receiver, &event_sender);
+        (result, events.try_iter().collect())
+    }
+
+    /// Collect events until one matches.
+    fn wait_for(events: &Receiver<Event>, done: impl Fn(&Event) -> bool) -> Vec<Event> {
+        let mut received = Vec::new();
+        loop {
+            let event = events.recv_timeout(EVENT_TIMEOUT).unwrap();
+            let last = done(&event);
+            received.push(event);
+            if last {
+                return received;
+            }
+        }
+    }
+
+    fn frames(events: &[Event]) -> Vec<Frame> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Frame(frame) => Some(**frame),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn logged(events: &[Event], text: &str) -> bool {
+        events
+            .iter()
+            .any(|event| matches!(event, Event::Log(message) if message.contains(text)))
+    }
+
+    #[test]
+    fn receives_preview_frames() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("getConfig"),
+            Step::Expect("listPrograms"),
+            Step::Frames {
+                count: 2,
+                color: RED,
+            },
+            Step::Close,
+        ]]);
+
+        let (result, events) = communicate(&mock);
+        assert!(matches!(result, Err(Error::Close)));
+        assert_eq!(frames(&events), [[RED; NEOTRELLIS_PIXELS]; 2]);
+        assert!(events.contains(&Event::ActivePattern(String::new())));
+        assert!(logged(&events, "Received program list with 3 patterns"));
+        assert_eq!(
+            mock.finish(),
+            [r#"{"getConfig":true}"#, r#"{"listPrograms":true}"#]
+        );
+    }
+
+    #[test]
+    fn starts_first_pattern_when_idle() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            Step::Expect(r#"{"setActivePattern":"kuJfFyCSkCKNasyNE"}"#),
+            Step::Expect(r#"{"pause":false}"#),
+            Step::Close,
+        ]]);
+
+        let (result, events) = communicate(&mock);
+        assert!(matches!(result, Err(Error::Close)));
+        assert!(events.contains(&Event::ActivePattern(PATTERNS[0].1.into())));
+        assert!(logged(
+            &events,
+            "Pattern switch to kuJfFyCSkCKNasyNE confirmed"
+        ));
+        mock.finish();
+    }
+
+    #[test]
+    fn selects_patterns_from_program_list() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            Step::Frames {
+                count: 1,
+                color: RED,
+            },
+            Step::Expect(r#"{"setActivePattern":"Wt2xjGTu3oD9zdn6S"}"#),
+            Step::AwaitClose,
+        ]]);
+        let mut pixel_streamer = PixelStreamer::connect(&mock.endpoint(), Mapping::FirstN).unwrap();
+        let (commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let client =
+            thread::spawn(move || pixel_streamer.communicate(&command_receiver, &event_sender));
+
+        // The program list arrived before the first frame
+        wait_for(&events, |event| matches!(event, Event::Frame(_)));
+        commands.send(Command::SelectPattern(1)).unwrap();
+        let name = Event::ActivePattern(PATTERNS[1].1.into());
+        wait_for(&events, |event| *event == name);
+        commands.send(Command::Close).unwrap();
+
+        assert!(matches!(client.join().unwrap(), Err(Error::Close)));
+        mock.finish();
+    }
+
+    #[test]
+    fn answers_pings() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            Step::Ping,
+            Step::Frames {
+                count: 1,
+                color: GREEN,
+            },
+            Step::Close,
+        ]]);
+
+        let (result, events) = communicate(&mock);
+        assert!(matches!(result, Err(Error::Close)));
+        assert_eq!(frames(&events), [[GREEN; NEOTRELLIS_PIXELS]]);
+        mock.finish();
+    }
+
+    #[test]
+    fn skips_malformed_messages() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            // No message type
+            Step::Binary(vec![]),
+            // Partial pixel
+            Step::Binary(vec![5, 0xFF, 0, 0, 0xFF]),
+            // Last frame of a program list that never started
+            Step::Binary(vec![7, 4]),
+            Step::Text(r#"{"activeProgram":{"name":"#),
+            Step::Frames {
+                count: 1,
+                color: GREEN,
+            },
+            Step::Close,
+        ]]);
+
+        let (result, events) = communicate(&mock);
+        assert!(matches!(result, Err(Error::Close)));
+        assert_eq!(frames(&events), [[GREEN; NEOTRELLIS_PIXELS]]);
+        assert!(logged(&events, "Empty binary message"));
+        assert!(logged(&events, "Failed to parse preview frame"));
+        assert!(logged(&events, "Invalid program list frame"));
+        assert!(logged(&events, "Failed to parse text message"));
+        mock.finish();
+    }
+
+    #[test]
+    fn resubscribes_when_frames_stop() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            Step::PauseUpdates,
+            Step::Expect(r#"{"sendUpdates":true}"#),
+            Step::Frames {
+                count: 1,
+                color: GREEN,
+            },
+            Step::Close,
+        ]]);
+
+        let (result, events) = communicate(&mock);
+        assert!(matches!(result, Err(Error::Close)));
+        assert_eq!(frames(&events), [[GREEN; NEOTRELLIS_PIXELS]]);
+        assert!(logged(&events, "Low frame rate"));
+        mock.finish();
+    }
+
+    #[test]
+    fn fails_on_protocol_violation() {
+        let mock = MockPixelblaze::start(vec![vec![
+            Step::Expect("listPrograms"),
+            // Text frame with a reserved opcode
+            Step::Garbage(&[0x83, 0x00]),
+        ]]);
+
+        let (result, _) = communicate(&mock);
+        assert!(matches!(result, Err(Error::WebSocket(_))));
+        mock.finish();
+    }
+
+    #[test]
+    fn reconnects_through_fallback_endpoints() {
+        // Nothing listens on the first endpoint
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let refused = Endpoint {
+            addr: listener.local_addr().unwrap(),
+        };
+        drop(listener);
+
+        let mock = MockPixelblaze::start(vec![
+            // Dropped without a close frame
+            vec![Step::Expect("listPrograms")],
+            vec![
+                Step::Expect("listPrograms"),
+                Step::Frames {
+                    count: 1,
+                    color: GREEN,
+                },
+                Step::AwaitClose,
+            ],
+        ]);
+        let endpoints = [refused, mock.endpoint()].into_iter().collect();
+        let (commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let client =
+            thread::spawn(move || run(&endpoints, Mapping::FirstN, command_receiver, event_sender));
+
+        let received = wait_for(&events, |event| matches!(event, Event::Frame(_)));
+        let connection: Vec<&Event> = received
+            .iter()
+            .filter(|event| !matches!(event, Event::Log(_) | Event::ActivePattern(_)))
+            .collect();
+        assert_eq!(
+            connection,
+            [
+                &Event::Connected(mock.endpoint()),
+                &Event::Disconnected,
+                &Event::Connected(mock.endpoint()),
+                &Event::Frame(Box::new([GREEN; NEOTRELLIS_PIXELS])),
+            ]
+        );
+        let failure = format!("Failed to establish WebSocket connection to {}", refused);
+        assert!(logged(&received, &failure));
+
+        // Nobody listening stops the client after the connection closed
+        drop(events);
+        commands.send(Command::Close).unwrap();
+        client.join().unwrap();
+        mock.finish();
+    }
+}
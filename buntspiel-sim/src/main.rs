@@ -0,0 +1,331 @@
+//! # Buntspiel Simulator
+//!
+//! Desktop stand-in for the companion cube. Connects to a Pixelblaze with the
+//! protocol handling of the firmware (`buntspiel_core`) and shows the matrix
+//! in the terminal, with keyboard keys standing in for the NeoTrellis buttons.
+//!
+//! ```text
+//! buntspiel-sim [--pixel-map pixelmap.dat] [ip:port,...]
+//! ```
+//!
+//! Like the firmware, the simulator tries a comma separated list of
+//! endpoints in order, by default the Pixelblaze access point.
+//!
+//! The matrix layout follows the `tiles-*` features, like the firmware.
+
+mod client; // Pixelblaze WebSocket client on std networking
+#[cfg(test)]
+mod mock_pixelblaze; // Scriptable Pixelblaze stand-in for the client tests
+mod terminal; // Truecolor matrix and keyboard input
+
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use std::{env, fs, io, thread};
+
+use buntspiel_core::animation::{WAIT_ANIMATION, WAIT_ANIMATION_FRAME_MS};
+use buntspiel_core::color::ColorPipeline;
+use buntspiel_core::endpoint::{Endpoint, EndpointList, ACCESS_POINT};
+use buntspiel_core::frame::{Frame, Rgb};
+use buntspiel_core::keymap::{ButtonEvent, KeyAction, Keypad, SHIFT_KEY};
+use buntspiel_core::layout::{MATRIX_COLUMNS, MATRIX_ROWS, NEOTRELLIS_PIXELS};
+use buntspiel_core::pixel_map::{Axis, Projection, ProjectionTable};
+use buntspiel_core::preview::Mapping;
+
+use client::{Command, Event};
+use terminal::{Input, Terminal};
+
+/// Endpoints tried when none are given.
+const DEFAULT_ENDPOINTS: [Endpoint; 1] = [ACCESS_POINT];
+
+/// Strategy used without a pixel map.
+const DEFAULT_MAPPING: Mapping = Mapping::Averaged;
+
+/// How the pixel map is flattened onto the matrix.
+const PROJECTION: Projection = Projection::Cylindrical { vertical: Axis::Z };
+
+/// Brightness change per press of a brightness key (0.0-1.0 scale).
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// Brightness change per press of a matrix brightness key (0-255 scale).
+const MATRIX_BRIGHTNESS_STEP: i16 = 16;
+
+/// Color shown on a key right after it was pressed.
+const PRESSED_COLOR: Rgb = Rgb {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+
+/// White balance of the simulated LEDs (none).
+const NEUTRAL_BALANCE: Rgb = Rgb {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+
+/// How long a pressed key stays lit (terminals don't report key releases).
+const PRESSED_DURATION: Duration = Duration::from_millis(150);
+
+/// How often the matrix is redrawn and the keyboard polled.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(20);
+
+fn main() -> ExitCode {
+    let mut endpoints = DEFAULT_ENDPOINTS.into_iter().collect();
+    let mut pixel_map = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pixel-map" => pixel_map = args.next(),
+            "-h" | "--help" => {
+                println!("usage: buntspiel-sim [--pixel-map pixelmap.dat] [ip:port,...]");
+                return ExitCode::SUCCESS;
+            }
+            _ => match EndpointList::parse(&arg) {
+                Ok(list) if !list.is_empty() => endpoints = list,
+                _ => {
+                    eprintln!("Invalid endpoints: {}", arg);
+                    return ExitCode::FAILURE;
+                }
+            },
+        }
+    }
+
+    // Project the lighthouse pixel map (if given) before frames arrive
+    let projection = match pixel_map.map(|path| load_projection(&path)).transpose() {
+        Ok(projection) => projection,
+        Err(e) => {
+            eprintln!("Invalid pixel map: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mapping = projection
+        .map(Mapping::Projected)
+        .unwrap_or(DEFAULT_MAPPING);
+
+    let (command_sender, command_receiver) = mpsc::channel();
+    let (event_sender, event_receiver) = mpsc::channel();
+    let client_endpoints = endpoints.clone();
+    thread::spawn(move || client::run(&client_endpoints, mapping, command_receiver, event_sender));
+
+    let mut simulator = Simulator::new(endpoints, mapping, projection);
+    let result = Terminal::enter()
+        .and_then(|mut terminal| simulator.run(&mut terminal, &command_sender, &event_receiver));
+    _ = command_sender.send(Command::Close);
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Terminal error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Project a pixel map file onto the matrix, kept for the whole run.
+fn load_projection(path: &str) -> Result<&'static ProjectionTable, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let table = ProjectionTable::build(&data, PROJECTION, MATRIX_COLUMNS, MATRIX_ROWS)
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(Box::leak(Box::new(table)))
+}
+
+/// State of the simulated cube.
+struct Simulator {
+    /// Endpoints the client tries
+    endpoints: EndpointList,
+    /// Endpoint the client is connected to
+    connected: Option<Endpoint>,
+    /// Last preview frame, `None` until one arrived on this connection
+    frame: Option<Frame>,
+    /// Name of the active pattern
+    pattern: String,
+    /// Strategy used for new preview frames
+    mapping: Mapping,
+    /// Projection available for [`Mapping::Projected`]
+    projection: Option<&'static ProjectionTable>,
+    /// Matrix brightness (the terminal already shows gamma-encoded colors)
+    pipeline: ColorPipeline,
+    /// Last key pressed and when
+    pressed: Option<(usize, Instant)>,
+    /// Last log message of the client
+    log: String,
+    /// Start of the wait animation
+    started: Instant,
+}
+
+impl Simulator {
+    fn new(
+        endpoints: EndpointList,
+        mapping: Mapping,
+        projection: Option<&'static ProjectionTable>,
+    ) -> Self {
+        Self {
+            endpoints,
+            connected: None,
+            frame: None,
+            pattern: String::new(),
+            mapping,
+            projection,
+            pipeline: ColorPipeline::new(1.0, 0xFF, NEUTRAL_BALANCE, u32::MAX),
+            pressed: None,
+            log: String::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Show frames and handle keys until the user quits.
+    fn run(
+        &mut self,
+        terminal: &mut Terminal,
+        commands: &Sender<Command>,
+        events: &Receiver<Event>,
+    ) -> io::Result<()> {
+        loop {
+            // Only the latest frame matters, older ones are skipped
+            while let Ok(event) = events.try_recv() {
+                self.handle_event(event);
+            }
+
+            let frame = self.render();
+            let status = self.status();
+            let status: Vec<&str> = status.iter().map(String::as_str).collect();
+            terminal.draw(&frame, &status)?;
+
+            match terminal.poll(REDRAW_INTERVAL)? {
+                Some(Input::Key(key)) => self.press(key, false, commands),
+                Some(Input::ShiftedKey(key)) => self.press(key, true, commands),
+                Some(Input::Quit) => return Ok(()),
+                None => {}
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Connected(endpoint) => self.connected = Some(endpoint),
+            Event::Disconnected => {
+                self.connected = None;
+                self.frame = None;
+                self.started = Instant::now();
+            }
+            Event::Frame(frame) => self.frame = Some(*frame),
+            Event::ActivePattern(name) => self.pattern = name,
+            Event::Log(message) => self.log = message,
+        }
+    }
+
+    /// Frame as shown on the LEDs.
+    fn render(&mut self) -> Frame {
+        let mut frame = match self.frame {
+            Some(frame) => frame,
+            None => {
+                let elapsed = self.started.elapsed().as_millis() as u64;
+                let n = (elapsed / WAIT_ANIMATION_FRAME_MS) as usize % WAIT_ANIMATION.len();
+                WAIT_ANIMATION[n].into()
+            }
+        };
+
+        match self.pressed {
+            Some((key, at)) if at.elapsed() < PRESSED_DURATION => {
+                if let Some(pixel) = frame.get_mut(key) {
+                    *pixel = PRESSED_COLOR;
+                }
+            }
+            _ => self.pressed = None,
+        }
+
+        self.pipeline.apply(&mut frame);
+        frame
+    }
+
+    fn status(&self) -> [String; 4] {
+        let connection = match self.connected {
+            Some(endpoint) => format!("connected to {}", endpoint),
+            None => {
+                let endpoints: Vec<String> =
+                    self.endpoints.iter().map(Endpoint::to_string).collect();
+                format!("connecting to {}...", endpoints.join(", "))
+            }
+        };
+        [
+            format!("Pixelblaze: {}", connection),
+            format!(
+                "Pattern: {}  Mapping: {}  Brightness: {}",
+                self.pattern,
+                mapping_name(self.mapping),
+                self.pipeline.brightness()
+            ),
+            terminal::key_help(),
+            self.log.clone(),
+        ]
+    }
+
+    /// Tap a matrix key, optionally while holding the shift key, like the
+    /// firmware's button task.
+    fn press(&mut self, key: u8, shifted: bool, commands: &Sender<Command>) {
+        if usize::from(key) >= NEOTRELLIS_PIXELS {
+            return;
+        }
+        self.pressed = Some((usize::from(key), Instant::now()));
+
+        let tap = [ButtonEvent::Pressed(key), ButtonEvent::Released(key)];
+        let shifted_tap = [
+            ButtonEvent::Pressed(SHIFT_KEY),
+            ButtonEvent::Pressed(key),
+            ButtonEvent::Released(key),
+            ButtonEvent::Released(SHIFT_KEY),
+        ];
+        let events: &[ButtonEvent] = if shifted { &shifted_tap } else { &tap };
+
+        let mut keypad = Keypad::default();
+        for &event in events {
+            let action = keypad.event(event);
+            self.act(action, commands);
+        }
+    }
+
+    /// Trigger the action of a key.
+    fn act(&mut self, action: KeyAction, commands: &Sender<Command>) {
+        let command = match action {
+            KeyAction::None => None,
+            KeyAction::SelectPattern(n) => Some(Command::SelectPattern(n.into())),
+            KeyAction::NextPattern => Some(Command::NextPattern),
+            KeyAction::PreviousPattern => Some(Command::PreviousPattern),
+            KeyAction::BrightnessUp => Some(Command::AdjustBrightness(BRIGHTNESS_STEP)),
+            KeyAction::BrightnessDown => Some(Command::AdjustBrightness(-BRIGHTNESS_STEP)),
+            KeyAction::NextPreviewMapping => {
+                self.mapping = self.mapping.next(None, self.projection);
+                Some(Command::SetMapping(self.mapping))
+            }
+            KeyAction::BrightenMatrix => {
+                self.adjust_brightness(MATRIX_BRIGHTNESS_STEP);
+                None
+            }
+            KeyAction::DimMatrix => {
+                self.adjust_brightness(-MATRIX_BRIGHTNESS_STEP);
+                None
+            }
+        };
+
+        if let Some(command) = command {
+            _ = commands.send(command);
+        }
+    }
+
+    fn adjust_brightness(&mut self, delta: i16) {
+        let brightness = (i16::from(self.pipeline.brightness()) + delta).clamp(0, 255);
+        self.pipeline.set_brightness(brightness as u8);
+    }
+}
+
+/// Short name of a mapping strategy for the status line.
+fn mapping_name(mapping: Mapping) -> &'static str {
+    match mapping {
+        Mapping::FirstN => "first pixels",
+        Mapping::Sampled => "sampled",
+        Mapping::Averaged => "averaged",
+        Mapping::IndexTable(_) => "index table",
+        Mapping::Projected(_) => "pixel map",
+    }
+}
@@ -0,0 +1,301 @@
+//! # Mock Pixelblaze
+//!
+//! Scriptable stand-in for a Pixelblaze on localhost, so the client can be
+//! tested without a lighthouse.
+//!
+//! The mock speaks the WebSocket handshake and answers requests like
+//! Pixelblaze does:
+//! - `getConfig`: configuration followed by the active program
+//! - `listPrograms`: the [`PATTERNS`] as a program list split across two frames
+//! - `setActivePattern`: the new active program
+//! - `sendUpdates`: (re)enables preview frames
+//!
+//! Everything else the server does is scripted with [`Step`]s, one script per
+//! connection. The connection is dropped when its script ends.
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use buntspiel_core::endpoint::Endpoint;
+use buntspiel_core::frame::Rgb;
+use tungstenite::{Message, WebSocket};
+
+/// Patterns stored on the mock (ID and name).
+pub(crate) const PATTERNS: [(&str, &str); 3] = [
+    ("kuJfFyCSkCKNasyNE", "blink fade"),
+    ("Wt2xjGTu3oD9zdn6S", "Leuchtturm bunt"),
+    ("Hy5bT3sGg8xR2nVqL", "sparkfire"),
+];
+
+/// Pixels in the preview frames of the mock.
+const PIXEL_COUNT: usize = 300;
+
+/// How long a step waits for the client before the script fails.
+const STEP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long a read may block before the step timeout is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What the mock does next on a connection.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Step {
+    /// Wait for a text message from the client containing the given text
+    Expect(&'static str),
+    /// Stream preview frames of a single color (while updates are enabled)
+    Frames { count: usize, color: Rgb },
+    /// Stop preview frames until the client sends `sendUpdates`
+    PauseUpdates,
+    /// Send a text message as is
+    Text(&'static str),
+    /// Send a binary message as is, e.g. a malformed preview frame
+    Binary(Vec<u8>),
+    /// Send a ping and wait for the pong
+    Ping,
+    /// Close the connection gracefully and wait for the client's reply
+    Close,
+    /// Wait for the client to close the connection
+    AwaitClose,
+    /// Write raw bytes that aren't a valid WebSocket frame
+    Garbage(&'static [u8]),
+}
+
+/// Mock Pixelblaze serving scripted connections on a background thread.
+pub(crate) struct MockPixelblaze {
+    /// Local address of the listening socket
+    addr: SocketAddr,
+    /// Text messages received on all connections
+    received: Arc<Mutex<Vec<String>>>,
+    /// Server thread, ends after the last script
+    server: JoinHandle<()>,
+}
+
+impl MockPixelblaze {
+    /// Listen on a free localhost port and serve one connection per script,
+    /// in order.
+    pub(crate) fn start(scripts: Vec<Vec<Step>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&received);
+
+        let server = thread::spawn(move || {
+            for script in scripts {
+                let (stream, _) = listener.accept().unwrap();
+                Connection::accept(stream, Arc::clone(&log)).run(script);
+            }
+        });
+
+        Self {
+            addr,
+            received,
+            server,
+        }
+    }
+
+    /// Address to connect to.
+    pub(crate) fn endpoint(&self) -> Endpoint {
+        Endpoint { addr: self.addr }
+    }
+
+    /// Wait until all scripts ran and return the text messages received.
+    ///
+    /// Panics if a script failed.
+    pub(crate) fn finish(self) -> Vec<String> {
+        if let Err(e) = self.server.join() {
+            panic::resume_unwind(e);
+        }
+        let received = self.received.lock().unwrap();
+        received.clone()
+    }
+}
+
+/// One client connection.
+struct Connection {
+    socket: WebSocket<TcpStream>,
+    /// Text messages received on all connections
+    received: Arc<Mutex<Vec<String>>>,
+    /// Messages before this index were matched by an earlier [`Step::Expect`]
+    expected: usize,
+    /// Index of the active pattern in [`PATTERNS`]
+    active: Option<usize>,
+    /// Whether preview frames are sent
+    updates: bool,
+}
+
+impl Connection {
+    fn accept(stream: TcpStream, received: Arc<Mutex<Vec<String>>>) -> Self {
+        let socket = tungstenite::accept(stream).unwrap();
+        socket
+            .get_ref()
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .unwrap();
+        let expected = received.lock().unwrap().len();
+
+        Self {
+            socket,
+            received,
+            expected,
+            active: None,
+            updates: true,
+        }
+    }
+
+    fn run(mut self, script: Vec<Step>) {
+        for step in script {
+            match step {
+                Step::Expect(text) => self.expect(text),
+                Step::Frames { count, color } => {
+                    for _ in 0..count {
+                        if self.updates {
+                            self.send(Message::binary(preview_frame(color)));
+                        }
+                    }
+                }
+                Step::PauseUpdates => self.updates = false,
+                Step::Text(text) => self.send(Message::text(text)),
+                Step::Binary(payload) => self.send(Message::binary(payload)),
+                Step::Ping => {
+                    self.send(Message::Ping(Vec::new().into()));
+                    self.wait_for("pong", |message| matches!(message, Message::Pong(_)));
+                }
+                Step::Close => {
+                    self.socket.close(None).unwrap();
+                    self.wait_for("close reply", |message| {
+                        matches!(message, Message::Close(_))
+                    });
+                }
+                Step::AwaitClose => {
+                    self.wait_for("close", |message| matches!(message, Message::Close(_)));
+                    // Reply to the close frame
+                    _ = self.socket.flush();
+                }
+                Step::Garbage(bytes) => {
+                    let stream = self.socket.get_mut();
+                    stream.write_all(bytes).unwrap();
+                    stream.flush().unwrap();
+                }
+            }
+        }
+    }
+
+    /// Wait for a text message containing `text` not matched before.
+    fn expect(&mut self, text: &str) {
+        let started = Instant::now();
+        loop {
+            {
+                let received = self.received.lock().unwrap();
+                let pending = &received[self.expected..];
+                if let Some(n) = pending.iter().position(|m| m.contains(text)) {
+                    self.expected += n + 1;
+                    return;
+                }
+                if started.elapsed() > STEP_TIMEOUT {
+                    panic!(
+                        "mock pixelblaze: expected {:?}, received {:?}",
+                        text, pending
+                    );
+                }
+            }
+            self.read();
+        }
+    }
+
+    /// Read until a message satisfies `done`.
+    fn wait_for(&mut self, what: &str, done: impl Fn(&Message) -> bool) {
+        let started = Instant::now();
+        loop {
+            if self.read().as_ref().is_some_and(&done) {
+                return;
+            }
+            if started.elapsed() > STEP_TIMEOUT {
+                panic!("mock pixelblaze: timed out waiting for {}", what);
+            }
+        }
+    }
+
+    /// Read the next message and answer it if it's a request, `None` on
+    /// timeout. A closed connection reads as a close frame.
+    fn read(&mut self) -> Option<Message> {
+        match self.socket.read() {
+            Ok(Message::Text(text)) => {
+                self.received.lock().unwrap().push(text.to_string());
+                self.answer(text.as_str());
+                Some(Message::Text(text))
+            }
+            Ok(message) => Some(message),
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                None
+            }
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                Some(Message::Close(None))
+            }
+            Err(e) => panic!("mock pixelblaze: {}", e),
+        }
+    }
+
+    /// Answer a request like Pixelblaze does.
+    fn answer(&mut self, request: &str) {
+        if request.contains("getConfig") {
+            self.reply(Message::text(format!(
+                r#"{{"name":"Mock","pixelCount":{},"brightness":0.6,"sequencerMode":0,"runSequencer":false,"ver":"3.40"}}"#,
+                PIXEL_COUNT
+            )));
+            self.send_active_program();
+        }
+        if request.contains("listPrograms") {
+            let list: String = PATTERNS
+                .iter()
+                .map(|(id, name)| format!("{}\t{}\n", id, name))
+                .collect();
+            let (first, last) = list.as_bytes().split_at(list.len() / 2);
+            self.reply(Message::binary([&[7, 1], first].concat()));
+            self.reply(Message::binary([&[7, 4], last].concat()));
+        }
+        if request.contains("setActivePattern") {
+            self.active = PATTERNS.iter().position(|(id, _)| request.contains(id));
+            self.send_active_program();
+        }
+        if request.contains(r#""sendUpdates":true"#) {
+            self.updates = true;
+        }
+    }
+
+    fn send_active_program(&mut self) {
+        let message = match self.active.map(|n| PATTERNS[n]) {
+            Some((id, name)) => format!(
+                r#"{{"activeProgram":{{"name":"{}","activeProgramId":"{}","controls":{{}}}}}}"#,
+                name, id
+            ),
+            None => r#"{"activeProgram":{"name":"","activeProgramId":null,"controls":{}}}"#.into(),
+        };
+        self.reply(Message::text(message));
+    }
+
+    fn send(&mut self, message: Message) {
+        self.socket.send(message).unwrap();
+    }
+
+    /// Send an answer. Unlike scripted messages, answers are lost if the
+    /// client closed the connection in the meantime.
+    fn reply(&mut self, message: Message) {
+        _ = self.socket.send(message);
+    }
+}
+
+/// Type 5 preview frame with all pixels set to `color`.
+pub(crate) fn preview_frame(color: Rgb) -> Vec<u8> {
+    let mut frame = vec![5];
+    for _ in 0..PIXEL_COUNT {
+        frame.extend([color.r, color.g, color.b]);
+    }
+    frame
+}
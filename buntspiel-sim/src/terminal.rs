@@ -0,0 +1,171 @@
+//! # Terminal Matrix
+//!
+//! Draws the LED matrix with truecolor blocks and turns keyboard keys into
+//! NeoTrellis button presses.
+//!
+//! ## Keys
+//! The keyboard rows `1234567890`, `qwertyuiop`, `asdfghjkl;` and
+//! `zxcvbnm,./` stand in for the keys at the top left of the matrix, so a
+//! single board is played with `1234`, `qwer`, `asdf` and `zxcv`. Esc or
+//! Ctrl-C quits.
+
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+use buntspiel_core::frame::Frame;
+use buntspiel_core::keymap::SHIFT_KEY;
+use buntspiel_core::layout::{MATRIX_COLUMNS, MATRIX_ROWS};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+/// Keyboard rows standing in for the matrix rows, left to right.
+const KEY_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl;", "zxcvbnm,./"];
+
+/// Keyboard input relevant to the simulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Input {
+    /// Matrix key pressed (row-major index)
+    Key(u8),
+    /// Matrix key pressed with Shift, stands in for holding the shift key
+    ShiftedKey(u8),
+    /// Quit the simulator
+    Quit,
+}
+
+/// Matrix key index of a keyboard key, if it stands in for one.
+pub(crate) fn key_index(c: char) -> Option<u8> {
+    let c = c.to_ascii_lowercase();
+    KEY_ROWS.iter().enumerate().find_map(|(y, row)| {
+        let x = row.chars().position(|k| k == c)?;
+        (x < MATRIX_COLUMNS && y < MATRIX_ROWS).then_some((y * MATRIX_COLUMNS + x) as u8)
+    })
+}
+
+/// Help line naming the keyboard keys standing in for the matrix keys.
+///
+/// Only the top-left 10x4 keys of larger matrices have a keyboard key. Shift
+/// stands in for holding the shift key, even without a keyboard key of its own.
+pub(crate) fn key_help() -> String {
+    let rows: Vec<String> = KEY_ROWS
+        .iter()
+        .map(|row| row.chars().filter(|&c| key_index(c).is_some()).collect())
+        .filter(|row: &String| !row.is_empty())
+        .collect();
+    let columns = rows.first().map_or(0, |row| row.chars().count());
+
+    let mut help = format!("Keys {} act as buttons", rows.join("/"));
+    if columns < MATRIX_COLUMNS || rows.len() < MATRIX_ROWS {
+        help += &format!(" (top-left {}x{} only)", columns, rows.len());
+    }
+    let shift = KEY_ROWS
+        .iter()
+        .flat_map(|row| row.chars())
+        .find(|&c| key_index(c) == Some(SHIFT_KEY));
+    match shift {
+        Some(c) => help += &format!(", Shift holds {}", c),
+        None => help += &format!(", Shift holds key {}", SHIFT_KEY),
+    }
+    help + ", Esc quits"
+}
+
+/// Terminal in raw mode showing the matrix, restored when dropped.
+pub(crate) struct Terminal {
+    out: Stdout,
+}
+
+impl Terminal {
+    /// Switch to the alternate screen in raw mode.
+    pub(crate) fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stdout();
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self { out })
+    }
+
+    /// Draw a frame followed by status lines.
+    pub(crate) fn draw(&mut self, frame: &Frame, status: &[&str]) -> io::Result<()> {
+        queue!(self.out, cursor::MoveTo(0, 0))?;
+        for row in frame.chunks(MATRIX_COLUMNS) {
+            for pixel in row {
+                let color = Color::Rgb {
+                    r: pixel.r,
+                    g: pixel.g,
+                    b: pixel.b,
+                };
+                queue!(self.out, SetForegroundColor(color), Print("██ "))?;
+            }
+            queue!(self.out, ResetColor, Print("\r\n"))?;
+        }
+        for line in status {
+            queue!(
+                self.out,
+                Print("\r\n"),
+                Print(line),
+                terminal::Clear(ClearType::UntilNewLine)
+            )?;
+        }
+        self.out.flush()
+    }
+
+    /// Wait up to `timeout` for keyboard input.
+    pub(crate) fn poll(&self, timeout: Duration) -> io::Result<Option<Input>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        let Event::Key(key) = event::read()? else {
+            return Ok(None);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(None);
+        }
+        Ok(match key.code {
+            KeyCode::Esc => Some(Input::Quit),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Input::Quit)
+            }
+            KeyCode::Char(c) if c.is_ascii_uppercase() => key_index(c).map(Input::ShiftedKey),
+            KeyCode::Char(c) => key_index(c).map(Input::Key),
+            _ => None,
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_keyboard_rows_to_matrix_rows() {
+        assert_eq!(key_index('1'), Some(0));
+        assert_eq!(key_index('4'), Some(3));
+        assert_eq!(key_index('Q'), Some(MATRIX_COLUMNS as u8));
+        assert_eq!(key_index('z'), Some(3 * MATRIX_COLUMNS as u8));
+        assert_eq!(key_index('!'), None);
+
+        // Keys right of the matrix do nothing
+        let beyond = KEY_ROWS[0].chars().nth(MATRIX_COLUMNS);
+        assert_eq!(beyond.and_then(key_index), None);
+    }
+
+    #[test]
+    fn names_keys_in_help() {
+        let help = key_help();
+        if (MATRIX_COLUMNS, MATRIX_ROWS) == (4, 4) {
+            assert_eq!(
+                help,
+                "Keys 1234/qwer/asdf/zxcv act as buttons, Shift holds z, Esc quits"
+            );
+        } else {
+            assert!(help.contains("top-left"), "{help}");
+        }
+    }
+}
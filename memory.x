@@ -1,6 +1,9 @@
 MEMORY {
     BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 16K
+
+    /* Persistent configuration (src/config.rs), kept clear of the firmware */
+    CONFIG : ORIGIN = 0x10000000 + 2048K - 16K, LENGTH = 16K
 
     /* Pick one of the two options for RAM layout     */
 
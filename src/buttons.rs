@@ -0,0 +1,155 @@
+//! # VJ Button Mapping
+//!
+//! Translates NeoTrellis button presses into Pixelblaze commands.
+//!
+//! Button events are produced by the NeoTrellis driver on core 1 and consumed
+//! here on core 0, where each key is looked up in `buntspiel_core::keymap`
+//! and turned into a command for the Pixelblaze WebSocket client. Keys
+//! pressed while the shift key is held trigger their shifted action.
+//!
+//! Once Pixelblaze confirmed or failed a pattern switch, the pattern key
+//! flashes green or red.
+
+use defmt::info;
+use embassy_futures::select::{select3, Either3};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+
+use buntspiel_core::frame::Rgb;
+use buntspiel_core::keymap::{ButtonEvent, KeyAction, Keypad};
+use buntspiel_core::session::PatternSwitch;
+
+use crate::neotrellis;
+use crate::pixelblaze::{
+    self, CONNECTION_LOST_SIGNAL, PATTERN_SWITCH_SIGNAL, PIXELBLAZE_CONTROL_CHANNEL,
+};
+use crate::preview;
+
+/// Maximum number of button events queued between the cores.
+const MAX_BUTTON_EVENTS: usize = 8;
+
+/// Brightness change per press of a brightness key (0.0-1.0 scale).
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// Brightness change per press of a matrix brightness key (0-255 scale).
+const MATRIX_BRIGHTNESS_STEP: i16 = 16;
+
+/// Color flashed on a pattern key once Pixelblaze switched to the pattern.
+const SWITCH_CONFIRMED_COLOR: Rgb = Rgb {
+    r: 0x00,
+    g: 0xFF,
+    b: 0x00,
+};
+
+/// Color flashed on a pattern key if Pixelblaze didn't switch.
+const SWITCH_FAILED_COLOR: Rgb = Rgb {
+    r: 0xFF,
+    g: 0x00,
+    b: 0x00,
+};
+
+/// Channel for button events from the NeoTrellis driver.
+pub(crate) static BUTTON_CHANNEL: Channel<CriticalSectionRawMutex, ButtonEvent, MAX_BUTTON_EVENTS> =
+    Channel::new();
+
+/// Pixelblaze command for a key action, if any.
+fn to_control(action: KeyAction) -> Option<pixelblaze::Control> {
+    Some(match action {
+        KeyAction::None
+        | KeyAction::NextPreviewMapping
+        | KeyAction::BrightenMatrix
+        | KeyAction::DimMatrix => return None,
+        KeyAction::SelectPattern(n) => pixelblaze::Control::SelectPattern(n.into()),
+        KeyAction::NextPattern => pixelblaze::Control::NextPattern,
+        KeyAction::PreviousPattern => pixelblaze::Control::PreviousPattern,
+        KeyAction::BrightnessUp => pixelblaze::Control::AdjustBrightness(BRIGHTNESS_STEP),
+        KeyAction::BrightnessDown => pixelblaze::Control::AdjustBrightness(-BRIGHTNESS_STEP),
+    })
+}
+
+/// Button handling task.
+///
+/// Runs on core 0, mapping key presses to Pixelblaze commands and showing
+/// the outcome of pattern switches.
+#[embassy_executor::task]
+pub(crate) async fn buttons_task() -> ! {
+    let receiver = BUTTON_CHANNEL.receiver();
+    let control_commands = PIXELBLAZE_CONTROL_CHANNEL.sender();
+
+    // Shift key state
+    let mut keypad = Keypad::default();
+    // Key of the last pattern switch, waiting for its outcome
+    let mut pattern_key = None;
+
+    info!("buttons: 🎛️  Waiting for button presses");
+
+    loop {
+        let event = match select3(
+            receiver.receive(),
+            PATTERN_SWITCH_SIGNAL.wait(),
+            CONNECTION_LOST_SIGNAL.wait(),
+        )
+        .await
+        {
+            Either3::First(event) => event,
+            Either3::Second(switch) => {
+                // Switches not started by a key (e.g. when idle) aren't shown
+                if let Some(key) = pattern_key.take() {
+                    flash_key(key, switch);
+                }
+                continue;
+            }
+            Either3::Third(()) => {
+                // The switch of the key will never be confirmed
+                pattern_key = None;
+                continue;
+            }
+        };
+
+        // Actions trigger on press, except for a tap of the shift key
+        let action = keypad.event(event);
+        let (ButtonEvent::Pressed(key) | ButtonEvent::Released(key)) = event;
+        if action == KeyAction::None {
+            continue;
+        }
+        info!("buttons: 🔘 Key {}: {}", key, action);
+
+        match action {
+            KeyAction::NextPreviewMapping => {
+                let mapping = preview::next_mapping();
+                info!("buttons: 🗺️  Preview mapping: {}", mapping);
+                preview::set_mapping(mapping);
+            }
+            KeyAction::BrightenMatrix => adjust_matrix_brightness(MATRIX_BRIGHTNESS_STEP),
+            KeyAction::DimMatrix => adjust_matrix_brightness(-MATRIX_BRIGHTNESS_STEP),
+            _ => {}
+        }
+
+        if let Some(control) = to_control(action) {
+            // Drop the command if the client is backed up (e.g. disconnected)
+            let sent = control_commands.try_send(control).is_ok();
+            let switches_pattern = matches!(
+                action,
+                KeyAction::SelectPattern(_) | KeyAction::NextPattern | KeyAction::PreviousPattern
+            );
+            if sent && switches_pattern {
+                pattern_key = Some(key);
+            }
+        }
+    }
+}
+
+/// Ask the NeoTrellis driver to flash a pattern key with the switch outcome.
+fn flash_key(key: u8, switch: PatternSwitch) {
+    let color = match switch {
+        PatternSwitch::Confirmed(_) => SWITCH_CONFIRMED_COLOR,
+        PatternSwitch::Failed(_) => SWITCH_FAILED_COLOR,
+    };
+    // Dropped if the driver is backed up, only feedback
+    _ = neotrellis::CONTROL_CHANNEL.try_send(neotrellis::Control::FlashKey { key, color });
+}
+
+/// Ask the NeoTrellis driver to change the matrix brightness.
+fn adjust_matrix_brightness(delta: i16) {
+    // Dropped if the driver is backed up, the user can press again
+    _ = neotrellis::CONTROL_CHANNEL.try_send(neotrellis::Control::AdjustBrightness(delta));
+}
@@ -0,0 +1,136 @@
+//! # Persistent Configuration
+//!
+//! Loads the settings from the flash region reserved as `CONFIG` in
+//! `memory.x` at startup and saves changes back (see
+//! `buntspiel_core::config` for the record format and wear leveling).
+//!
+//! Until a configuration was saved, or if the stored one is corrupted, the
+//! defaults of the modules apply: `wifi::WIFI_NETWORKS`,
+//! `pixelblaze::DEFAULT_ENDPOINTS` and `neotrellis::DEFAULT_BRIGHTNESS`.
+
+use core::cell::RefCell;
+
+use defmt::{info, warn};
+use embassy_rp::flash::{Blocking, Error as FlashError, Flash, ERASE_SIZE};
+use embassy_rp::peripherals::FLASH;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::mutex::Mutex as AsyncMutex;
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+use buntspiel_core::config::{Config, ConfigError, ConfigFlash, ConfigStore};
+use buntspiel_core::networks::{KnownNetwork, KnownNetworks};
+
+use crate::neotrellis::DEFAULT_BRIGHTNESS;
+use crate::pixelblaze::DEFAULT_ENDPOINTS;
+use crate::wifi::WIFI_NETWORKS;
+
+/// Size of the Pico W flash
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
+
+/// Size of the configuration region, must match `CONFIG` in `memory.x`
+const CONFIG_SIZE: u32 = 16 * 1024;
+
+/// Start of the configuration region, at the end of the flash
+const CONFIG_OFFSET: u32 = FLASH_SIZE as u32 - CONFIG_SIZE;
+
+/// Configuration region of the onboard flash.
+struct ConfigRegion(Flash<'static, FLASH, Blocking, FLASH_SIZE>);
+
+impl ConfigFlash for ConfigRegion {
+    type Error = FlashError;
+
+    const SECTOR_SIZE: u32 = ERASE_SIZE as u32;
+
+    fn capacity(&self) -> u32 {
+        CONFIG_SIZE
+    }
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), FlashError> {
+        ReadNorFlash::read(&mut self.0, CONFIG_OFFSET + offset, bytes)
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), FlashError> {
+        NorFlash::write(&mut self.0, CONFIG_OFFSET + offset, bytes)
+    }
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), FlashError> {
+        NorFlash::erase(&mut self.0, CONFIG_OFFSET + from, CONFIG_OFFSET + to)
+    }
+}
+
+/// Configuration log, `None` until [`init_config`] was called.
+///
+/// An async mutex, so no critical section is held while the flash is
+/// written (which pauses Core 1).
+static STORE: AsyncMutex<CriticalSectionRawMutex, Option<ConfigStore<ConfigRegion>>> =
+    AsyncMutex::new(None);
+
+/// Configuration in use, `None` until loaded.
+static CONFIG: Mutex<CriticalSectionRawMutex, RefCell<Option<Config>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Load the configuration from flash.
+///
+/// Must be called once at startup, before the tasks using it are spawned.
+pub(crate) async fn init_config(flash: FLASH) {
+    let mut store = ConfigStore::new(ConfigRegion(Flash::new_blocking(flash)));
+
+    match store.load() {
+        Ok(Some(config)) => {
+            info!(
+                "config: 💾 Loaded {} networks, endpoints {}, brightness {}",
+                config.networks.len(),
+                config.endpoints,
+                config.brightness
+            );
+            CONFIG.lock(|c| c.replace(Some(config)));
+        }
+        Ok(None) => info!("config: 🆕 No stored configuration, using defaults"),
+        Err(e) => warn!(
+            "config: ❌ Failed to read configuration: {}, using defaults",
+            e
+        ),
+    }
+
+    STORE.lock().await.replace(store);
+}
+
+/// Configuration in use, the defaults if none is stored.
+pub(crate) fn config() -> Config {
+    CONFIG.lock(|c| c.borrow().clone()).unwrap_or_else(defaults)
+}
+
+/// Store the configuration. WiFi and Pixelblaze pick it up with their next
+/// connection attempt, the brightness after a reboot.
+pub(crate) async fn save_config(config: Config) -> Result<(), ConfigError> {
+    let mut store = STORE.lock().await;
+    let store = store.as_mut().ok_or(ConfigError::Flash)?;
+    store.save(&config)?;
+
+    info!("config: 💾 Configuration saved");
+    CONFIG.lock(|c| c.replace(Some(config)));
+    Ok(())
+}
+
+/// Configuration built from the module constants.
+fn defaults() -> Config {
+    let networks: KnownNetworks = WIFI_NETWORKS
+        .iter()
+        .filter_map(|&(ssid, passphrase, priority)| {
+            match KnownNetwork::new(ssid, passphrase, priority) {
+                Ok(network) => Some(network),
+                Err(e) => {
+                    warn!("config: ⚠️ Skipping network '{}': {:?}", ssid, e);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    Config {
+        networks,
+        endpoints: DEFAULT_ENDPOINTS.into_iter().collect(),
+        brightness: DEFAULT_BRIGHTNESS,
+    }
+}
@@ -0,0 +1,103 @@
+//! # Pixelblaze Discovery
+//!
+//! Listens for the beacons Pixelblaze controllers broadcast on UDP port 1889
+//! and hands the controllers heard to the Pixelblaze client, which tries them
+//! before the configured endpoints. This way the cube finds the lighthouse on
+//! any network, without a fixed IP.
+
+use core::net::{IpAddr, Ipv4Addr};
+
+use defmt::{info, unwrap, warn, Display2Format};
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+use embassy_net::IpAddress;
+use embassy_time::{with_timeout, Duration, Instant};
+
+use buntspiel_core::discovery::{Beacon, Controllers, Discovery, DISCOVERY_PORT};
+
+use crate::config;
+use crate::pixelblaze;
+
+/// Only follow the controller with this ID (chip ID of the lighthouse
+/// Pixelblaze), `None` to follow any controller on the network
+const PIXELBLAZE_ID: Option<u32> = None;
+
+/// How often silent controllers are checked for while no beacons arrive
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Beacons buffered while the task is busy
+const MAX_PACKETS: usize = 4;
+
+/// Pixelblaze discovery task.
+///
+/// Updates the Pixelblaze endpoints whenever a controller appears, moves to
+/// another address or goes silent.
+#[embassy_executor::task]
+pub(crate) async fn discovery_task(
+    stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>,
+) -> ! {
+    let mut rx_meta = [PacketMetadata::EMPTY; MAX_PACKETS];
+    let mut rx_buffer = [0_u8; 256];
+    // Nothing is sent, a minimal transmit buffer suffices
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0_u8; 16];
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    unwrap!(socket.bind(DISCOVERY_PORT));
+    info!("discovery: 🔭 Listening for Pixelblaze beacons on port {}", DISCOVERY_PORT);
+
+    let mut controllers = Controllers::default();
+    let mut buf = [0_u8; 64];
+
+    loop {
+        let mut changed = false;
+
+        match with_timeout(EXPIRE_INTERVAL, socket.recv_from(&mut buf)).await {
+            Ok(Ok((len, sender))) => {
+                let beacon = Beacon::decode(&buf[..len])
+                    .filter(|b| PIXELBLAZE_ID.is_none_or(|id| id == b.sender_id));
+                if let Some(beacon) = beacon {
+                    let IpAddress::Ipv4(ip) = sender.addr;
+                    let ip = IpAddr::V4(Ipv4Addr::from(ip.0));
+                    let now = Instant::now().as_millis();
+
+                    match controllers.record(&beacon, ip, now) {
+                        Discovery::New => {
+                            info!(
+                                "discovery: ✨ Found Pixelblaze {=u32:08x} at {}",
+                                beacon.sender_id,
+                                Display2Format(&ip)
+                            );
+                            changed = true;
+                        }
+                        Discovery::Moved => {
+                            info!(
+                                "discovery: 🚚 Pixelblaze {=u32:08x} moved to {}",
+                                beacon.sender_id,
+                                Display2Format(&ip)
+                            );
+                            changed = true;
+                        }
+                        Discovery::Seen => {}
+                    }
+                }
+            }
+            Ok(Err(e)) => warn!("discovery: ❌ Failed to receive beacon: {:?}", e),
+            Err(_) => {} // No beacons, check for silent controllers
+        }
+
+        if controllers.expire(Instant::now().as_millis()) {
+            info!("discovery: 👻 Pixelblaze went silent, forgetting it");
+            changed = true;
+        }
+
+        if changed {
+            let fallback = config::config().endpoints;
+            pixelblaze::set_endpoints(controllers.endpoints(fallback.as_slice()));
+        }
+    }
+}
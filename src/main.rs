@@ -15,7 +15,8 @@
 //! - **Core 1**: I2C communication, LED matrix control, animations
 //!
 //! ## Network
-//! - Connects to Pixelblaze at 192.168.4.1:81 via WebSocket
+//! - Finds Pixelblaze by its UDP beacons, falling back to 192.168.4.1:81
+//! - Connects to Pixelblaze via WebSocket
 //! - Receives real-time LED preview frames
 //! - Sends pattern control commands
 
@@ -24,10 +25,16 @@
 #![allow(async_fn_in_trait)]
 #![feature(impl_trait_in_assoc_type)]
 
-// Application modules
+// Application modules (hardware independent logic lives in buntspiel-core)
 mod animate; // Fallback animations (spinning pattern while connecting)
+mod buttons; // NeoTrellis button to Pixelblaze action mapping
+mod config; // Persistent settings in flash
+mod discovery; // Pixelblaze discovery via UDP beacons
+mod multitrellis; // Tiled NeoTrellis boards driven as one matrix
 mod neotrellis; // NeoTrellis 4x4 LED matrix driver and control
 mod pixelblaze; // Pixelblaze WebSocket protocol and communication
+mod preview; // Preview mapping configuration and run-time selection
+mod provisioning; // Setup access point with captive portal
 mod wifi; // WiFi connection management and initialization
 
 use cyw43_pio::PioSpi;
@@ -35,12 +42,15 @@ use defmt::{info, unwrap};
 use embassy_executor::{Executor, Spawner};
 use embassy_rp::{
     bind_interrupts,
+    clocks::RoscRng,
     gpio::{Level, Output},
     i2c,
     multicore::spawn_core1,
-    peripherals::{DMA_CH0, PIO0},
+    peripherals::{DMA_CH0, I2C1, PIO0},
     pio::{self, Pio},
 };
+use buttons::buttons_task;
+use discovery::discovery_task;
 use neotrellis::{neotrellis_task, I2C_FREQUENCY};
 use pixelblaze::pixelblaze_task;
 use rand::{rngs::SmallRng, RngCore, SeedableRng};
@@ -57,17 +67,27 @@ bind_interrupts!(struct Irqs {
     PIO0_IRQ_0 => pio::InterruptHandler<PIO0>;
 });
 
+// Interrupt binding for the NeoTrellis I2C bus, unmasked on Core 1 by the driver
+bind_interrupts!(struct I2cIrqs {
+    I2C1_IRQ => i2c::InterruptHandler<I2C1>;
+});
+
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
     info!("🚀 Buntspiel Companion Cube Starting Up!");
 
-    // Initialize random number generator for WiFi and networking
-    // Fixed seed for deterministic behavior during development
-    let mut rng = SmallRng::from_seed(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef_u128.to_le_bytes());
-
     // Initialize Pico W peripherals
     let p = embassy_rp::init(Default::default());
 
+    // Initialize random number generator for WiFi and networking, seeded
+    // from the ring oscillator so cubes don't retry in lockstep
+    let mut seed = [0; 16];
+    RoscRng.fill_bytes(&mut seed);
+    let mut rng = SmallRng::from_seed(seed);
+
+    // Load the stored settings before the tasks using them start
+    config::init_config(p.FLASH).await;
+
     // WiFi chip (CYW43) control pins
     let pwr = Output::new(p.PIN_23, Level::Low); // Power control
     let cs = Output::new(p.PIN_25, Level::High); // Chip select
@@ -88,11 +108,20 @@ async fn main(spawner: Spawner) {
     info!("📡 Initializing WiFi connection...");
     let net_stack = init_wifi(spawner, spi, pwr, rng.next_u64()).await;
 
+    // Project the lighthouse pixel map (if configured) before frames arrive
+    preview::init_projection();
+
     // Start Pixelblaze WebSocket communication task on Core 0
     // This handles the real-time pattern data streaming
     info!("🌐 Starting Pixelblaze WebSocket client...");
     unwrap!(spawner.spawn(pixelblaze_task(net_stack, rng,)));
 
+    // Listen for Pixelblaze beacons so the lighthouse is found on any network
+    unwrap!(spawner.spawn(discovery_task(net_stack)));
+
+    // Map NeoTrellis button presses to Pixelblaze commands on Core 0
+    unwrap!(spawner.spawn(buttons_task()));
+
     // Spawn LED control tasks on Core 1 to avoid blocking WiFi operations
     // This ensures smooth network communication while driving the LED matrix
     info!("💡 Starting LED control on Core 1...");
@@ -107,17 +136,11 @@ async fn main(spawner: Spawner) {
                 // Hardware: SDA=Pin6, SCL=Pin7, 100kHz frequency
                 let mut config = i2c::Config::default();
                 config.frequency = I2C_FREQUENCY;
-                let i2c = i2c::I2c::new_blocking(p.I2C1, p.PIN_7, p.PIN_6, config);
-
-                // TODO: Complete NeoTrellis integration
-                // Currently blocked on async I2C driver compatibility
-                // The neotrellis_task needs async I2C but we're using blocking I2C here
-                // Options:
-                // 1. Convert to async I2C (requires async executor on Core 1)
-                // 2. Use blocking I2C in a loop with yields
-                // 3. Implement custom async wrapper
-                todo!("Integrate neotrellis_task with proper async I2C handling");
-                // unwrap!(spawner.spawn(neotrellis_task(i2c)))
+
+                // Created on Core 1 so the I2C1 interrupt is handled by this core's executor
+                let i2c = i2c::I2c::new_async(p.I2C1, p.PIN_7, p.PIN_6, I2cIrqs, config);
+
+                unwrap!(spawner.spawn(neotrellis_task(i2c)))
             });
         },
     );
@@ -0,0 +1,177 @@
+//! # Tiled NeoTrellis Arrays
+//!
+//! Combines several NeoTrellis 4x4 boards on one I2C bus into a single logical
+//! LED matrix with unified button coordinates.
+//!
+//! The tile layout is selected at build time, see `buntspiel_core::layout`.
+//! Pixels and keys are numbered row-major over the whole matrix, top-left
+//! first.
+//!
+//! ## I2C Traffic
+//! Each board remembers the pixels it shows. Only runs of changed pixels are
+//! written, each with a single seesaw buffer write, and boards without
+//! changes are not synced at all.
+
+use adafruit_seesaw::{
+    devices::{NeoTrellis, SeesawDevice, SeesawDeviceInit},
+    modules::keypad::EventType,
+    prelude::{KeypadModule, NeopixelModule},
+    SeesawError,
+};
+use embedded_hal_1::i2c::I2c;
+use heapless::Vec;
+
+use buntspiel_core::frame::Rgb;
+use buntspiel_core::keymap::ButtonEvent;
+use buntspiel_core::layout::{changed_runs, Tile, MAX_RUN, MAX_TILES, TILE_PIXELS, TILE_SIZE};
+
+/// Maximum number of button events collected per poll
+const MAX_EVENTS: usize = 16;
+
+/// Seesaw NeoPixel module and its pixel buffer register
+const NEOPIXEL_BUF: [u8; 2] = [0x0E, 0x04];
+
+/// Pixels sent to the boards by [`MultiTrellis::set_frame`].
+#[derive(defmt::Format, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FrameWrite {
+    /// Pixels written
+    pub(crate) pixels: usize,
+    /// Buffer writes used (one per run of changed pixels)
+    pub(crate) writes: usize,
+}
+
+/// A single initialized board.
+struct Board<D: adafruit_seesaw::Driver> {
+    trellis: NeoTrellis<D>,
+    /// Placement of the board
+    tile: Tile,
+    /// Pixels in the board's buffer (`None` until first written)
+    shown: Option<[Rgb; TILE_PIXELS]>,
+    /// Whether the buffer changed since the last sync
+    dirty: bool,
+}
+
+impl<D: adafruit_seesaw::Driver> Board<D> {
+    /// Write the changed pixels of `next` into the board's buffer.
+    fn write(&mut self, next: &[Rgb; TILE_PIXELS]) -> Result<FrameWrite, SeesawError<D::Error>> {
+        let mut write = FrameWrite::default();
+        for run in changed_runs(self.shown.as_ref(), next) {
+            // NeoTrellis pixels are GRB, addressed by byte offset
+            let [hi, lo] = ((run.start * 3) as u16).to_be_bytes();
+            let mut buffer: Vec<u8, { 4 + 3 * MAX_RUN }> = Vec::new();
+            _ = buffer.extend_from_slice(&[NEOPIXEL_BUF[0], NEOPIXEL_BUF[1], hi, lo]);
+            for Rgb { r, g, b } in &next[run.clone()] {
+                _ = buffer.extend_from_slice(&[*g, *r, *b]);
+            }
+
+            let addr = self.trellis.addr();
+            self.trellis
+                .driver()
+                .write(addr, &buffer)
+                .map_err(SeesawError::I2c)?;
+            write.pixels += run.len();
+            write.writes += 1;
+        }
+
+        self.shown = Some(*next);
+        self.dirty |= write.writes > 0;
+        Ok(write)
+    }
+
+    /// Board pixels of a logical frame.
+    fn pixels(&self, frame: &[Rgb]) -> [Rgb; TILE_PIXELS] {
+        core::array::from_fn(|n| {
+            let (x, y) = ((n % TILE_SIZE) as u8, (n / TILE_SIZE) as u8);
+            frame
+                .get(self.tile.to_logical(x, y))
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// All boards of the layout driven as one logical matrix.
+pub(crate) struct MultiTrellis<D: adafruit_seesaw::Driver> {
+    /// Initialized boards, in the same order as the layout
+    boards: Vec<Board<D>, MAX_TILES>,
+}
+
+impl<D: adafruit_seesaw::Driver> MultiTrellis<D> {
+    /// Initialize every board of `layout`, acquiring one bus driver per board.
+    pub(crate) fn new(
+        layout: &'static [Tile],
+        mut acquire_driver: impl FnMut() -> D,
+    ) -> Result<Self, SeesawError<D::Error>> {
+        let mut boards = Vec::new();
+        for tile in layout.iter().take(MAX_TILES) {
+            let board = Board {
+                trellis: NeoTrellis::new(tile.addr, acquire_driver()).init()?,
+                tile: *tile,
+                shown: None,
+                dirty: false,
+            };
+            if boards.push(board).is_err() {
+                unreachable!("layout is limited to MAX_TILES");
+            }
+        }
+        Ok(Self { boards })
+    }
+
+    /// Set a pixel by its logical (row-major) matrix index.
+    pub(crate) fn set_pixel(
+        &mut self,
+        index: usize,
+        pixel: Rgb,
+    ) -> Result<(), SeesawError<D::Error>> {
+        for board in self.boards.iter_mut() {
+            for n in 0..TILE_PIXELS {
+                let (x, y) = ((n % TILE_SIZE) as u8, (n / TILE_SIZE) as u8);
+                if board.tile.to_logical(x, y) == index {
+                    let mut next = board.shown.unwrap_or_default();
+                    next[n] = pixel;
+                    board.write(&next)?;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(()) // Index not covered by any board
+    }
+
+    /// Write the changed pixels of a logical frame to all boards.
+    pub(crate) fn set_frame(&mut self, frame: &[Rgb]) -> Result<FrameWrite, SeesawError<D::Error>> {
+        let mut total = FrameWrite::default();
+        for board in self.boards.iter_mut() {
+            let write = board.write(&board.pixels(frame))?;
+            total.pixels += write.pixels;
+            total.writes += write.writes;
+        }
+        Ok(total)
+    }
+
+    /// Show pending LED changes on all boards that have any.
+    pub(crate) fn sync(&mut self) -> Result<(), SeesawError<D::Error>> {
+        for board in self.boards.iter_mut().filter(|board| board.dirty) {
+            board.trellis.sync_neopixel()?;
+            board.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Poll all keypads, returning button events in logical key indices.
+    pub(crate) fn poll(&mut self) -> Result<Vec<ButtonEvent, MAX_EVENTS>, SeesawError<D::Error>> {
+        let mut events = Vec::new();
+        for board in self.boards.iter_mut() {
+            for evt in board.trellis.poll()? {
+                let key = board.tile.to_logical(evt.x, evt.y) as u8;
+                let event = match evt.event {
+                    EventType::Pressed => ButtonEvent::Pressed(key),
+                    EventType::Released => ButtonEvent::Released(key),
+                    _ => continue, // Other event types (hold, etc.)
+                };
+                // Events beyond MAX_EVENTS per poll are dropped
+                _ = events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
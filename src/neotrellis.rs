@@ -1,42 +1,83 @@
-//! # NeoTrellis 4x4 LED Matrix Driver
+//! # NeoTrellis LED Matrix Driver
 //!
-//! Controls an Adafruit NeoTrellis 4x4 RGB LED matrix via I2C to display
-//! real-time pattern previews from Pixelblaze.
+//! Controls one or more Adafruit NeoTrellis 4x4 RGB LED boards via I2C to
+//! display real-time pattern previews from Pixelblaze.
 //!
 //! ## Hardware
-//! - **Device**: NeoTrellis 4x4 (16 RGB LEDs + 16 buttons)
+//! - **Device**: NeoTrellis 4x4 (16 RGB LEDs + 16 buttons per board), tiled
+//!   according to `buntspiel_core::layout`
 //! - **Interface**: I2C (SDA=Pin6, SCL=Pin7) on Pico W
 //!
 //! ## Communication
-//! Receives RGB frames via `CONTROL_CHANNEL` from the Pixelblaze WebSocket client.
+//! Receives RGB frames via [`show_frame`] from the Pixelblaze WebSocket client
+//! and the fallback animations, where a newer frame replaces one not shown yet.
+//! Other commands arrive in order via `CONTROL_CHANNEL`. Button presses are
+//! reported via `buttons::BUTTON_CHANNEL`.
+//!
+//! ## Color Pipeline
+//! Every frame passes [`ColorPipeline`] before it reaches the LEDs: gamma
+//! correction, white balance, global brightness and a current limit.
 
-use adafruit_seesaw::{
-    devices::{NeoTrellis, SeesawDevice, SeesawDeviceInit},
-    prelude::NeopixelModule,
-    SeesawError, SeesawRefCell,
+use adafruit_seesaw::{SeesawError, SeesawRefCell};
+use defmt::{info, warn, Debug2Format};
+use embassy_futures::select::{select3, Either3};
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, signal::Signal,
 };
-use defmt::{info, Debug2Format};
-use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
-use embassy_time::{Duration, Timer};
-
-/// Number of RGB LEDs in the NeoTrellis 4x4 matrix
-pub(crate) const NEOTRELLIS_PIXELS: usize = 16;
-
-/// RGB color value for a single LED pixel (0-255).
-#[derive(defmt::Format, Default)]
-pub(crate) struct Rgb {
-    /// Red component (0-255)
-    pub(crate) r: u8,
-    /// Green component (0-255)
-    pub(crate) g: u8,
-    /// Blue component (0-255)
-    pub(crate) b: u8,
-}
+use embassy_time::{Duration, Instant, Timer};
+use portable_atomic::{AtomicU32, Ordering};
+
+use buntspiel_core::color::ColorPipeline;
+use buntspiel_core::frame::{Frame, Rgb};
+use buntspiel_core::keymap::ButtonEvent;
+use buntspiel_core::layout::{MATRIX_COLUMNS, MATRIX_ROWS, NEOTRELLIS_PIXELS, TILES};
+
+use crate::buttons::BUTTON_CHANNEL;
+use crate::config::config;
+use crate::multitrellis::{FrameWrite, MultiTrellis};
+
+/// How often the keypad is polled for button events.
+const KEYPAD_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Color shown on a key while it is held down.
+const PRESSED_COLOR: Rgb = Rgb {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+
+/// How long a key flashes, see [`Control::FlashKey`].
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// Gamma of the LED correction curve.
+const GAMMA: f32 = 2.2;
+
+/// Global brightness after startup (0-255), until one is stored in the
+/// configuration.
+pub(crate) const DEFAULT_BRIGHTNESS: u8 = 0x40;
 
-/// Control messages for the NeoTrellis LED matrix.
+/// Per-channel scale (0-255) to neutralize the LEDs' tint, tune to taste.
+const WHITE_BALANCE: Rgb = Rgb {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+
+/// Current budget for all LEDs together (mA), keeps within USB power limits.
+const CURRENT_LIMIT_MA: u32 = 500;
+
+/// Control messages for the NeoTrellis LED matrix (frames go through
+/// [`show_frame`] instead).
 pub(crate) enum Control {
-    /// Complete frame of RGB data for the entire 4x4 matrix (row-major order).
-    SyncFrame([Rgb; NEOTRELLIS_PIXELS]),
+    /// Change the global brightness by the given amount (0-255 scale).
+    AdjustBrightness(i16),
+    /// Light a key in a color for [`FLASH_DURATION`], over the frame.
+    FlashKey {
+        /// Key index
+        key: u8,
+        /// Color shown on the key
+        color: Rgb,
+    },
 }
 
 /// Maximum number of control messages that can be queued.
@@ -46,6 +87,69 @@ pub(crate) const MAX_CONTROL: usize = 5;
 pub(crate) static CONTROL_CHANNEL: Channel<CriticalSectionRawMutex, Control, MAX_CONTROL> =
     Channel::new();
 
+/// Mailbox holding the most recent frame not shown yet.
+static FRAME_MAILBOX: Signal<CriticalSectionRawMutex, Frame> = Signal::new();
+
+/// Hand a frame to the display, replacing any frame not shown yet.
+///
+/// Never blocks. Returns `true` if a pending frame was replaced.
+pub(crate) fn show_frame(frame: Frame) -> bool {
+    let replaced = FRAME_MAILBOX.signaled();
+    FRAME_MAILBOX.signal(frame);
+    if replaced {
+        DISPLAY_STATS
+            .replaced_frames
+            .fetch_add(1, Ordering::Relaxed);
+    }
+    replaced
+}
+
+/// Display statistics, updated by the driver on core 1.
+pub(crate) struct DisplayStats {
+    /// Frames shown
+    pub(crate) frames: AtomicU32,
+    /// Frames replaced by a newer one before they were shown
+    pub(crate) replaced_frames: AtomicU32,
+    /// Frames identical to the one shown, neither written nor synced
+    pub(crate) unchanged_frames: AtomicU32,
+    /// Pixels written over I2C
+    pub(crate) pixels_written: AtomicU32,
+    /// Pixels not written because they didn't change
+    pub(crate) pixels_skipped: AtomicU32,
+    /// Seesaw buffer writes (one per run of changed pixels)
+    pub(crate) buffer_writes: AtomicU32,
+}
+
+impl DisplayStats {
+    const fn new() -> Self {
+        Self {
+            frames: AtomicU32::new(0),
+            replaced_frames: AtomicU32::new(0),
+            unchanged_frames: AtomicU32::new(0),
+            pixels_written: AtomicU32::new(0),
+            pixels_skipped: AtomicU32::new(0),
+            buffer_writes: AtomicU32::new(0),
+        }
+    }
+
+    fn record(&self, write: FrameWrite) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        if write.pixels == 0 {
+            self.unchanged_frames.fetch_add(1, Ordering::Relaxed);
+        }
+        let skipped = NEOTRELLIS_PIXELS.saturating_sub(write.pixels);
+        self.pixels_written
+            .fetch_add(write.pixels as u32, Ordering::Relaxed);
+        self.pixels_skipped
+            .fetch_add(skipped as u32, Ordering::Relaxed);
+        self.buffer_writes
+            .fetch_add(write.writes as u32, Ordering::Relaxed);
+    }
+}
+
+/// Statistics of the NeoTrellis display, for monitoring.
+pub(crate) static DISPLAY_STATS: DisplayStats = DisplayStats::new();
+
 /// I2C communication frequency.
 pub(crate) const I2C_FREQUENCY: u32 = 100_000;
 
@@ -62,11 +166,15 @@ pub(crate) async fn neotrellis_task(
     // Wrap I2C driver in Seesaw-compatible interface
     let seesaw = SeesawRefCell::new(delay, i2c);
 
+    // Color correction, kept across driver restarts
+    let mut pipeline =
+        ColorPipeline::new(GAMMA, config().brightness, WHITE_BALANCE, CURRENT_LIMIT_MA);
+
     info!("neotrellis: 🚀 Starting NeoTrellis control loop");
 
     // Main task loop with error recovery
     loop {
-        if let Err(e) = drive_neotrellis(seesaw.acquire_driver()).await {
+        if let Err(e) = drive_neotrellis(|| seesaw.acquire_driver(), &mut pipeline).await {
             info!(
                 "neotrellis: ❌ Driver error: {}. Restarting in 2 seconds...",
                 Debug2Format(&e)
@@ -78,67 +186,107 @@ pub(crate) async fn neotrellis_task(
 
 /// Core NeoTrellis driver loop.
 ///
-/// Initializes the device and processes frame updates from the control channel.
+/// Initializes all boards, shows frames from the mailbox, processes control
+/// messages and polls the keypads in between.
 async fn drive_neotrellis<Seesaw: adafruit_seesaw::Driver>(
-    seesaw: Seesaw,
+    acquire_driver: impl FnMut() -> Seesaw,
+    pipeline: &mut ColorPipeline,
 ) -> Result<(), SeesawError<Seesaw::Error>> {
-    // Initialize NeoTrellis (default addr 0x2E)
-    let mut neotrellis = NeoTrellis::new_with_default_addr(seesaw).init()?;
-    info!("neotrellis: ✅ Hardware initialized successfully");
+    // Initialize all boards of the configured layout
+    let mut trellis = MultiTrellis::new(TILES, acquire_driver)?;
+    info!(
+        "neotrellis: ✅ Hardware initialized successfully ({} boards, {}x{})",
+        TILES.len(),
+        MATRIX_COLUMNS,
+        MATRIX_ROWS
+    );
 
     // Get receiver for LED control messages
     let receiver = CONTROL_CHANNEL.receiver();
 
+    // Last frame received, used to restore keys after they are released
+    let mut frame: Frame = [Rgb::default(); NEOTRELLIS_PIXELS];
+    // Keys currently held down (shown in PRESSED_COLOR)
+    let mut held = [false; NEOTRELLIS_PIXELS];
+    // Flashing key, its color and when the flash ends
+    let mut flash: Option<(usize, Rgb, Instant)> = None;
+
     info!("neotrellis: 🎨 Ready to display patterns");
 
     // Main frame processing loop
     loop {
-        // Wait for new frame data from Pixelblaze
-        let Control::SyncFrame(preview_frame) = receiver.receive().await;
-
-        // Update all 16 LEDs (row-major order)
-        for (n, Rgb { r, g, b }) in preview_frame.iter().enumerate() {
-            neotrellis.set_nth_neopixel_color(
-                n.try_into().expect("Pixel index out of range"), // Should never fail for 0-15
-                *r,
-                *g,
-                *b,
-            )?;
+        // Wait for a new frame or command, waking up regularly to poll the keypads
+        let update = match select3(
+            FRAME_MAILBOX.wait(),
+            receiver.receive(),
+            Timer::after(KEYPAD_POLL_INTERVAL),
+        )
+        .await
+        {
+            Either3::First(next) => {
+                frame = next;
+                true
+            }
+            Either3::Second(Control::AdjustBrightness(delta)) => {
+                let brightness = (i16::from(pipeline.brightness()) + delta).clamp(0, 255);
+                info!("neotrellis: 🔆 Setting brightness to {}", brightness);
+                pipeline.set_brightness(brightness as u8);
+                true
+            }
+            Either3::Second(Control::FlashKey { key, color }) => {
+                flash = Some((usize::from(key), color, Instant::now() + FLASH_DURATION));
+                true
+            }
+            Either3::Third(()) => false,
+        };
+
+        // End the flash, restoring the key
+        let flash_ended = flash.is_some_and(|(_, _, until)| Instant::now() >= until);
+        if flash_ended {
+            flash = None;
         }
 
-        // Commit LED changes
-        neotrellis.sync_neopixel()?;
-        // TODO: Implement button event handling for VJ interface
-        // This code would enable pattern selection via physical button presses
-        //
-        // for evt in neotrellis.poll()? {
-        //     info!("neotrellis: 🔘 Button event at x={} y={}", evt.x, evt.y);
-        //     match evt.event {
-        //         EventType::Pressed => {
-        //             // Button pressed - could trigger pattern selection
-        //             let led_index = (evt.y * 4) + evt.x;
-        //             neotrellis.set_nth_neopixel_color(
-        //                 led_index.into(),
-        //                 0xFF, 0xFF, 0xFF, // White feedback
-        //             )?;
-        //             neotrellis.sync_neopixel()?;
-        //             // TODO: Send pattern selection command to Pixelblaze
-        //         }
-        //         EventType::Released => {
-        //             // Button released - restore pattern color
-        //             let led_index = (evt.y * 4) + evt.x;
-        //             neotrellis.set_nth_neopixel_color(
-        //                 led_index.into(),
-        //                 evt.x * 0x40,  // Position-based color
-        //                 0xFF,
-        //                 evt.y * 0x40,
-        //             )?;
-        //             neotrellis.sync_neopixel()?;
-        //         }
-        //         _ => {
-        //             // Other event types (hold, etc.)
-        //         }
-        //     };
-        // }
+        if update || flash_ended {
+            // Update all LEDs, keeping held keys lit and the flashing key on top
+            let mut shown = frame;
+            for (pixel, _) in shown.iter_mut().zip(held).filter(|(_, held)| *held) {
+                *pixel = PRESSED_COLOR;
+            }
+            if let Some((key, color, _)) = flash {
+                if let Some(pixel) = shown.get_mut(key) {
+                    *pixel = color;
+                }
+            }
+            pipeline.apply(&mut shown);
+            let write = trellis.set_frame(&shown)?;
+            DISPLAY_STATS.record(write);
+
+            // Commit LED changes, skipped for identical frames
+            trellis.sync()?;
+        }
+
+        // Handle button events for the VJ interface
+        for event in trellis.poll()? {
+            let (key, color) = match event {
+                ButtonEvent::Pressed(key) => (usize::from(key), PRESSED_COLOR),
+                ButtonEvent::Released(key) => {
+                    let key = usize::from(key);
+                    match flash {
+                        Some((flashing, color, _)) if flashing == key => (key, color),
+                        _ => (key, frame[key]),
+                    }
+                }
+            };
+            held[key] = matches!(event, ButtonEvent::Pressed(_));
+
+            // Immediate local feedback, before the Pixelblaze reacts
+            trellis.set_pixel(key, pipeline.correct(color))?;
+            trellis.sync()?;
+
+            // Forward to core 0 without blocking the LED loop
+            if BUTTON_CHANNEL.try_send(event).is_err() {
+                warn!("neotrellis: ⚠️  Button event dropped, queue full");
+            }
+        }
     }
 }
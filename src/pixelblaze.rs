@@ -1,8 +1,14 @@
 //! # Pixelblaze WebSocket Client
 //!
 //! Implements the Pixelblaze WebSocket protocol for real-time LED pattern preview.
-//! Connects to the Pixelblaze controller (typically 192.168.4.1:81) and streams
-//! RGB frame data to the NeoTrellis LED matrix.
+//! Connects to the Pixelblaze controller and streams RGB frame data to the
+//! NeoTrellis LED matrix.
+//!
+//! ## Endpoints
+//! Pixelblaze is tried at each endpoint of a fallback list in order, the
+//! endpoints stored in the configuration, by default its own access point
+//! (192.168.4.1:81). The list is replaced at run time with [`set_endpoints`]
+//! by the discovery task, once the lighthouse was heard on the network.
 //!
 //! ## Protocol
 //! - **Text**: JSON commands for config/control.
@@ -10,10 +16,8 @@
 //!   Format: `[message_type: u8, r1: u8, g1: u8, b1: u8, ...]`
 //!   (Preview Frame = Type 5)
 
-use core::cell::Cell;
-use core::cmp::min;
-use core::net::{IpAddr, Ipv4Addr, SocketAddr};
-use core::str::{from_utf8, FromStr};
+use core::cell::{Cell, RefCell};
+use core::str::from_utf8;
 use edge_net::nal::TcpSplit;
 use futures::try_join;
 
@@ -23,21 +27,42 @@ use edge_http::ws::{MAX_BASE64_KEY_LEN, MAX_BASE64_KEY_RESPONSE_LEN, NONCE_LEN};
 use edge_nal_embassy::{TcpSocket, TcpSocketRead, TcpSocketWrite};
 use edge_ws::{FrameHeader, FrameType};
 use embassy_net::driver::Driver;
-use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_sync::channel::Channel;
-use embassy_time::{Duration, Timer};
-use heapless::String;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+use portable_atomic::Ordering;
 use rand::{rngs::SmallRng, RngCore};
 
-use crate::neotrellis::{self, Rgb, NEOTRELLIS_PIXELS};
+use buntspiel_core::backoff::Backoff;
+use buntspiel_core::endpoint::{Endpoint, EndpointList, ACCESS_POINT};
+use buntspiel_core::frame::Frame;
+use buntspiel_core::pixelblaze_state::{PatternId, StateUpdate};
+use buntspiel_core::protocol::{
+    encode_brightness, encode_set_active_pattern, encode_set_controls, PatternControls,
+    GET_CONFIG, LIST_PROGRAMS, SEND_UPDATES, UNPAUSE,
+};
+use buntspiel_core::session::{
+    Message, PatternChoice, PatternSwitch, ReceiveError, Received, Session,
+    IDLE_CHECK_AFTER_MS, MONITOR_INTERVAL_MS, PATTERN_SWITCH_TIMEOUT_MS,
+};
+
+use crate::config;
+use crate::neotrellis;
+use crate::preview;
 
 // Pixelblaze connection configuration
-// Default Pixelblaze access point configuration when running in AP mode
-const PIXELBLAZE_WS_HOST: &str = "192.168.4.1:81";        // WebSocket host:port
-const PIXELBLAZE_WS_ORIGIN: &str = "http://192.168.4.1";  // HTTP origin for WebSocket upgrade
-const PIXELBLAZE_WS_URI: &str = "/";                      // WebSocket URI path
-const PIXELBLAZE_PORT: u16 = 81;                          // WebSocket port (default Pixelblaze)
-const PIXELBLAZE_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 4, 1)); // Static IP
+/// Endpoints used until endpoints are stored in the configuration
+pub(crate) const DEFAULT_ENDPOINTS: [Endpoint; 1] = [ACCESS_POINT];
+const PIXELBLAZE_WS_URI: &str = "/"; // WebSocket URI path
+
+/// How long to wait for an endpoint before trying the next one
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay between connection attempts: 1 s doubling up to 1 min, up to a
+/// quarter shorter at random
+const RECONNECT_BACKOFF: Backoff = Backoff::new(1_000, 60_000, 25);
 
 // Network resource constraints
 const MAX_SOCKETS: usize = 1; // Only one TCP socket allowed (memory constraint)
@@ -52,10 +77,25 @@ pub(crate) enum Control {
     SubscribePreviewFrames,
     /// Request current Pixelblaze configuration
     GetConfig,
+    /// Request the list of patterns stored on Pixelblaze
+    GetProgramList,
+    /// Switch to the N-th pattern of the program list
+    SelectPattern(usize),
+    /// Switch to the pattern after the active one in the program list
+    NextPattern,
+    /// Switch to the pattern before the active one in the program list
+    PreviousPattern,
+    /// Change the global Pixelblaze brightness by the given amount (0.0-1.0 scale)
+    AdjustBrightness(f32),
     /// Close the WebSocket connection gracefully
     Close,
-    /// Set the active pattern on Pixelblaze (not fully implemented)
-    SetActivePattern,
+    /// Switch Pixelblaze to the given pattern, optionally setting its controls
+    SetActivePattern {
+        /// Pattern ID as listed in the program list
+        id: PatternId,
+        /// Control values applied after switching
+        controls: Option<PatternControls>,
+    },
 }
 
 /// Channel for sending control commands to the Pixelblaze client.
@@ -65,6 +105,31 @@ pub(crate) static PIXELBLAZE_CONTROL_CHANNEL: Channel<
     MAX_CONTROL,
 > = Channel::new();
 
+/// Endpoints tried in order, `None` until set at run time.
+static ENDPOINTS: Mutex<CriticalSectionRawMutex, RefCell<Option<EndpointList>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Replace the Pixelblaze endpoints, used from the next connection attempt.
+pub(crate) fn set_endpoints(endpoints: EndpointList) {
+    info!("pixelblaze: 🧭 Endpoints set to {}", endpoints);
+    ENDPOINTS.lock(|e| e.replace(Some(endpoints)));
+}
+
+/// Endpoints to try, in order: the configured ones until set at run time.
+pub(crate) fn endpoints() -> EndpointList {
+    ENDPOINTS
+        .lock(|e| e.borrow().clone())
+        .unwrap_or_else(|| config::config().endpoints)
+}
+
+/// Signal reporting the result of the most recent pattern switch.
+pub(crate) static PATTERN_SWITCH_SIGNAL: Signal<CriticalSectionRawMutex, PatternSwitch> =
+    Signal::new();
+
+/// Signal raised when the connection ended, dropping the pending pattern
+/// switch and commands still queued for it.
+pub(crate) static CONNECTION_LOST_SIGNAL: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
 /// Pixelblaze client error types
 #[derive(Debug, defmt::Format)]
 pub enum Error {
@@ -84,50 +149,9 @@ impl<E> From<edge_http::io::Error<E>> for Error {
         Error::Error
     }
 }
-
-/// Pixelblaze binary message types.
-#[derive(defmt::Format, PartialEq, Eq)]
-pub enum PixelblazeMessageType {
-    // Messages from Web UI to Pixelblaze
-    /// Upload pattern source code to Pixelblaze
-    PutSourceCode,
-    /// Upload compiled bytecode to Pixelblaze
-    PutByteCode,
-    /// Send preview image data
-    PreviewImage,
-    /// Request list of available patterns
-    GetProgramList,
-    /// Upload pixel mapping configuration
-    PutPixelMap,
-
-    // Messages from Pixelblaze to Web UI
-    /// **Most important**: Real-time RGB frame data for LED preview
-    /// Format: [5, r1, g1, b1, r2, g2, b2, ...]
-    PreviewFrame,
-    /// Response with pattern source code
-    GetSourceCode,
-
-    // Bidirectional messages
-    /// LED strip expander configuration
-    ExpanderConfig,
-
-    /// Unknown or unsupported message type
-    Unknown(u8),
-}
-
-impl From<u8> for PixelblazeMessageType {
-    fn from(value: u8) -> Self {
-        match value {
-            1 => Self::PutSourceCode,
-            3 => Self::PutByteCode,
-            4 => Self::PreviewImage,
-            5 => Self::PreviewFrame,
-            6 => Self::GetSourceCode,
-            7 => Self::GetProgramList,
-            8 => Self::PutPixelMap,
-            9 => Self::ExpanderConfig,
-            v => Self::Unknown(v),
-        }
+impl From<core::fmt::Error> for Error {
+    fn from(_: core::fmt::Error) -> Self {
+        Error::Error
     }
 }
 
@@ -138,39 +162,61 @@ impl From<u8> for PixelblazeMessageType {
 #[embassy_executor::task]
 pub(crate) async fn pixelblaze_task(
     stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>,
-    rng: SmallRng,
+    mut rng: SmallRng,
 ) -> ! {
     // Allocate network buffers for TCP communication
     let tcpbuf = edge_nal_embassy::TcpBuffers::new();
     let mut buf = [0_u8; 2048]; // Buffer for WebSocket frames
     let mut nonce = [0_u8; NONCE_LEN]; // WebSocket handshake nonce
+    let mut backoff = RECONNECT_BACKOFF;
 
     // Main connection loop - never exits
     loop {
-        info!("pixelblaze: 🔌 Attempting connection to Pixelblaze...");
-        let mut tcp = edge_nal_embassy::Tcp::<_, MAX_SOCKETS>::new(stack, &tcpbuf);
-
-        match PixelStreamer::connect(&mut tcp, rng.clone(), &mut buf, &mut nonce).await {
-            Ok((pixel_streamer, socket)) => {
-                info!("pixelblaze: ✅ WebSocket connection established!");
-
-                // Run the main communication loop
-                if pixel_streamer
-                    .communicate(socket, rng.clone())
-                    .await
-                    .is_err()
-                {
-                    warn!("pixelblaze: ❌ WebSocket communication failed");
+        // Try the endpoints in order, the first one accepting the connection wins
+        for endpoint in endpoints().iter() {
+            info!(
+                "pixelblaze: 🔌 Attempting connection to Pixelblaze at {}...",
+                endpoint
+            );
+            let mut tcp = edge_nal_embassy::Tcp::<_, MAX_SOCKETS>::new(stack, &tcpbuf);
+
+            let connection = with_timeout(
+                CONNECT_TIMEOUT,
+                PixelStreamer::connect(&mut tcp, endpoint, rng.clone(), &mut buf, &mut nonce),
+            )
+            .await;
+            match connection {
+                Ok(Ok((pixel_streamer, socket))) => {
+                    info!("pixelblaze: ✅ WebSocket connection established!");
+                    backoff.reset();
+
+                    // Run the main communication loop
+                    if pixel_streamer
+                        .communicate(socket, rng.clone())
+                        .await
+                        .is_err()
+                    {
+                        warn!("pixelblaze: ❌ WebSocket communication failed");
+                    }
+                    CONNECTION_LOST_SIGNAL.signal(());
+                    break;
+                }
+                Ok(Err(_)) => {
+                    warn!(
+                        "pixelblaze: ❌ Failed to establish WebSocket connection to {}",
+                        endpoint
+                    );
+                }
+                Err(_) => {
+                    warn!("pixelblaze: ⏰ Connection to {} timed out", endpoint);
                 }
-            }
-            Err(_) => {
-                warn!("pixelblaze: ❌ Failed to establish WebSocket connection");
             }
         }
 
-        // Wait before reconnecting (exponential backoff could be added here)
-        warn!("pixelblaze: 🔄 Reconnecting in 5 seconds...");
-        Timer::after(Duration::from_secs(5)).await;
+        // Wait longer after every failed attempt in a row
+        let delay = backoff.next_delay_ms(rng.next_u32());
+        warn!("pixelblaze: 🔄 Reconnecting in {} ms...", delay);
+        Timer::after_millis(delay).await;
     }
 }
 
@@ -178,19 +224,18 @@ pub(crate) async fn pixelblaze_task(
 ///
 /// Manages the streaming connection and performance metrics.
 struct PixelStreamer {
-    /// Currently active pattern ID (if known)
-    current_pattern_id: Cell<Option<String<17>>>,
-    /// Currently active pattern name (if known)
-    current_pattern_name: Cell<Option<String<50>>>,
-    /// Total frames received since connection (for FPS calculation)
-    received_frames: Cell<u64>,
-    /// Total frames dropped due to processing backlog
+    /// Pixelblaze state, program list and frame statistics
+    session: RefCell<Session>,
+    /// Signalled whenever a pattern switch is sent, starts its timeout
+    switch_started: Signal<NoopRawMutex, ()>,
+    /// Total frames replaced by a newer one before the display showed them
     dropped_frames: Cell<u64>,
 }
 impl<'b> PixelStreamer {
     /// Establish WebSocket connection to Pixelblaze.
     async fn connect<'d, D>(
         tcp: &'d mut edge_nal_embassy::Tcp<'d, D, MAX_SOCKETS>,
+        endpoint: &Endpoint,
         mut rng: SmallRng,
         rx_buf: &'d mut [u8],
         nonce: &'d mut [u8; NONCE_LEN],
@@ -199,20 +244,22 @@ impl<'b> PixelStreamer {
         D: Driver,
     {
         // Create HTTP connection to Pixelblaze
-        let mut conn: Connection<_> =
-            Connection::new(rx_buf, tcp, SocketAddr::new(PIXELBLAZE_IP, PIXELBLAZE_PORT));
+        let mut conn: Connection<_> = Connection::new(rx_buf, tcp, endpoint.addr);
 
         // Generate random nonce for WebSocket handshake security
         rng.fill_bytes(nonce);
 
         // Step 1: Send WebSocket upgrade request
+        // Pixelblaze expects the origin of its own web UI
+        let host = endpoint.host();
+        let origin = endpoint.origin();
         let mut buf = [0_u8; MAX_BASE64_KEY_LEN];
         conn.initiate_ws_upgrade_request(
-            Some(PIXELBLAZE_WS_HOST),   // Host header
-            Some(PIXELBLAZE_WS_ORIGIN), // Origin header
-            PIXELBLAZE_WS_URI,          // Request URI (/)
-            None,                       // No subprotocol
-            nonce,                      // Security nonce
+            Some(&host),       // Host header
+            Some(&origin),     // Origin header
+            PIXELBLAZE_WS_URI, // Request URI (/)
+            None,              // No subprotocol
+            nonce,             // Security nonce
             &mut buf,
         )
         .await?;
@@ -235,9 +282,8 @@ impl<'b> PixelStreamer {
 
         Ok((
             PixelStreamer {
-                current_pattern_name: Cell::new(None),
-                current_pattern_id: Cell::new(None),
-                received_frames: Cell::new(0),
+                session: RefCell::new(Session::default()),
+                switch_started: Signal::new(),
                 dropped_frames: Cell::new(0),
             },
             socket,
@@ -257,11 +303,12 @@ impl<'b> PixelStreamer {
         // Split socket for concurrent read/write operations
         let (rx, tx) = socket.split();
 
-        // Run all three loops concurrently - any failure terminates all
+        // Run all loops concurrently - any failure terminates all
         try_join!(
-            self.monitoring_loop(),    // FPS monitoring and health checks
-            self.send_loop(tx, rng),   // Outgoing command processing
-            self.receive_loop(rx),     // Incoming frame processing
+            self.monitoring_loop(),     // FPS monitoring and health checks
+            self.send_loop(tx, rng),    // Outgoing command processing
+            self.receive_loop(rx),      // Incoming frame processing
+            self.pattern_switch_loop(), // Pattern switch timeouts
         )?;
 
         info!("pixelblaze: 🔌 Connection terminated, cleaning up");
@@ -278,48 +325,72 @@ impl<'b> PixelStreamer {
         info!("pixelblaze: 🔧 Initializing connection...");
         Timer::after_millis(500).await;
         control_commands.send(Control::GetConfig).await;
-        Timer::after_millis(500).await;
-
-        // TODO: Conditional pattern activation
-        // Only set active pattern if GetConfig shows no pattern is running
-        // Current issue: SetActivePattern command doesn't seem to work reliably
-        // Expected response: {"activeProgram":{"name":"Pattern Name","activeProgramId":"abc123","controls":{}},"sequencerMode":2,"runSequencer":true}
-        // Actual response: {"activeProgram":{"name":"","activeProgramId":null,"controls":{}},"sequencerMode":2,"runSequencer":true}
-        //control_commands.send(Control::SetActivePattern).await;
+        control_commands.send(Control::GetProgramList).await;
+        Timer::after_millis(IDLE_CHECK_AFTER_MS - 500).await;
+
+        // Start the first stored pattern if Pixelblaze isn't running one
+        let idle_pattern = self.session.borrow().idle_pattern();
+        if let Some(id) = idle_pattern {
+            info!("pixelblaze: 💤 No active pattern, starting {}", id.as_str());
+            control_commands
+                .send(Control::SetActivePattern { id, controls: None })
+                .await;
+        }
 
         // Frame rate monitoring variables
-        let mut last_received_frames: u64 = 0;
         let mut last_dropped_frames: u64 = 0;
+        let seconds = MONITOR_INTERVAL_MS / 1000;
 
         // Main monitoring loop - runs every 10 seconds
         loop {
-            Timer::after_millis(10000).await;
+            Timer::after_millis(MONITOR_INTERVAL_MS).await;
 
             // Calculate frame rates over the last 10-second window
-            let new_received_frames = self.received_frames.get();
-            let fps_received = new_received_frames - last_received_frames;
-            last_received_frames = new_received_frames;
+            let health = self.session.borrow_mut().check_frame_rate();
 
             let new_dropped_frames = self.dropped_frames.get();
             let fps_dropped = new_dropped_frames - last_dropped_frames;
             last_dropped_frames = new_dropped_frames;
 
             // Health check: if frame rate is too low, resubscribe
-            if fps_received < 1 {
+            if health.resubscribe {
                 // Use try_send to avoid blocking on channel full
                 _ = control_commands.try_send(Control::SubscribePreviewFrames);
                 warn!(
                     "pixelblaze: ⚠️  Low frame rate - rx={}/s dropped={}/s (resubscribing)",
-                    fps_received / 10,
-                    fps_dropped / 10,
+                    health.frames / seconds,
+                    fps_dropped / seconds,
                 );
             } else {
                 info!(
                     "pixelblaze: 📊 Frame rate healthy - rx={}/s dropped={}/s",
-                    fps_received / 10,
-                    fps_dropped / 10,
+                    health.frames / seconds,
+                    fps_dropped / seconds,
                 );
             }
+
+            // I2C savings of the NeoTrellis display (totals since boot)
+            let stats = &neotrellis::DISPLAY_STATS;
+            info!(
+                "pixelblaze: 🖼️  Display - frames={} replaced={} unchanged={} pixels written={} skipped={} writes={}",
+                stats.frames.load(Ordering::Relaxed),
+                stats.replaced_frames.load(Ordering::Relaxed),
+                stats.unchanged_frames.load(Ordering::Relaxed),
+                stats.pixels_written.load(Ordering::Relaxed),
+                stats.pixels_skipped.load(Ordering::Relaxed),
+                stats.buffer_writes.load(Ordering::Relaxed),
+            );
+        }
+    }
+
+    /// Report pattern switches Pixelblaze didn't confirm in time, even if
+    /// no further message arrives.
+    async fn pattern_switch_loop(&self) -> Result<(), Error> {
+        loop {
+            self.switch_started.wait().await;
+            // A switch sent meanwhile restarts the wait on the next pass
+            Timer::after_millis(PATTERN_SWITCH_TIMEOUT_MS).await;
+            self.check_pattern_switch();
         }
     }
 
@@ -332,7 +403,13 @@ impl<'b> PixelStreamer {
         let control_commands = PIXELBLAZE_CONTROL_CHANNEL.receiver();
 
         // Clear any stale commands from previous connections
-        while control_commands.try_receive().is_ok() {}
+        let mut stale = false;
+        while control_commands.try_receive().is_ok() {
+            stale = true;
+        }
+        if stale {
+            CONNECTION_LOST_SIGNAL.signal(());
+        }
 
         info!("pixelblaze: 📤 Send loop ready for commands");
 
@@ -351,35 +428,45 @@ impl<'b> PixelStreamer {
 
                 Control::SubscribePreviewFrames => {
                     info!("pixelblaze: 🎬 Subscribing to preview frames");
-                    send_text_frame(&mut tx, &mut rng, r#"{"sendUpdates":true}"#).await?;
+                    send_text_frame(&mut tx, &mut rng, SEND_UPDATES).await?;
                 }
 
                 Control::GetConfig => {
                     info!("pixelblaze: ⚙️  Requesting configuration");
-                    send_text_frame(&mut tx, &mut rng, r#"{"getConfig":true}"#).await?;
+                    send_text_frame(&mut tx, &mut rng, GET_CONFIG).await?;
+                }
+
+                Control::GetProgramList => {
+                    info!("pixelblaze: 📚 Requesting program list");
+                    send_text_frame(&mut tx, &mut rng, LIST_PROGRAMS).await?;
+                }
+
+                Control::SetActivePattern { id, controls } => {
+                    self.set_active_pattern(&mut tx, &mut rng, id, controls)
+                        .await?;
+                }
+
+                Control::SelectPattern(index) => {
+                    self.set_listed_pattern(&mut tx, &mut rng, PatternChoice::Index(index))
+                        .await?;
+                }
+
+                Control::NextPattern => {
+                    self.set_listed_pattern(&mut tx, &mut rng, PatternChoice::Next)
+                        .await?;
+                }
+
+                Control::PreviousPattern => {
+                    self.set_listed_pattern(&mut tx, &mut rng, PatternChoice::Previous)
+                        .await?;
                 }
 
-                Control::SetActivePattern => {
-                    info!("pixelblaze: 🎨 Setting active pattern");
-                    // TODO: Make pattern ID configurable instead of hardcoded
-                    send_text_frame(
-                        &mut tx,
-                        &mut rng,
-                        r#"{"setActivePattern":"kuJfFyCSkCKNasyNE"}"#,
-                    )
-                    .await?;
-
-                    // Update local state tracking
-                    self.current_pattern_name
-                        .replace(Some(String::from_str("Editor: blink fade").unwrap()));
-                    self.current_pattern_id
-                        .replace(Some(String::from_str("kuJfFyCSkCKNasyNE").unwrap()));
-
-                    // Send additional configuration commands
-                    Timer::after_millis(100).await;
-                    send_text_frame(&mut tx, &mut rng, r#"{"setControls":{}}"#).await?;
-                    Timer::after_millis(100).await;
-                    send_text_frame(&mut tx, &mut rng, r#"{"pause":false}"#).await?;
+                Control::AdjustBrightness(delta) => {
+                    let brightness = self.session.borrow_mut().adjust_brightness(delta);
+                    info!("pixelblaze: 🔆 Setting brightness to {}", brightness);
+
+                    // Don't persist on Pixelblaze, saves its flash from wear
+                    send_text_frame(&mut tx, &mut rng, &encode_brightness(brightness)?).await?;
                 }
 
                 Control::Close => {
@@ -397,7 +484,12 @@ impl<'b> PixelStreamer {
         }
     }
 
+    /// WebSocket receiving loop.
+    async fn receive_loop<'d>(&self, mut rx: TcpSocketRead<'d>) -> Result<(), Error> {
+        let control_commands = PIXELBLAZE_CONTROL_CHANNEL.sender();
+        let mut buf = [0_u8; 2048]; // Buffer for incoming frame payloads
 
+        info!("pixelblaze: 📥 Receive loop ready for frames");
 
         loop {
             // Read WebSocket frame header and payload
@@ -416,11 +508,7 @@ impl<'b> PixelStreamer {
             match header.frame_type {
                 FrameType::Text(_) => {
                     if let Ok(payload_str) = from_utf8(payload) {
-                        // Filter out high-frequency FPS status messages to reduce log spam
-                        // TODO: Parse and handle FPS status messages properly
-                        if !payload_str.starts_with(r#"{"fps""#) {
-                            info!("pixelblaze: 📄 Text message: {}", payload_str);
-                        }
+                        self.handle_message(Message::Text(payload_str));
                     } else {
                         warn!(
                             "pixelblaze: ⚠️  Invalid UTF-8 in text frame: {}",
@@ -428,25 +516,7 @@ impl<'b> PixelStreamer {
                         );
                     }
                 }
-                FrameType::Binary(_) => {
-                    let t = PixelblazeMessageType::from(payload[0]);
-                    if t == PixelblazeMessageType::PreviewFrame {
-                        // This is the critical path - RGB frame data for LED display
-                        match neotrellis::Control::try_from(payload) {
-                            Ok(neotrellis::Control::SyncFrame(frame)) => {
-                                self.handle_preview_frame(frame)
-                            }
-                            Err(e) => {
-                                error!("pixelblaze: ❌ Failed to parse preview frame: {}", e)
-                            }
-                        }
-                    } else {
-                        info!(
-                            "pixelblaze: 🔲 Binary message type={} payload={}",
-                            t, payload,
-                        );
-                    }
-                }
+                FrameType::Binary(_) => self.handle_message(Message::Binary(payload)),
                 FrameType::Ping => {
                     info!("pixelblaze: 🏓 Received ping, sending pong");
                     control_commands.send(Control::SendPong).await;
@@ -468,10 +538,151 @@ impl<'b> PixelStreamer {
         }
     }
 
+    /// Process a message from Pixelblaze.
+    fn handle_message(&self, message: Message<'_>) {
+        let received = self
+            .session
+            .borrow_mut()
+            .receive(message, preview::mapping());
+        match received {
+            // This is the critical path - RGB frame data for LED display
+            Ok(Received::Frame(frame)) => self.handle_preview_frame(frame),
+            Ok(Received::State(update)) => self.handle_state_update(update, message),
+            Ok(Received::ProgramList) => {
+                let session = self.session.borrow();
+                info!(
+                    "pixelblaze: 📚 Received program list with {} patterns ({} dropped)",
+                    session.programs.len(),
+                    session.programs.dropped,
+                );
+            }
+            Ok(Received::ProgramListPart) => {} // More frames to come
+            Ok(Received::Unhandled(t)) => {
+                info!("pixelblaze: 🔲 Binary message type={} payload={}", t, message);
+            }
+            Err(ReceiveError::Empty) => warn!("pixelblaze: ⚠️  Empty binary frame"),
+            Err(ReceiveError::Text(e)) => warn!(
+                "pixelblaze: ⚠️  Failed to parse text message ({}): {}",
+                e, message
+            ),
+            Err(ReceiveError::PreviewFrame(e)) => {
+                error!("pixelblaze: ❌ Failed to parse preview frame: {}", e)
+            }
+            Err(ReceiveError::ProgramList(e)) => {
+                warn!("pixelblaze: ⚠️  Invalid program list frame: {}", e)
+            }
+        }
+        self.check_pattern_switch();
+    }
+
+    /// Publish the outcome of a pattern switch on [`PATTERN_SWITCH_SIGNAL`]
+    /// once Pixelblaze confirmed it or the time is up.
+    fn check_pattern_switch(&self) {
+        let outcome = self
+            .session
+            .borrow_mut()
+            .pattern_switch(Instant::now().as_millis());
+        let Some(outcome) = outcome else {
+            return;
+        };
+        match &outcome {
+            PatternSwitch::Confirmed(id) => {
+                info!("pixelblaze: ✅ Pattern switch to {} confirmed", id.as_str())
+            }
+            PatternSwitch::Failed(id) => error!(
+                "pixelblaze: ❌ Pattern switch to {} failed, still running {}",
+                id.as_str(),
+                self.active_pattern_id().as_deref(),
+            ),
+        }
+        PATTERN_SWITCH_SIGNAL.signal(outcome);
+    }
+
+    /// Report what a JSON text message from Pixelblaze changed.
+    fn handle_state_update(&self, update: StateUpdate, message: Message<'_>) {
+        let session = self.session.borrow();
+        let state = &session.state;
+        match update {
+            // High-frequency FPS status messages are applied silently
+            update if update.status && !update.config && !update.active_program => {}
+            update if update.active_program => match &state.active_program {
+                Some(program) => info!(
+                    "pixelblaze: 🎨 Active pattern: '{}' (id={})",
+                    program.name.as_str(),
+                    program.id.as_deref(),
+                ),
+                None => info!("pixelblaze: 🎨 No active pattern"),
+            },
+            update if update.config => info!(
+                "pixelblaze: ⚙️  Config: name={} ver={} pixels={} brightness={}",
+                state.name.as_deref(),
+                state.version.as_deref(),
+                state.pixel_count,
+                state.brightness,
+            ),
+            _ => info!("pixelblaze: 📄 Text message: {}", message),
+        }
+    }
+
+    /// Switch Pixelblaze to a pattern.
+    ///
+    /// The switch is confirmed by the `activeProgram` message that follows,
+    /// see [`Self::check_pattern_switch`], or fails after
+    /// [`PATTERN_SWITCH_TIMEOUT_MS`] in [`Self::pattern_switch_loop`].
+    async fn set_active_pattern(
+        &self,
+        tx: &mut TcpSocketWrite<'_>,
+        rng: &mut SmallRng,
+        id: PatternId,
+        controls: Option<PatternControls>,
+    ) -> Result<(), Error> {
+        info!("pixelblaze: 🎨 Setting active pattern {}", id.as_str());
+        let encoded = encode_set_active_pattern(&id)?;
+        self.session
+            .borrow_mut()
+            .switching_to(id, Instant::now().as_millis());
+        self.switch_started.signal(());
+
+        send_text_frame(tx, rng, &encoded).await?;
+
+        // Send additional configuration commands
+        if let Some(controls) = controls {
+            Timer::after_millis(100).await;
+            send_text_frame(tx, rng, &encode_set_controls(&controls)?).await?;
+        }
+        Timer::after_millis(100).await;
+        send_text_frame(tx, rng, UNPAUSE).await?;
+        send_text_frame(tx, rng, GET_CONFIG).await?;
+
+        Ok(())
+    }
+
+    /// Switch to a pattern picked from the program list.
+    async fn set_listed_pattern(
+        &self,
+        tx: &mut TcpSocketWrite<'_>,
+        rng: &mut SmallRng,
+        choice: PatternChoice,
+    ) -> Result<(), Error> {
+        let id = self.session.borrow().pattern(choice);
+        match id {
+            Some(id) => self.set_active_pattern(tx, rng, id, None).await,
+            None => {
+                warn!("pixelblaze: ⚠️  Pattern not in program list (not received yet?)");
+                Ok(())
+            }
+        }
+    }
+
+    /// ID of the pattern currently running on Pixelblaze (if known).
+    fn active_pattern_id(&self) -> Option<PatternId> {
+        self.session.borrow().active_pattern_id()
+    }
+
     /// Process a preview frame from Pixelblaze.
-    fn handle_preview_frame(&self, frame: [Rgb; NEOTRELLIS_PIXELS]) {
+    fn handle_preview_frame(&self, frame: Frame) {
         // Debug logging (commented out to avoid spam at 60+ FPS)
-        // let received_frames = self.received_frames.get();
+        // let received_frames = self.session.borrow().received_frames();
         // if received_frames % 200 == 0 {
         //     info!(
         //         "pixelblaze: 🎨 Preview frame #{}: {:?}",
@@ -479,16 +690,9 @@ impl<'b> PixelStreamer {
         //     );
         // }
 
-        // Update frame statistics
-        let received_frames = self.received_frames.get();
-        self.received_frames.set(received_frames + 1);
-
         // Forward to NeoTrellis (non-blocking to avoid slowdown)
-        // If the channel is full, the frame is dropped and counted
-        if neotrellis::CONTROL_CHANNEL
-            .try_send(neotrellis::Control::SyncFrame(frame))
-            .is_err()
-        {
+        // A frame the display hasn't picked up yet is replaced and counted
+        if neotrellis::show_frame(frame) {
             // Frame dropped - NeoTrellis can't keep up
             self.dropped_frames.set(self.dropped_frames.get() + 1)
         }
@@ -517,48 +721,3 @@ async fn send_text_frame<'d>(
 
     Ok(())
 }
-
-/// Error types for preview frame parsing
-#[derive(defmt::Format)]
-pub(crate) enum PreviewFrameErr {
-    /// Frame data is invalid or corrupted
-    Invalid,
-}
-
-/// Convert Pixelblaze binary preview frame to NeoTrellis control message.
-impl TryFrom<&[u8]> for neotrellis::Control {
-    type Error = PreviewFrameErr;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        // Validate minimum frame size and message type
-        if value.len() < 4
-            || PixelblazeMessageType::from(value[0]) != PixelblazeMessageType::PreviewFrame
-        {
-            return Err(PreviewFrameErr::Invalid);
-        }
-
-        // RGB data starts after the message type byte
-        let rgb_bytes = value.len() - 1;
-
-        // Ensure we have complete RGB triplets (no partial pixels)
-        if rgb_bytes % 3 != 0 {
-            return Err(PreviewFrameErr::Invalid);
-        }
-
-        let mut preview_frame: [Rgb; NEOTRELLIS_PIXELS] = Default::default();
-
-        // Convert RGB bytes to pixel array
-        // TODO: Use Iterator.collect() once no_std supports it better
-        let pixels = rgb_bytes / 3;
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..min(NEOTRELLIS_PIXELS, pixels) {
-            let position = 1 + (i * 3); // Skip message type byte
-            let [r, g, b] = value[position..position + 3] else {
-                return Err(PreviewFrameErr::Invalid);
-            };
-            preview_frame[i] = Rgb { r, g, b }
-        }
-
-        Ok(neotrellis::Control::SyncFrame(preview_frame))
-    }
-}
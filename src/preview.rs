@@ -0,0 +1,86 @@
+//! # Preview Pixel Mapping
+//!
+//! Configuration and run-time state of the preview mapping, which reduces a
+//! Pixelblaze preview frame to the pixels of the NeoTrellis matrix (see
+//! `buntspiel_core::preview` for the strategies).
+//!
+//! The strategy is chosen at build time with [`DEFAULT_MAPPING`] and can be
+//! switched at run time with [`set_mapping`]. A configured pixel map takes
+//! precedence over the default.
+
+use core::cell::Cell;
+
+use defmt::{info, warn};
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use static_cell::StaticCell;
+
+use buntspiel_core::layout::{MATRIX_COLUMNS, MATRIX_ROWS};
+use buntspiel_core::pixel_map::{Axis, Projection, ProjectionTable};
+use buntspiel_core::preview::Mapping;
+
+/// Strategy used until switched at run time.
+pub(crate) const DEFAULT_MAPPING: Mapping = Mapping::Averaged;
+
+/// Strip index shown on each matrix pixel (row-major), if configured.
+///
+/// Set this to map specific LEDs of the lighthouse, e.g. one per side and
+/// level. Matrix pixels without an entry stay dark.
+const INDEX_TABLE: Option<&[u16]> = None;
+
+/// Lighthouse pixel map as downloaded from `http://<pixelblaze>/pixelmap.dat`,
+/// if configured (e.g. `Some(include_bytes!("../pixelmap.dat"))`). Not
+/// fetched at run time, see "Pixel Map" in the README.
+const PIXEL_MAP: Option<&[u8]> = None;
+
+/// How the pixel map is flattened onto the matrix.
+const PROJECTION: Projection = Projection::Cylindrical { vertical: Axis::Z };
+
+/// Strategy currently in use.
+static MAPPING: Mutex<CriticalSectionRawMutex, Cell<Mapping>> =
+    Mutex::new(Cell::new(DEFAULT_MAPPING));
+
+/// Strategy used for new preview frames.
+pub(crate) fn mapping() -> Mapping {
+    MAPPING.lock(|mapping| mapping.get())
+}
+
+/// The strategy after the current one, skipping unconfigured ones.
+pub(crate) fn next_mapping() -> Mapping {
+    mapping().next(INDEX_TABLE, projection())
+}
+
+/// Switch the strategy for all following preview frames.
+pub(crate) fn set_mapping(mapping: Mapping) {
+    MAPPING.lock(|current| current.set(mapping));
+}
+
+/// Projection of the configured pixel map, built once at startup.
+static PROJECTION_TABLE: StaticCell<ProjectionTable> = StaticCell::new();
+
+/// Projection available for [`Mapping::Projected`].
+static PROJECTED: Mutex<CriticalSectionRawMutex, Cell<Option<&'static ProjectionTable>>> =
+    Mutex::new(Cell::new(None));
+
+/// Projection of the configured pixel map, if any.
+pub(crate) fn projection() -> Option<&'static ProjectionTable> {
+    PROJECTED.lock(|projected| projected.get())
+}
+
+/// Project the configured pixel map onto the matrix and switch to it.
+///
+/// Must be called once at startup, does nothing without a pixel map.
+pub(crate) fn init_projection() {
+    let Some(pixel_map) = PIXEL_MAP else {
+        return;
+    };
+
+    match ProjectionTable::build(pixel_map, PROJECTION, MATRIX_COLUMNS, MATRIX_ROWS) {
+        Ok(table) => {
+            let table: &'static ProjectionTable = PROJECTION_TABLE.init(table);
+            info!("preview: 🗺️  Using pixel map: {} {}", PROJECTION, table);
+            PROJECTED.lock(|projected| projected.set(Some(table)));
+            set_mapping(Mapping::Projected(table));
+        }
+        Err(e) => warn!("preview: ⚠️  Invalid pixel map: {}", e),
+    }
+}
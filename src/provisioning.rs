@@ -0,0 +1,290 @@
+//! # Provisioning Access Point
+//!
+//! When no known network is reachable, the cube opens its own access point
+//! `Buntspiel-Setup` and serves a setup page to enter WiFi credentials,
+//! Pixelblaze endpoints and brightness (see `buntspiel_core::provisioning`
+//! for the form). Saving writes them to the persistent configuration and
+//! reboots into station mode.
+//!
+//! ## Services
+//! The cube is `192.168.42.1` on its network (not `192.168.4.1`, so the
+//! Pixelblaze access point stays reachable afterwards):
+//! - **DHCP**: Addresses for phones and laptops joining the access point.
+//! - **DNS**: Every name resolves to the cube, so phones open the setup page
+//!   on their own (captive portal).
+//! - **HTTP**: The setup page on port 80, for any path.
+//!
+//! Without a saved configuration the cube reboots after [`SETUP_TIMEOUT`] to
+//! look for the known networks again.
+
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use cortex_m::peripheral::SCB;
+use cyw43::Control;
+use defmt::{info, warn, Debug2Format, Display2Format};
+use edge_http::io::server::{Connection, Handler, Server};
+use edge_http::Method;
+use edge_nal_embassy::{Tcp, TcpBuffers, Udp, UdpBuffers};
+use edge_net::captive;
+use edge_net::dhcp::io::{self as dhcp_io, DEFAULT_SERVER_PORT};
+use edge_net::dhcp::server::{Server as DhcpServer, ServerOptions};
+use edge_net::nal::{TcpBind, UdpBind};
+use embassy_futures::select::{select, select3, Either};
+use embassy_net::{ConfigV4, Ipv4Address, Ipv4Cidr, StaticConfigV4};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer};
+use embedded_io_async::{Read, Write};
+
+use buntspiel_core::provisioning::apply_setup_form;
+
+use crate::config;
+
+// Access point configuration
+const SETUP_SSID: &str = "Buntspiel-Setup"; // Open network, nothing secret is served
+const SETUP_CHANNEL: u8 = 6;
+const SETUP_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 42, 1);
+
+/// Reboot to look for the known networks again if nothing was saved by then
+const SETUP_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Time for the confirmation page to reach the browser before rebooting
+const REBOOT_DELAY: Duration = Duration::from_secs(2);
+
+/// Maximum DHCP leases handed out at once
+const MAX_LEASES: usize = 4;
+
+/// How long DNS answers may be cached by clients
+const DNS_TTL: core::time::Duration = core::time::Duration::from_secs(60);
+
+// HTTP server resources: a single connection at a time is enough for setup
+const HTTP_HANDLERS: usize = 1;
+const HTTP_BUF_SIZE: usize = 2048;
+const MAX_HEADERS: usize = 16;
+
+/// Longest accepted form body
+const MAX_FORM_LEN: usize = 512;
+
+/// Path the setup form is posted to
+const SAVE_PATH: &str = "/save";
+
+/// Setup page, served for any path so captive portal checks land on it.
+const SETUP_PAGE: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width">
+<title>Buntspiel Setup</title>
+<style>body{font-family:sans-serif;margin:2em auto;max-width:24em}label,input{display:block;width:100%;margin:.3em 0}input{padding:.4em}</style>
+</head><body>
+<h1>🎨 Buntspiel Setup</h1>
+<form method="post" action="/save">
+<label>WiFi network <input name="ssid" required maxlength="32"></label>
+<label>Passphrase (empty for open networks) <input name="passphrase" type="password" maxlength="64"></label>
+<label>Pixelblaze address (optional) <input name="pixelblaze" placeholder="192.168.4.1:81, 10.0.0.42"></label>
+<label>Brightness 0-255 (optional) <input name="brightness" type="number" min="0" max="255"></label>
+<input type="submit" value="Save and restart">
+</form>
+</body></html>
+"#;
+
+/// Confirmation page after saving.
+const SAVED_PAGE: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>Buntspiel Setup</title></head><body>
+<h1>✅ Saved</h1><p>The cube restarts and joins the network.</p>
+</body></html>
+"#;
+
+/// Signalled once the configuration was saved.
+static SAVED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Open the provisioning access point and serve the setup page until a
+/// configuration was saved or [`SETUP_TIMEOUT`] passed, then reboot.
+pub(crate) async fn run_setup_portal(
+    control: &mut Control<'static>,
+    stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>,
+) -> ! {
+    info!("provisioning: 📶 Opening access point '{}'...", SETUP_SSID);
+    let [a, b, c, d] = SETUP_IP.octets();
+    stack.set_config_v4(ConfigV4::Static(StaticConfigV4 {
+        address: Ipv4Cidr::new(Ipv4Address::new(a, b, c, d), 24),
+        gateway: None,
+        dns_servers: heapless::Vec::new(),
+    }));
+    control.start_ap_open(SETUP_SSID, SETUP_CHANNEL).await;
+    info!(
+        "provisioning: 🌐 Setup page at http://{}/",
+        Display2Format(&SETUP_IP)
+    );
+
+    let services = select3(serve_dhcp(stack), serve_dns(stack), serve_http(stack));
+    let saved = async {
+        match select(SAVED.wait(), Timer::after(SETUP_TIMEOUT)).await {
+            Either::First(()) => Timer::after(REBOOT_DELAY).await,
+            Either::Second(()) => {
+                info!("provisioning: ⌛ Nothing saved, looking for known networks again")
+            }
+        }
+    };
+    select(services, saved).await;
+
+    info!("provisioning: 🔁 Rebooting into station mode");
+    SCB::sys_reset()
+}
+
+/// Hand out addresses to clients of the access point.
+async fn serve_dhcp(stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>) -> ! {
+    let buffers = UdpBuffers::<1, 1024, 1024, 2>::new();
+    let udp = Udp::new(stack, &buffers);
+    let mut buf = [0_u8; 1024];
+    let mut gateways = [SETUP_IP];
+    let dns = [SETUP_IP];
+
+    loop {
+        let mut options = ServerOptions::new(SETUP_IP, Some(&mut gateways));
+        options.dns = &dns;
+        let mut server = DhcpServer::<MAX_LEASES>::new(SETUP_IP);
+
+        let address = SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            DEFAULT_SERVER_PORT,
+        ));
+        let result = match udp.bind(address).await {
+            Ok(mut socket) => dhcp_io::server::run(&mut server, &options, &mut socket, &mut buf)
+                .await
+                .map_err(|e| Debug2Format(&e)),
+            Err(e) => Err(Debug2Format(&e)),
+        };
+        if let Err(e) = result {
+            warn!("provisioning: ❌ DHCP server failed: {}, restarting", e);
+        }
+        Timer::after_secs(1).await;
+    }
+}
+
+/// Answer every DNS query with the address of the cube.
+async fn serve_dns(stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>) -> ! {
+    let buffers = UdpBuffers::<1, 512, 512, 2>::new();
+    let udp = Udp::new(stack, &buffers);
+    let mut tx_buf = [0_u8; 512];
+    let mut rx_buf = [0_u8; 512];
+
+    loop {
+        let address = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 53));
+        if let Err(e) =
+            captive::io::run(&udp, address, &mut tx_buf, &mut rx_buf, SETUP_IP, DNS_TTL).await
+        {
+            warn!(
+                "provisioning: ❌ DNS server failed: {}, restarting",
+                Debug2Format(&e)
+            );
+        }
+        Timer::after_secs(1).await;
+    }
+}
+
+/// Serve the setup page and accept the posted form.
+async fn serve_http(stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>) -> ! {
+    let buffers = TcpBuffers::<HTTP_HANDLERS, 1024, 1024>::new();
+    let tcp = Tcp::new(stack, &buffers);
+    let mut server = Server::<HTTP_HANDLERS, HTTP_BUF_SIZE, MAX_HEADERS>::new();
+
+    loop {
+        let address = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 80));
+        let result = match tcp.bind(address).await {
+            Ok(acceptor) => server
+                .run(acceptor, SetupHandler, None)
+                .await
+                .map_err(|e| Debug2Format(&e)),
+            Err(e) => Err(Debug2Format(&e)),
+        };
+        if let Err(e) = result {
+            warn!("provisioning: ❌ HTTP server failed: {}, restarting", e);
+        }
+        Timer::after_secs(1).await;
+    }
+}
+
+/// Serves [`SETUP_PAGE`] and saves the posted form.
+struct SetupHandler;
+
+impl<'b, T, const N: usize> Handler<'b, T, N> for SetupHandler
+where
+    T: Read + Write,
+{
+    type Error = edge_http::io::Error<T::Error>;
+
+    async fn handle(&self, conn: &mut Connection<'b, T, N>) -> Result<(), Self::Error> {
+        let (method, save, content_len) = {
+            let headers = conn.headers()?;
+            (
+                headers.method,
+                headers.path == SAVE_PATH,
+                headers.headers.content_len(),
+            )
+        };
+
+        match (method, save) {
+            (Method::Post, true) => {
+                // Read the form, longer bodies are cut off and fail to decode
+                let mut body = [0_u8; MAX_FORM_LEN];
+                let expected = content_len.map_or(MAX_FORM_LEN, |len| {
+                    usize::try_from(len)
+                        .unwrap_or(MAX_FORM_LEN)
+                        .min(MAX_FORM_LEN)
+                });
+                let mut len = 0;
+                while len < expected {
+                    match conn.read(&mut body[len..expected]).await? {
+                        0 => break,
+                        n => len += n,
+                    }
+                }
+
+                match apply_setup_form(&body[..len], &config::config()) {
+                    Ok(new_config) => match config::save_config(new_config).await {
+                        Ok(()) => {
+                            info!("provisioning: 💾 Setup saved");
+                            respond(conn, 200, "OK", SAVED_PAGE).await?;
+                            SAVED.signal(());
+                        }
+                        Err(e) => {
+                            warn!("provisioning: ❌ Failed to save setup: {}", e);
+                            respond(conn, 500, "Internal Server Error", "Saving failed").await?;
+                        }
+                    },
+                    Err(e) => {
+                        warn!("provisioning: ⚠️ Invalid setup form: {}", e);
+                        respond(
+                            conn,
+                            400,
+                            "Bad Request",
+                            "Invalid setup, please go back and check the fields",
+                        )
+                        .await?;
+                    }
+                }
+            }
+            (Method::Get | Method::Head, _) => respond(conn, 200, "OK", SETUP_PAGE).await?,
+            _ => respond(conn, 405, "Method Not Allowed", "").await?,
+        }
+        Ok(())
+    }
+}
+
+/// Send a complete response with the given body.
+async fn respond<'b, T, const N: usize>(
+    conn: &mut Connection<'b, T, N>,
+    status: u16,
+    message: &str,
+    body: &str,
+) -> Result<(), edge_http::io::Error<T::Error>>
+where
+    T: Read + Write,
+{
+    let content_type = match body.starts_with("<!DOCTYPE") {
+        true => "text/html; charset=utf-8",
+        false => "text/plain; charset=utf-8",
+    };
+    conn.initiate_response(status, Some(message), &[("Content-Type", content_type)])
+        .await?;
+    conn.write_all(body.as_bytes()).await?;
+    Ok(())
+}
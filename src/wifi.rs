@@ -4,16 +4,20 @@
 //! including visual feedback during connection attempts.
 //!
 //! ## Strategy
-//! - **Primary**: Configured WiFi network.
-//! - **Fallback**: Pixelblaze access point (192.168.4.1).
-//! - **Recovery**: Automatic reconnection with backoff.
+//! - **Selection**: Scan, then join the known network in range with the
+//!   strongest signal, the highest priority one among equally strong ones.
+//! - **Fallback**: The remaining known networks in order, down to the
+//!   Pixelblaze access point (192.168.4.1) and networks not seen in the scan.
+//! - **Recovery**: Automatic reconnection with exponential backoff and jitter.
+//! - **Setup**: If no known network is reachable, the provisioning access
+//!   point opens to enter new credentials (see `provisioning`).
 //!
 //! ## Power Management
 //! Uses CYW43 power saving mode to extend battery life.
 
 use cyw43::Control;
 use cyw43_pio::PioSpi;
-use defmt::{info, unwrap};
+use defmt::{info, unwrap, warn};
 use embassy_executor::Spawner;
 use embassy_futures::select::select;
 use embassy_rp::{
@@ -21,18 +25,41 @@ use embassy_rp::{
     peripherals::{DMA_CH0, PIN_23, PIN_24, PIN_25, PIN_29, PIO0},
     pio::{InterruptHandler, Pio},
 };
-use embassy_time::{Duration, Timer};
+use embassy_time::{with_timeout, Duration, Timer};
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
 use static_cell::StaticCell;
 
-use crate::animate::wait_animation;
-
-// WiFi Network Configuration
-// TODO: Move to external config file or environment variables for security
-const WIFI_NETWORK: &str = "Testturm2"; // Festival network or local WiFi
-const WIFI_PASSWORD: &str = "12345678"; // TODO: Use secure credential storage
+use buntspiel_core::backoff::Backoff;
+use buntspiel_core::networks::{KnownNetworks, ScanResults};
 
-/// Maximum concurrent TCP sockets allowed (limited by Pico W RAM).
-const MAX_SOCKETS: usize = 3;
+use crate::animate::wait_animation;
+use crate::config;
+use crate::provisioning;
+
+// WiFi Network Configuration: SSID, passphrase (empty for open networks) and
+// priority (higher wins ties and among unseen networks), used until networks
+// are stored in flash
+pub(crate) const WIFI_NETWORKS: [(&str, &str, u8); 3] = [
+    ("Testturm2", "12345678", 3),           // Festival network
+    ("Pixelblaze_Leuchtturm", "", 2),       // Lighthouse Pixelblaze access point
+    ("YourHomeNetwork", "YourPassword", 1), // Local WiFi
+];
+
+/// How long to wait for a DHCP lease before trying the next network
+const DHCP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay between association attempts: 2 s doubling up to 2 min, up to a
+/// quarter shorter at random
+const RECONNECT_BACKOFF: Backoff = Backoff::new(2_000, 120_000, 25);
+
+/// Open the provisioning access point after this many failed connection
+/// attempts in a row
+const SETUP_AFTER_ATTEMPTS: u32 = 4;
+
+/// Maximum concurrent sockets allowed (limited by Pico W RAM): DHCP, the
+/// Pixelblaze WebSocket and the discovery listener, plus the DHCP, DNS and
+/// HTTP servers of the provisioning access point.
+const MAX_SOCKETS: usize = 6;
 
 /// Initialize WiFi subsystem and network stack.
 pub(crate) async fn init_wifi<'a>(
@@ -44,6 +71,9 @@ pub(crate) async fn init_wifi<'a>(
     static STATE: StaticCell<cyw43::State> = StaticCell::new();
     let state = STATE.init(cyw43::State::new());
 
+    // Randomness for TCP sequence numbers and reconnect jitter
+    let mut rng = SmallRng::seed_from_u64(random_seed);
+
     // Load WiFi firmware and regulatory data from embedded binaries
     // These files are licensed from Infineon and included in the firmware directory
     let fw = include_bytes!("../cyw43-firmware/43439A0.bin"); // Main WiFi firmware
@@ -82,43 +112,83 @@ pub(crate) async fn init_wifi<'a>(
         net_device,                                                        // CYW43 network device
         config,                                                            // DHCP or static config
         RESOURCES.init(embassy_net::StackResources::<MAX_SOCKETS>::new()), // Socket resources
-        random_seed, // For TCP sequence numbers
+        rng.next_u64(), // For TCP sequence numbers
     ));
 
     // Spawn network stack processing task (handles TCP/IP protocols)
     unwrap!(spawner.spawn(net_task(stack)));
 
     // Spawn connection management task (handles WiFi association and reconnection)
-    unwrap!(spawner.spawn(control_task(control, stack)));
+    unwrap!(spawner.spawn(control_task(control, stack, rng)));
 
     info!("wifi: ✅ Network stack initialized successfully");
     stack
 }
 
-/// Attempt to connect to the configured WiFi network.
+/// Attempt to connect to one of the known WiFi networks, in the order
+/// determined by a scan.
+///
+/// Returns whether the network is up.
 async fn connect_to_wifi<'d>(
     control: &'d mut Control<'static>,
     stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>,
-) {
-    info!("wifi: 🔍 Scanning for network '{}'...", WIFI_NETWORK);
+    networks: &KnownNetworks,
+) -> bool {
+    info!("wifi: 🔍 Scanning for known networks...");
+    let mut scan = ScanResults::default();
+    {
+        let mut scanner = control.scan(Default::default()).await;
+        while let Some(bss) = scanner.next().await {
+            let ssid_len = usize::from(bss.ssid_len).min(bss.ssid.len());
+            scan.record(networks, &bss.ssid[..ssid_len], bss.rssi);
+        }
+    }
 
-    // Attempt WPA2 connection with configured credentials
-    match control.join_wpa2(WIFI_NETWORK, WIFI_PASSWORD).await {
-        Ok(_) => {
-            info!("wifi: 🤝 Successfully associated with network");
+    for n in scan.connection_order(networks) {
+        let network = &networks[n];
+        match scan.rssi(n) {
+            Some(rssi) => info!(
+                "wifi: 📶 Joining '{}' ({} dBm)...",
+                network.ssid.as_str(),
+                rssi
+            ),
+            None => info!(
+                "wifi: 👻 Joining '{}' (not seen in scan)...",
+                network.ssid.as_str()
+            ),
         }
-        Err(err) => {
+
+        let joined = match network.passphrase() {
+            Some(passphrase) => control.join_wpa2(&network.ssid, passphrase).await,
+            None => control.join_open(&network.ssid).await,
+        };
+        if let Err(err) = joined {
             info!("wifi: ❌ Association failed with status={}", err.status);
-            return;
+            continue;
+        }
+        info!("wifi: 🤝 Successfully associated with network");
+
+        // Wait for DHCP IP address assignment (not needed for static IP)
+        info!("wifi: 📄 Waiting for DHCP configuration...");
+        let configured = with_timeout(DHCP_TIMEOUT, async {
+            while !stack.is_config_up() {
+                Timer::after_millis(500).await;
+            }
+        })
+        .await;
+        if configured.is_err() {
+            warn!(
+                "wifi: ⌛ No DHCP lease from '{}', leaving",
+                network.ssid.as_str()
+            );
+            control.leave().await;
+            continue;
         }
-    }
 
-    // Wait for DHCP IP address assignment (not needed for static IP)
-    info!("wifi: 📄 Waiting for DHCP configuration...");
-    while !stack.is_config_up() {
-        Timer::after_millis(500).await;
+        info!("wifi: ✅ Network connection established with IP configuration!");
+        return true;
     }
-    info!("wifi: ✅ Network connection established with IP configuration!");
+    false
 }
 
 /// Low-level WiFi driver task handling SPI communication with the CYW43 chip.
@@ -142,8 +212,10 @@ async fn net_task(stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>)
 async fn control_task(
     mut control: Control<'static>,
     stack: &'static embassy_net::Stack<cyw43::NetDriver<'static>>,
+    mut rng: SmallRng,
 ) -> ! {
     info!("wifi: 👁️ Starting connection management task");
+    let mut backoff = RECONNECT_BACKOFF;
 
     loop {
         // If we're already connected, just monitor the connection
@@ -153,19 +225,25 @@ async fn control_task(
         }
 
         info!("wifi: 🔄 Network disconnected, starting reconnection sequence");
+        let networks = config::config().networks;
 
         // Run connection attempts with visual feedback
         // The select! ensures animation stops immediately when connection succeeds
         select(
             // Connection attempt loop
             async {
-                while !stack.is_config_up() {
-                    connect_to_wifi(&mut control, stack).await;
-                    if !stack.is_config_up() {
-                        info!("wifi: ⏳ Connection failed, retrying in 2 seconds...");
-                        Timer::after(Duration::from_secs(2)).await;
+                while !connect_to_wifi(&mut control, stack, &networks).await {
+                    if networks.is_empty() || backoff.attempts() + 1 >= SETUP_AFTER_ATTEMPTS {
+                        info!("wifi: 🛠️ No known network reachable, opening setup access point");
+                        provisioning::run_setup_portal(&mut control, stack).await;
                     }
+
+                    // Wait longer after every failed attempt in a row
+                    let delay = backoff.next_delay_ms(rng.next_u32());
+                    info!("wifi: ⏳ Connection failed, retrying in {} ms...", delay);
+                    Timer::after_millis(delay).await;
                 }
+                backoff.reset();
                 info!("wifi: 🎉 Connection restored!");
             },
             // Visual feedback during connection attempts
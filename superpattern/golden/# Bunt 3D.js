@@ -0,0 +1,21 @@
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = time(0.5)
+  __globals__[1] = time(0.05)
+  __globals__[2] = time(0.04)// *square(1, 1)
+}
+
+export function render3D(__state__, __globals__, index,x,y) {
+  //h = t1 + index/pixelCount
+  __globals__[3] = (__globals__[0]-index/48/40)
+  __globals__[4] = 1 //(wave(t2*4)+1)*0.4
+  __globals__[5] = (wave(__globals__[1])+1)//*0.4
+  // The hue output range is -0.5 to 1.5. hsv() will wrap hues outside 0..1.
+  
+ // v = (1 + sin(x + t1) + cos(y + t1)) * 1
+  
+  
+  //v = ((1+y)*t1*5)/2
+  __globals__[5] = __globals__[5]*__globals__[5]*__globals__[5]*__globals__[5]
+  
+  hsv(__globals__[3], __globals__[4], __globals__[5],)
+}
\ No newline at end of file
@@ -0,0 +1,73 @@
+__state__[0] = 0
+__state__[1] = 0
+
+export function toggleOnOff(__state__, __globals__, isEnabled){
+  
+  __state__[1] = isEnabled
+}
+
+export function togglemode(__state__, __globals__, isEnabled){
+  
+  __state__[0] = isEnabled
+}
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = square(time(.01), 0.5)
+  __globals__[1] = wave(time(.5))
+  __globals__[2] = wave(time(.05))
+  __globals__[3] = (__globals__[3] - delta * 0.0005) % 1
+  __globals__[4] = 48
+  __globals__[5] = 5
+}
+
+export function render(__state__, __globals__, index) {
+  index = index % __globals__[4]
+  __globals__[6] = 1-(__globals__[1]* __state__[0])
+  __globals__[7] = 0 %__globals__[2] * 5 
+  __globals__[8] = square((__globals__[5]/2* (index + __globals__[7]) / __globals__[4]), .5)
+  __globals__[9] = square((__globals__[5]/2 * (index + __globals__[4]/__globals__[5] + __globals__[7]) / __globals__[4]), .5)
+  __globals__[10] = __globals__[8]*__globals__[6] + __globals__[9]
+  __globals__[11] = __globals__[8]
+  __globals__[12] = __globals__[8] + 1*__globals__[9] * !(index % 1) 
+  
+  //s = square((2 * index / N), .5) * 1 + 0
+  //v = square((2 * index / N), .5) * 1 +  0.3 * ((index+1) % 2) //+  0.5 * ((index+1*t1) % 2)
+  
+  hsv(__globals__[10], __globals__[11], __globals__[12])
+}
+
+
+
+// You can also project up a dimension. Think of this as mixing in the z value
+// to x and y in order to compose a stack of matrices.
+export function render3D(__state__, __globals__, index, x, y, z) {
+  if (__state__[1]){
+  index = index % __globals__[4]
+  if (__state__[0]){
+    __globals__[6] = __globals__[3] + index/(0.1*pixelCount) //1-(t2 * mode * floor(index/N))
+  }else{
+    __globals__[6] = 1
+  }
+  //farbe = t4 
+  __globals__[7] = 0 %__globals__[2] * 5 
+  __globals__[8] = square((__globals__[5]/2* (index + __globals__[7]) / __globals__[4]), .5)
+  __globals__[9] = square((__globals__[5]/2 * (index + __globals__[4]/__globals__[5] + __globals__[7]) / __globals__[4]), .5)
+  __globals__[10] = __globals__[8]*__globals__[6] + __globals__[9]
+  __globals__[11] = __globals__[8]
+  __globals__[12] = __globals__[8] + 1*__globals__[9] * !(index % 2) * 1
+  
+  
+  
+    
+  } else{
+    __globals__[10] = 0
+    __globals__[11] = 0
+    __globals__[12] = 0
+  }
+  
+  //s = square((2 * index / N), .5) * 1 + 0
+  //v = square((2 * index / N), .5) * 1 +  0.3 * ((index+1) % 2) //+  0.5 * ((index+1*t1) % 2)
+  
+  hsv(__globals__[10], __globals__[11], __globals__[12])
+}
+
@@ -0,0 +1,102 @@
+__state__[0] = 0
+__state__[1] = 0
+__state__[2] = 1/500
+__state__[3] = __state__[2]
+__state__[4] = 48
+
+export function sliderSpeedRange(__state__, __globals__, s){
+  __state__[3] = s*s*__state__[2]
+}
+
+export function toggleOnOff(__state__, __globals__, isEnabled){
+  
+  __state__[1] = isEnabled
+}
+
+export function togglemode(__state__, __globals__, isEnabled){
+  
+  __state__[0] = isEnabled
+}
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[1] = square(time(.01), 0.5)
+  __globals__[2] = wave(time(.5))
+  __globals__[3] = wave(time(.1))
+  __globals__[4] = (__globals__[4] + delta * __state__[3]) % 1
+  __globals__[5] = time(0.01)
+  
+  __globals__[6] = 1
+}
+
+export function render(__state__, __globals__, index) {
+  index = index % __state__[4]
+  __globals__[7] = 1-(__globals__[2]* __state__[0])
+  __globals__[8] = 0 %__globals__[3] * 5 
+  __globals__[9] = square((__globals__[6]/2* (index + __globals__[8]) / __state__[4]), .5)
+  __globals__[10] = square((__globals__[6]/2 * (index + __state__[4]/__globals__[6] + __globals__[8]) / __state__[4]), .5)
+  __globals__[11] = __globals__[9]*__globals__[7] + __globals__[10]
+  __globals__[12] = __globals__[9]
+  __globals__[13] = __globals__[9] + 1*__globals__[10] * !(index % 1) 
+  
+  //s = square((2 * index / N), .5) * 1 + 0
+  //v = square((2 * index / N), .5) * 1 +  0.3 * ((index+1) % 2) //+  0.5 * ((index+1*t1) % 2)
+  
+  hsv(__globals__[11], __globals__[12], __globals__[13])
+}
+
+__globals__[0] = array(__state__[4]*6)
+
+// You can also project up a dimension. Think of this as mixing in the z value
+// to x and y in order to compose a stack of matrices.
+export function render3D(__state__, __globals__, index, x, y, z) {
+  __globals__[14] = floor(index / (__state__[4]))
+  __globals__[15] = __globals__[14]/(pixelCount/__state__[4]) * 2 * PI
+  
+  if (__state__[1]){
+  __globals__[16] = index % __state__[4]
+  if (__state__[0]){
+    __globals__[7] = __globals__[4] + __globals__[15]/(2*PI)/6
+  }else{
+    __globals__[7] = 1
+  }
+  
+   
+  __globals__[17] = __globals__[14]/(pixelCount/__state__[4]) //zwischen 0 und 1
+  __globals__[18] = square(__globals__[5], 0.1)
+  
+  __globals__[19] = floor(__globals__[5] * 6) 
+  if ((__globals__[19] == __globals__[14])){
+    __globals__[20] = 1
+  } else{
+    __globals__[20] = 0
+
+  }
+  __globals__[21] = 1 - (abs((__globals__[19] - __globals__[14])/3) - __globals__[19]/3 ) / 2
+  __globals__[20] = 1 - abs((__globals__[19] - __globals__[14])/3)/(2*1)
+  __globals__[20] = __globals__[20] //* bling
+  
+ 
+  //if (index==180) alph = rotator//floor(index / N)
+  __globals__[0][floor(index/__state__[4])] = __globals__[21]
+  __globals__[8] = 0 %__globals__[3] * 5 
+  __globals__[9] = square((__globals__[6]/2* (__globals__[16] + __globals__[8]) / __state__[4]), .5)
+  __globals__[10] = square((__globals__[6]/2 * (__globals__[16] + __state__[4]/__globals__[6] + __globals__[8]) / __state__[4]), .5)
+  __globals__[11] = __globals__[9]*__globals__[7] + __globals__[10]
+  __globals__[12] = __globals__[9]
+  __globals__[13] = __globals__[9]*__globals__[20] + 0*__globals__[10] * !(index % 2) * 1
+  
+  
+  
+    
+  } else{
+    __globals__[11] = 0
+    __globals__[12] = 0
+    __globals__[13] = 0
+  }
+  
+  //s = square((2 * index / N), .5) * 1 + 0
+  //v = square((2 * index / N), .5) * 1 +  0.3 * ((index+1) % 2) //+  0.5 * ((index+1*t1) % 2)
+  
+  hsv(__globals__[11], __globals__[12], __globals__[13])
+}
+
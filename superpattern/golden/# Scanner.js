@@ -0,0 +1,135 @@
+/* 
+  Based on the stock KITT pattern, this pattern has two points that move back and forth
+  across each side of a strip. Use the sliders to adjust the movement speed, trail length,
+  and color shift speed, or to enable/disable rainbow coloring.
+*/
+
+// Variables //
+
+  __globals__[1] = 1
+  __globals__[2] = 1
+  
+  // Find the midpoint of the strip and subtract 1 to work with an array
+  __globals__[3] = floor((pixelCount  - 1) / 2)
+  
+  // Set start and end points for each lead pixel
+  __globals__[4] = 0
+  __globals__[5] = pixelCount - 1
+  
+  __globals__[6] = __globals__[3]
+  __globals__[7] = __globals__[3] + 1
+  
+  // Set starting point for each lead pixel
+  __globals__[8] = __globals__[4]
+  __globals__[9] = __globals__[5]
+  
+  // Set starting direction for each lead pixel
+  __globals__[10] = 1
+  __globals__[11] = -1
+  
+  // Create an array to store the calculated values of each pixel
+  __globals__[12] = array(pixelCount + 1)
+
+// UI Sliders //
+
+  // Create slider to control movement speed where full slider produces fastest movement
+  __globals__[13] = 0.095
+  export function sliderSpeed(__state__, __globals__, setSpeed) {
+      __globals__[13] = clamp(setSpeed * .2, 0.03, 0.2)
+  }
+  
+  // Create slider to control trail length where full slider produces longest trails
+  __globals__[14] = 0.0025
+  export function sliderTrails(__state__, __globals__, setTrails) {
+      __globals__[14] = clamp(0.008 - (setTrails * 0.008), 0.0005, 0.008)  // Convert slider value to 0.15..2.0 (higher values = shorter trails)
+  }
+  
+  // Create slider to control color shift speed
+  __globals__[15] = 1.0
+  export function sliderColorShift(__state__, __globals__, setColorShift) {
+      __globals__[15] = clamp(2 - (setColorShift * 2), 0.15, 2.0)  // Convert slider value to 0.15..2.0
+  }
+  
+  // Create slider to enable/disable rainbow coloring
+__globals__[0] = true
+  export function sliderRainbow(__state__, __globals__, setRainbow) {
+      __globals__[0] = setRainbow > 0    // Enable rainbow effect if slider is not set to zero
+  }
+
+
+export function beforeRender(__state__, __globals__, delta) {
+  
+  // Change color over time if rainbow is not enabled
+  if (__globals__[0] < 1.0) {
+	  __globals__[16] = wave(time(__globals__[15]))
+  }
+  
+  // Calculate movement for section A
+  __globals__[17] = floor(__globals__[8])
+  __globals__[8] += __globals__[10] * delta * __globals__[13]
+  
+    // Calculate movement for section B
+  __globals__[18] = floor(__globals__[9])
+  __globals__[9] += __globals__[11] * delta * __globals__[13]
+  
+  // Switch directions after reaching end of section A
+  if (__globals__[8] > __globals__[6]) {
+    __globals__[10] = -__globals__[10]
+    __globals__[8] = __globals__[6]
+  }
+  
+  // Switch directions after reaching end of section B
+  if (__globals__[9] < __globals__[7]) {
+    __globals__[11] = -__globals__[11]
+    __globals__[9] = __globals__[7]
+  }
+  
+  // Switch directions after reaching start of section A
+  if (__globals__[8] < __globals__[4]) {
+    __globals__[10] = -__globals__[10]
+    __globals__[8] = __globals__[4]
+  }
+  
+  // Switch directions after reaching start of section B
+  if (__globals__[9] > __globals__[5]) {
+    __globals__[11] = -__globals__[11]
+    __globals__[9] = __globals__[5]
+  }
+
+  // Fill pixels between frames for section A
+  __globals__[19] = __globals__[17] < __globals__[8] 
+  for (__globals__[20] = __globals__[17]; __globals__[20] != floor(__globals__[8]); __globals__[19] ? __globals__[20]++ : __globals__[20]-- ) __globals__[12][__globals__[20]] = 1
+  
+  // Set brightness levels for pixels in section A
+  for (__globals__[20] = __globals__[4]; __globals__[20] <= __globals__[6]; __globals__[20]++) {
+    __globals__[12][__globals__[20]] -= delta * __globals__[14]
+    __globals__[12][__globals__[20]] = max(0, __globals__[12][__globals__[20]])
+  }
+
+  // Fill pixels between frames for section B
+  __globals__[19] = __globals__[18] < __globals__[9] 
+  for (__globals__[20] = __globals__[18]; __globals__[20] != floor(__globals__[9]); __globals__[19] ? __globals__[20]++ : __globals__[20]-- ) __globals__[12][__globals__[20]] = 1
+  
+  // Set brightness levels for pixels in section B
+  for (__globals__[20] = __globals__[7]; __globals__[20] <= __globals__[5]; __globals__[20]++) {
+    __globals__[12][__globals__[20]] -= delta * __globals__[14]
+    __globals__[12][__globals__[20]] = max(0, __globals__[12][__globals__[20]])
+  }
+  
+}
+
+export function render(__state__, __globals__, index) {
+  
+  // Set brightness for the current pixel from calculated values stored in the array
+  __globals__[21] = __globals__[12][index]
+  
+  // Apply gamma correction to fade trail brightness more rapidly
+  __globals__[21] = __globals__[21] * __globals__[21] * __globals__[21]
+  
+  // Apply rainbow coloring if enabled
+  if (__globals__[0] > 0){
+    __globals__[16] = 2 * index / pixelCount
+  }
+  
+  hsv(__globals__[16], __globals__[1], __globals__[21])
+}
\ No newline at end of file
@@ -0,0 +1,53 @@
+__state__[0] = 0.015
+
+__state__[1] = 1
+__state__[2] = 1
+export function hsvPickerPrimaryColor (__state__, __globals__, h, s, v) {
+  __state__[1] = h
+  __state__[2] = s
+  // we ignore value, as we calculate below.
+}
+
+__globals__[0] = __state__[0]
+export function sliderColorInterval (__state__, __globals__, v) {
+  __globals__[0] = (v * 10 * __state__[0]);
+}
+
+__state__[3] = __state__[0]
+export function sliderTimeInterval (__state__, __globals__, v) {
+  __state__[3] = 0.005 + (v * 5 * __state__[0]);
+}
+
+__globals__[1] = 0
+export function sliderpauseAnzahl (__state__, __globals__, v) {
+  __globals__[1] = floor(v * 10);
+}
+
+
+__globals__[2] = 1
+export function sliderblinkAnzahl (__state__, __globals__, v) {
+  __globals__[2] = 1 + floor(v * 9);
+}
+
+__globals__[3] = 0
+__globals__[4] = 0
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[3] = wave(time(__state__[3]) - PI2)
+  
+  var n = __globals__[1] + __globals__[2]
+  __globals__[4] = square(time(__state__[3] * n), __globals__[2] / n)
+  
+  // for color
+  __globals__[6] = wave(time(__globals__[0] * n))
+}
+
+__globals__[5] = 0
+export function render(__state__, __globals__, index) {
+  __globals__[5] = __globals__[3] * __globals__[4]
+  if (__globals__[0]) {
+    hsv(__globals__[6], __state__[2], __globals__[5])
+  } else {
+    hsv(__state__[1], __state__[2], __globals__[5])
+  }
+}
+ 
@@ -0,0 +1,278 @@
+/*
+  Sound - blink fade
+
+  This pattern is designed to use the sensor expansion board.
+
+  First please check out the "blink fade" pattern. With that as background, the
+  goal now is to make it sound reactive.
+
+  We're going to use something called a PI controller to perform an important
+  function common to many sound-reactive patterns: Adjusting the sensitivity
+  (the gain).
+
+  Imagine a person who observes the pixels reacting to sound and is continuously
+  tuning the overall brightness knob to keep things looking good. They would
+  turn the brightness up when the sound is faint and everything's too dark, and
+  turn it down if the sound is loud and the LEDs are pegged too bright. The PI
+  controller is code to perform this job. This form of Automatic Gain Control
+  allows the pattern to adapt over time so it can be in a visual Goldielocks
+  zone, whether the environment's sound is soft, loud, or changing.
+
+  The wikipedia article is more approachable than some:
+
+    https://en.wikipedia.org/wiki/PID_controller#PI_controller
+*/
+
+/*
+  By exporting these special reserved variable names, they will be set to
+  contain data from the sensor board at about 40 Hz. 
+
+  By initializing energyAverage to a value that's not possible when the sensor
+  board is connected, we can choose when to simulate sound instead.
+*/
+__globals__[0] = -1 // Overall loudness across all frequencies
+__globals__[1] = 0       // Loudest detected tone with about 39 Hz accuracy
+
+__globals__[2] = array(pixelCount)
+__globals__[3] = array(pixelCount)
+
+// The PI controller will work to tune the gain (the sensitivity) to achieve 
+// 20% average pixel brightness
+__globals__[4] = 0.2
+
+/*
+  We'll add up all the pixels' brightnesses values in each frame and store it
+  in brightnessFeedback. The difference between this (per pixel) and targetFill
+  will be the error that the PI controller is attempting to eliminate.
+*/
+__globals__[5] = 0   
+
+/*
+  The output of a PI controller is the movement variable, which in our case is
+  the `sensitivity`. Sensitivity can be thought of as the gain applied to the
+  current sound loudness. It's a coefficient found to best chase our targetFill.
+  You can add "export var" in front of this to observe it react in the Vars 
+  Watch. When the sound gets quieter, you can watch sensitivity rise. If it's
+  always at its maximum value of 150 (ki * max), try increasing the accumulated
+  error's starting value and max in makePIController().
+*/
+__globals__[6] = 0
+
+/*
+  With these coefficients, it can take up to 20 seconds to fully adjust to a
+  sudden change, for example, from a long period of very loud music to silence.
+  Export this to watch pic[2], the accumulated error.
+*/
+__globals__[7] = makePIController(__state__, __globals__, .05, .15, 300, 0, 1000)
+
+// Makes a new PI Controller "object", which is 4 parameters and a state var for
+// the accumulated error
+function makePIController(__state__, __globals__, kp, ki, start, min, max) {
+  var pic = array(5)
+  
+  // kp is the proportional gain coefficient - the weight placed on the current 
+  // difference between where we are and where we want to be (targetFill)
+  pic[0] = kp
+
+  /*
+    ki is the integral gain - the weight placed on correcting a situation where
+    the proportional corrective pressure isn't enough, so we want to use the
+    fact that time has passed without us approaching our target to step up
+    the corrective pressure.
+  */
+  pic[1] = ki
+
+  /*
+     pic[2] stores the error accumulator (a sum of the historical differences 
+     between where we want to be and where we were then). This is an integral,
+     the area under a curve. While you could certainly store historical samples
+     and evict the oldest, it's simpler to just have a min and max for what the
+     area under this curve could be.
+
+     We initialize it to a starting value of 300, and keep it within 0..1000.
+  */
+  pic[2] = start
+  pic[3] = min
+  pic[4] = max
+  return pic
+}
+
+/*
+  Calculate a new output (the manipulated variable `sensitivity`), given
+  feedback about the current error. The error is the difference between the
+  current average brightness and `targetFill`, our desired setpoint.
+
+  Notice that the error can be negative when the LEDs are fuller than desired.
+  This happens when the sensitivity was in a steady state and the sound is now
+  much louder.
+*/
+function calcPIController(__state__, __globals__, pic, err) {
+  // Accumulate the error, subject to a min and max
+  pic[2] = clamp(pic[2] + err, pic[3], pic[4])
+
+  // The output of our controller is the new sensitivity. 
+  //   sensitivity = Kp * err + Ki * ∫err 
+  // Notice that with Ki = 0.15 and a max of 1000, the output range is 0..150.
+  return max(pic[0] * err + pic[1] * pic[2], .3)
+}
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[6] = calcPIController(__state__, __globals__, __globals__[7],
+                  __globals__[4] - __globals__[5] / pixelCount)
+
+  // Reset the brightnessFeedback between each frame
+  __globals__[5] = 0
+  
+  if (__globals__[0] == -1) { // No sensor board is connected
+    simulateSound(__state__, __globals__)
+  } else {                   // Load the live data from the sensor board
+    __state__[2] = __globals__[0]
+    __state__[3] = __globals__[1]
+  }
+
+  for (__globals__[8] = 0; __globals__[8] < pixelCount; __globals__[8]++) {
+    // Decay the brightness of each pixel proportional to how much time has
+    // passed as well as how loud it is right now
+    __globals__[2][__globals__[8]] -= .0005 * delta + abs(__state__[2] * __globals__[6] / 5000)
+
+    // If a pixel has faded out, reset it with a random brightness value that is
+    // scaled by the detected loudness and the computed sensitivity
+    if (__globals__[2][__globals__[8]] <= 0) {
+      __globals__[2][__globals__[8]] = random(1) * __state__[2] * __globals__[6] 
+
+      /*
+        The reinitialized pixel's color will be selected from a rotating 
+        pallette. The base hue cycles through the hue wheel every 4.6 seconds.
+        Then, up to 20% hue variation is added based on the loudest frequency
+        present. More varied sound produces more varied colors.
+      */
+      __globals__[3][__globals__[8]] = time(.07) + .2 * triangle(__state__[3] / 1000)
+    }
+  }
+}
+
+export function render(__state__, __globals__, index) {
+  __globals__[9] = __globals__[2][index]
+  __globals__[9] = __globals__[9] * __globals__[9]  // This could also go below the feedback calculation
+  
+  /*
+    Accumulate the brightness value from this pixel into an overall sum that 
+    will be averaged across all pixels. This average will be fed back into the
+    PI controller so it can adjust the sensitivity continuously, trying to make
+    the average v equal the targetFill of 0.2.
+  */
+  __globals__[5] += clamp(__globals__[9], 0, 1)
+
+  hsv(__globals__[3][index], 1, __globals__[9])
+}
+
+
+
+/*
+  These functions are used if the sensor board is not detected to simulate the
+  sound variables used in this pattern. This allows the pattern still be lively 
+  in the sequencer when there's no sensor board connected.
+  
+  16th notes (sequencerSlot)       S                              S
+  SB samples @40Hz (timeSlots)     T      T      T      T      T
+  beforeRender() e.g.~150FPS       B B B B B B B B B B B B B B B B B
+  
+  We cache the semi-random simulated data at the timeSlot level.
+*/
+
+__globals__[10] = 130  // Tempo in Beats Per Minute
+__state__[0] = 4 * 60 / __globals__[10]  // Seconds per 4 beat measure
+__state__[1] = ceil(__state__[0] * 40)  // SB updates at 40Hz
+
+// These globals store the simulated versions of the sensor data
+__state__[2] = 0;
+__state__[3] = 0;
+__state__[4] = 0
+__state__[5] = array(32)
+
+// Lookup table of frequencyData center frequencies
+__state__[6] = array(32)
+__state__[6][0] = 37.5; __state__[6][1] = 50; __state__[6][2] = 75; __state__[6][3] = 100; __state__[6][4] = 125; __state__[6][5] = 163; __state__[6][6] = 195; __state__[6][7] = 234; __state__[6][8] = 312; __state__[6][9] = 391; __state__[6][10] = 469; __state__[6][11] = 586; __state__[6][12] = 703; __state__[6][13] = 859; __state__[6][14] = 976; __state__[6][15] = 1170; __state__[6][16] = 1370; __state__[6][17] = 1560; __state__[6][18] = 1800; __state__[6][19] = 2070; __state__[6][20] = 2380; __state__[6][21] = 2730; __state__[6][22] = 3120; __state__[6][23] = 3590; __state__[6][24] = 4100; __state__[6][25] = 4650; __state__[6][26] = 5310; __state__[6][27] = 6020; __state__[6][28] = 6840; __state__[6][29] = 7770; __state__[6][30] = 8790; __state__[6][31] = 9960
+
+__state__[7] = 0;
+__state__[8] = 0;
+__state__[9] = 0;
+__state__[10] = 0;
+__state__[11] = 0
+function calcSequencerTime(__state__, __globals__) {
+  __globals__[11] = time(__state__[0] / 65.536)   // 0-1 every measure
+  __state__[7] = floor(__globals__[11] * 4)                // 0, 1, 2, 3
+  __state__[8] = __globals__[11] * 4 % 1                // 0-1.0 continuous for every beat
+  // There are samplesPerMeasure 40Hz timeSlots in each measure
+  __state__[9] = floor(__globals__[11] * __state__[1])
+  __state__[10] = 16 * __globals__[11]              // can be fractional 0..15.999
+  __state__[11] = floor(__state__[10]) // 0-15 every measure
+}
+
+// Calculate and memoize all simulated sensor board sound data
+__state__[12] = -1
+function simulateSound(__state__, __globals__) {
+  calcSequencerTime(__state__, __globals__) 
+  if (__state__[9] == __state__[12]) return // 140FPS -> 208FPS
+  
+  var energyTotal = 0, maxBin = 0, maxBinEnergy = 0
+  for (__globals__[12] = 0; __globals__[12] < 32; __globals__[12]++) {
+    __globals__[13] = simulateFrequencyData(__state__, __globals__, __globals__[12])
+    energyTotal += __globals__[13]
+    if (__globals__[13] > maxBinEnergy) {
+      maxBin = __globals__[12]; maxBinEnergy = __globals__[13]
+    }
+  }
+  __state__[2] = energyTotal / 32
+  __state__[3] = binomSample(__state__, __globals__, __state__[6][maxBin], 8)
+  __state__[4] = binomSample(__state__, __globals__, maxBinEnergy, 8)
+  __state__[12] = __state__[9]
+}
+
+
+__state__[13] = 4
+__state__[14] = array(__state__[13])
+__state__[14][0] = makeInstrument(__state__, __globals__, 0b1000100010001000, 1, 3, 0.01)    // kick drum
+__state__[14][1] = makeInstrument(__state__, __globals__, 0b1001001010001000, 5, 2, 0.01)    // bass
+__state__[14][2] = makeInstrument(__state__, __globals__, 0b1011101000011100, 10, 6, 0.01)   // lead synth
+__state__[14][3] = makeInstrument(__state__, __globals__, 0b0010001100100011, 20, 13, 0.05)  // high hat
+
+function makeInstrument(__state__, __globals__, sequence, centerBin, bandwidth, magnitude) {
+  __globals__[14] = array(4)
+  __globals__[14][0] = sequence   // 16 slot binary vector sequencer
+  __globals__[14][1] = centerBin  // center freq (bin index)
+  __globals__[14][2] = bandwidth  // half-bandwidth, in bins. Further bins are attenuated.
+  // Max instrument magnitude, if right on the sequencer attack and centerBin
+  __globals__[14][3] = magnitude    
+  return __globals__[14]
+}
+
+// For the current 40Hz timeSlot, simulate a _frequencyData[fBin]
+function simulateFrequencyData(__state__, __globals__, fBin) {
+  __globals__[15] = 1 - __state__[10] % 1 // Directly on-beat = 1. The D in ADSR.
+  var fDataBinSum = 0, binProximity = 0
+  for (__globals__[14] = 0; __globals__[14] < __state__[13]; __globals__[14]++) {
+    // If this instrument is "on" in this sequencer slot
+    if ((__state__[14][__globals__[14]][0] >> (15 - __state__[11])) & 1) {
+      // How close we are to the center frequency bin of the instrument
+      binProximity = max(0, 1 - abs(fBin - __state__[14][__globals__[14]][1]) / __state__[14][__globals__[14]][2])
+      // Add some energy to this bin that's statistically close to the
+      // instrument's nominal energy magnitude, decayed and splayed across bins
+      fDataBinSum += binomSample(__state__, __globals__, __state__[14][__globals__[14]][3], 3) * __globals__[15] * binProximity
+    }
+  }
+  return __state__[5][fBin] = fDataBinSum
+}
+
+/*
+  Returns a sample from a binomial distribution centered around a mean. Binomial
+  approaches a discrete normal distribution (a bell curve). Takes a whole number
+  `concentration` that determines how close the result usually is to the mean. A
+  high concentration implies low variance around the mean. concentration == 1
+  means it was selected from a flat distribution within +/- 50% of the mean.
+*/
+function binomSample(__state__, __globals__, mean, concentration) {
+  __globals__[16] = 0
+  for (__globals__[8] = 0; __globals__[8] < concentration; __globals__[8]++) __globals__[16] += random(1)
+  return mean * (0.5 + __globals__[16] / concentration)
+}
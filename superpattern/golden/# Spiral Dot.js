@@ -0,0 +1,29 @@
+__state__[0] = 0.5
+__globals__[0] = 99
+export function sliderMySetting(__state__, __globals__, v) {
+    __state__[0] = v
+}
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[1] = time(0.1*__state__[0])
+}
+
+export function render3D(__state__, __globals__, index, x,y,z) {
+  __globals__[2] = 0
+  __globals__[1] * 48
+  __globals__[3] = abs(__globals__[1]-z) < 1/48
+  __globals__[4] = atan(x/y)
+  __globals__[5] = (__globals__[1] * pixelCount % 6) * 2*PI
+  __globals__[6] = abs(__globals__[5]-__globals__[4]) < (2*PI/6)
+  if (x > 1 && y == 0) {
+    __globals__[0] = __globals__[4]
+  }
+  if (__globals__[3] && __globals__[6]) {
+    __globals__[2] = 1
+  }
+  __globals__[7] = __globals__[1] + index/pixelCount
+  __globals__[7] = 1
+  __globals__[8] = 1
+  __globals__[9] = __globals__[2]
+  hsv(__globals__[7], __globals__[8], __globals__[9])
+}
\ No newline at end of file
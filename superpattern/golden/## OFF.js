@@ -0,0 +1,6 @@
+export function render(__state__, __globals__, index) {
+  __globals__[0] = 0
+  __globals__[1] = 0
+  __globals__[2] = 0
+  hsv(__globals__[0], __globals__[1], __globals__[2])
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+__globals__[0] = .6 // length of the tail in 0..1
+__globals__[1] = .05
+__globals__[2] = 0
+__globals__[3] = 0
+
+__globals__[4] = 0
+__globals__[5] = 0
+__globals__[6] = 0
+__globals__[7] = 0
+
+export function sliderTail (__state__, __globals__, v) {
+  __globals__[0] = .6 + v/3;
+}
+
+export function sliderSpeed (__state__, __globals__, v) {
+  __globals__[1] = v/10;
+}
+
+export function sliderColor (__state__, __globals__, v) {
+  __globals__[5] = v
+}
+
+export function sliderFade (__state__, __globals__, v) {
+  __globals__[6] = floor(v)
+}
+
+export function sliderFadespeed (__state__, __globals__, v) {
+  __globals__[7] = 0.1 + v/3
+}
+
+export function render3D(__state__, __globals__, index,x,y,z) {
+  __globals__[2] =  z/2 - time(__globals__[1])
+  __globals__[3] = -z/2 - time(__globals__[1])
+  __globals__[8] = max (0,( __globals__[0] - 1 + triangle(__globals__[2]) * square(__globals__[2],.5) ) / __globals__[0] )
+    + max (0,( __globals__[0] - 1 + triangle(__globals__[3]) * square(__globals__[3],.5) ) / __globals__[0] )
+  __globals__[4] = __globals__[5] + __globals__[6]*wave(time(__globals__[7]))
+  hsv(__globals__[4], 1, __globals__[8] * __globals__[8] * __globals__[8])
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+/*
+  In this example you'll see:
+  - Colors!!!
+  - Using an array to switch between modes
+  - Lambda style function expressions
+  - More colors!
+  - Accumulating delta to make a mode switch timer
+  - Viewing which mode is playing in the variable watcher
+*/
+ 
+// First, keep track of how many modes there will be
+__globals__[1] = 8
+__globals__[2] = array(__globals__[1]) // Make an array to store the modes
+
+// Make a bunch of lambda style mode functions and put them in the modes array
+// f is expected to be in 0..4
+//modes[0]  = (f) => f     // As values progress, a rainbow is drawn
+//modes[1]  = (f) => 0     // A hue of 0.0 or 1.0 is red
+//modes[2]  = (f) => 1 / 3 // A hue around 1/3 is green
+//modes[3]  = (f) => 2 / 3 // 2/3 hue is blue
+//modes[4]  = (f) => 1     // This wraps back around to red
+__globals__[2][0]  = (__state__, __globals__, f) => f * .2 % .2 // Using modulus will wrap early and with a sharp edge
+__globals__[2][1]  = (__state__, __globals__, f) => triangle(f) * .2 // Using triangle will keep the transitions smooth
+__globals__[2][2]  = (__state__, __globals__, f) => wave(f) * .2 // Wave also works, but is non-linear
+__globals__[2][3]  = (__state__, __globals__, f) => square(f, wave(time(0.02))*.5) * .12 + 0.02 // Square can make stripes
+__globals__[2][4]  = (__state__, __globals__, f) => wave(f) * triangle(f*4) * .2 *wave(time(0.02))  // Color textures by combining waveforms
+__globals__[2][5] = (__state__, __globals__, f) => wave(f*time(0.3))*.5 % .2 - triangle(f*wave(time(0.4))) * .2 + .1 // More textures
+__globals__[2][6] = (__state__, __globals__, f) => (f + f % .2) * .5 // Mod error overlay
+__globals__[2][7] = (__state__, __globals__, f) => abs(f* .25 - .5) * 2 // Centered  
+
+__globals__[3] = 0 // Accumulate all the deltas each animation frame
+
+/*
+  Adding `export` when declaring a variable will send it back to any connected 
+  web browser via websockets. To see the current value of `mode`, click "Enable"
+  next to "Vars Watch" in the web editor.
+*/
+__globals__[0] = 0 // Start with mode 0
+
+/*
+  The beforeRender function is called once before each animation frame
+  and is passed a delta in fractional milliseconds since the last frame.
+  This has very high resolution, down to 6.25 nanoseconds!
+*/
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[3] += delta // Accumulate all the deltas into a timer
+  if (__globals__[3] > 20000) { // After 600ms, rewind the timer and switch modes
+    __globals__[3] -= 20000
+    __globals__[0] = (__globals__[0] + 1) % __globals__[1] // Go to the next mode, and keep between 0 and numModes
+  }
+  // Uncomment this line to check out a specific mode
+  //mode = 5
+}
+
+/*
+  The render function is called for every pixel. Here we're going to use 
+  the pixel's index to make a number between 0.0 and 4.0. This acts as a 4X 
+  frequency modifier, repeating the pattern 4 times across the strip length.
+  That 0-4 value is passed in to the current mode function and its output is 
+  used to set the pixel's hue. hsv() "wraps" hue between 0.0 and 1.0.
+*/
+export function render(__state__, __globals__, index) {
+  // Look up the current mode function and call it
+  __globals__[4] = __globals__[2][__globals__[0]](__state__, __globals__, 4 * index / pixelCount)
+  __globals__[5] = 0.6+wave(time(0.03))
+  hsv(__globals__[4], 1, __globals__[5])
+}
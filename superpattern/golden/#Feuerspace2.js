@@ -0,0 +1,140 @@
+// Newfire - Doomfire in 1D! 
+// Controllable, multicolored, realistic fire for
+// strips, strands and strings.  
+//
+// (Note that the saturation you choose with the color picker
+// controls the amount of "white heat" at the base of the 
+// fire.)
+//
+// MIT License
+// Take this code and make cool things
+// 12/19/23 ZRanger1
+  
+__globals__[0] = 0.065
+__globals__[1] = 0.1
+__globals__[2] = 0;
+__state__[0] = floor(pixelCount / 2)
+__globals__[6] = 40
+__globals__[7] = 0 
+__globals__[8] = 0;
+__globals__[9] = 1.75;
+__globals__[10] = 1;
+__globals__[11] = 0.03; //for movement in xy
+__globals__[3] = 0.5;
+__globals__[4] = 0.5;
+__globals__[5] = 0;
+__globals__[12] = 0.3; //from center, square
+
+// the heat array stores the current "temperature" of each pixel
+__state__[1] = array(pixelCount+1) 
+
+// heat[0] is our heat source. It drives the whole
+// simulation.We set it to 1, which is the hottest
+// possible value. 
+__state__[1][0] = 1;
+
+// short functions to map from heat array to actual pixels
+// this lets us display the flame in several different ways,
+// borrowed from the library "Sparkfire" pattern
+// 0 - single flame from bottom to top (normal)
+// 1 - single flame from top to bottom
+// 2 - two flames radiating out from center
+// 3 - two flames radiating in from ends
+__state__[2] = [
+  (__state__, __globals__, f) => f,  
+  (__state__, __globals__, f) => (pixelCount - f + 1),  
+  (__state__, __globals__, f) => 2 * (f < __state__[0]) ? abs(__state__[0] - f)  : f - __state__[0],
+  (__state__, __globals__, f) => 2 * (f < __state__[0]) ? f : pixelCount - f + 1
+]
+
+// UI
+
+// color - Hue and Brightness work as expected,
+// saturation controls the amount of "white heat"
+// allowed.
+export function hsvPickerColor(__state__, __globals__, h,s,v) {
+  __globals__[8] = h;
+  __globals__[9] = 1.9 * s;
+  __globals__[10] = v;
+}
+
+export function sliderFlameHeight(__state__, __globals__, v) {
+  __globals__[0] = mix(0.025,0.45,1 - v)
+}
+
+export function sliderHeat(__state__, __globals__, v) {
+  __state__[1][0] = mix(0.4,1,v);
+}
+
+export function sliderSparks(__state__, __globals__, v) {
+  __globals__[1] = v / 2;
+}
+
+export function sliderMode(__state__, __globals__, v) {
+  __globals__[2] = floor(0.5 + 3 * v)
+}
+ 
+export function beforeRender(__state__, __globals__, delta) {
+  // control the simulation rate so the fire moves at
+  // a more-or-less realistic speed. (This is independent
+  // of the actual LED frame rate, which will likely be
+  // much higher.)
+   __globals__[7] += delta
+  if ( __globals__[7] < __globals__[6]) return;
+   __globals__[7] = 0;
+
+  // move heat up the flame column. Instead of 2D DoomFire's
+  // regular convolution kernel + wind, we sample hotter pixels
+  // below us at slightly randomized distances.  This
+  // gives us a less predictable fire than simply looking
+  // at the pixel below the current one.
+  for (__globals__[13] = pixelCount; __globals__[13] >= 1; __globals__[13]--) {
+     __globals__[14] = random(__globals__[0]);
+     __globals__[15] = max(0,__globals__[13] - (1 + random(1)))
+     __state__[1][__globals__[13]] = max(0,__state__[1][__globals__[15]] - __globals__[14])
+  }
+
+  // borrowed and extended the spark concept from the
+  // Sparkfire library pattern
+  // This one can make dark spots as well as sparks, for
+  // a more interesting look.  Turn up sparks
+  // and you get a sputtering burner effect.
+  if (random(1) <= __globals__[1]) {
+    __globals__[13] = ceil(random(pixelCount / 8))
+    __state__[1][__globals__[13]] = min(__state__[1][__globals__[13]] + (random(2)-0.5),max(0.575,__state__[1][0])) 
+  }
+  
+  // movement in xy
+  __globals__[5] = __globals__[5] + random(PI) - PI/2
+  if (__globals__[5] > 2*PI){__globals__[5] = __globals__[5] - 2*PI}
+  if (__globals__[5] < 0){__globals__[5] = __globals__[5] + 2*PI}
+  
+  __globals__[3] = __globals__[3] + cos(__globals__[5]) * __globals__[11]
+  __globals__[4] = __globals__[4] + sin(__globals__[5]) * __globals__[11]
+  __globals__[16] = 0.5 - __globals__[12]
+  __globals__[17] = 0.5 + __globals__[12]
+  if (__globals__[3] > __globals__[17]){__globals__[3] = __globals__[17]}
+  if (__globals__[4] > __globals__[17]){__globals__[4] = __globals__[17]}
+  if (__globals__[3] < __globals__[16]){__globals__[3] = __globals__[16]}
+  if (__globals__[4] < __globals__[16]){__globals__[4] = __globals__[16]}
+}  
+
+export function render(__state__, __globals__, index) {
+  // map temperature to display pixel, gamma correct and display  
+  __globals__[15] = __state__[1][__state__[2][__globals__[2]](__state__, __globals__, index + 1)]
+  __globals__[15] = __globals__[15] * __globals__[15] * __globals__[15];
+  hsv(__globals__[8] + (0.1 * __globals__[15]),__globals__[9]-__globals__[15],__globals__[10] * __globals__[15])
+} 
+
+
+export function render3D(__state__, __globals__, index, x, y, z) {
+  __globals__[18] = sqrt((x-__globals__[3])*(x-__globals__[3]) + (y-__globals__[4])*(y-__globals__[4]))
+  if (__globals__[18] > 1){__globals__[18] = 1}
+  __globals__[19] = 1-__globals__[18]
+  
+  // map temperature to display pixel, gamma correct and display  
+  __globals__[15] = __state__[1][__state__[2][__globals__[2]](__state__, __globals__, index%48 + 1)] * __globals__[19]
+  __globals__[15] = __globals__[15] * __globals__[15] * __globals__[15];
+  hsv(__globals__[8] + (0.1 * __globals__[15]),__globals__[9]-__globals__[15],__globals__[10] * __globals__[15])
+} 
+
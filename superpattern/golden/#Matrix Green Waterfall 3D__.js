@@ -0,0 +1,38 @@
+/*
+  Basic green "The Matrix"-style waterfall display adapted for 
+  2D LED displays. 
+  
+  Not a perfect reproduction, but captures the feel pretty well,
+  and the code is small and fast enough to be usable as a background
+  or a texture in another pattern.
+   
+   7/15/21 JEM(zranger1)
+*/
+
+__state__[0] = 20;
+__state__[1] = 0.3333;
+
+// You may want to adjust wavebase for seriously non-rectangular
+// displays.  Half the number of x axis pixels is a good place
+// to start.
+__state__[2] = (sqrt(pixelCount) / 48);
+
+// uncomment mod() function for pixelblaze 2
+//function mod(dividend, divisor) {
+//  var r = dividend % divisor;
+//  return (r > 0) != (divisor > 0) ? r + divisor : r;
+//}
+
+export function sliderSpeed(__state__, __globals__, v) {
+  __state__[0] = floor(50 * v);
+}
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = __state__[0]*(time(.25)+0.02);
+  __globals__[1] = __state__[2] + (0.5*time(.3))
+}
+
+export function render3D(__state__, __globals__, index,x,y,z) {
+  var v = mod(y-__globals__[0],wave(x*__globals__[1])); v = v * v;
+  hsv(__state__[1], 1-((v > 0.8)/12), v);
+}
\ No newline at end of file
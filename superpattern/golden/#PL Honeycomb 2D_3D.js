@@ -0,0 +1,90 @@
+/*
+  Honeycomb 2D
+
+  This pattern is meant to be displayed on an LED matrix or other 2D surface
+  defined in the Mapper tab, but also has 1D and 3D renderers defined.
+  
+  Output demo: https://youtu.be/u9z8_XGe684
+  
+  The mapper allows us to share patterns that work in 2D or 3D space without the
+  pattern code being dependent on how the LEDs were wired or placed in space.
+  That means these three installations could all render the same pattern after
+  defining their specific LED placements in the mapper:
+    
+    1. A 8x8 matrix in a perfect grid, wired the common zigzag way
+    2. Individual pixels on a strand mounted in a triangle hexagon grid
+    3. Equal length strips wired as vertical columns on separate channels
+         of the output expander board
+  
+  To get started quickly with matrices, there's an inexpensive 8x8 on the 
+  Pixelblaze store. Load the default Matrix example in the mapper and you're
+  ready to go. 
+
+  This pattern builds on the example "pulse 2D". To best understand this one,
+  start there.
+*/
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = 5 // Overall animation duration constant. A smaller duration runs faster.
+  
+  __globals__[1]  = wave(time(__globals__[0] * 6.6 / 65.536)) * 5 + 2 // 2 to 7; Frequency (cell density)
+  __globals__[2] = wave(time(__globals__[0] * 9.8 / 65.536)) * PI2  // 0 to 2*PI; Oscillates x shift
+  __globals__[3] = wave(time(__globals__[0] * 12.5 / 65.536)) * PI2 // 0 to 2*PI; Oscillates y shift
+  __globals__[4] = wave(time(__globals__[0] * 9.8 / 65.536)) // Shift h: wavelength of tf * 9.8 s
+  __globals__[5] = time(__globals__[0] * 0.66 / 65.536) // Shift v: 0 to 1 every 0.66 sec
+}
+
+export function render2D(__state__, __globals__, index, x, y) {
+  __globals__[6] = (1 + sin(x * __globals__[1] + __globals__[2]) + cos(y * __globals__[1] + __globals__[3])) * .5 
+
+  /*
+    As explained in "Matrix 2D Pulse", z is now an egg-carton shaped surface
+    in x and y. The number of hills/valles visible (the frequency) is
+    proportional to f; f oscillates. The position of the centers in x and y 
+    oscillate with t1 and t2. z's value ranges from -0.5 to 1.5.
+    
+    First, we'll derive the brightness (v) from this field.
+    
+    t4 is a 0 to 1 sawtooth, so (z + t4) now is between -0.5 and 2.5 wave(z +
+    t4) therefore cycles 0 to 1 three times, ever shifting (by t4) with respect
+    to the original egg carton.
+  */
+  __globals__[7] = wave(__globals__[6] + __globals__[5])
+  
+  // Typical concave-upward brightness scaling for perceptual aesthetics.
+  // v enters and exits as 0-1. 0 -> 0, 1 -> 1, but 0.5 -> 0.125 
+  __globals__[7] = __globals__[7] * __globals__[7] * __globals__[7]
+  
+  /*
+    Triangle will essentially double the frequency; t3 will add an 
+    oscillating offset. With h in 0-1.5, hsv() "wraps" h, and since all
+    these functions are continuous, it's just spending extra time on the
+    hue wheel in the 0-0.5 range. Tweak this until you like how the final 
+    colors progress over time, but anything based on z will make colors
+    related to the circles seen from above in the egg carton pattern.
+  */
+  __globals__[8] = triangle(__globals__[6]) / 2 + __globals__[4]
+  
+  hsv(__globals__[8], 1, __globals__[7])
+}
+
+/*
+  When there's no map defined, Pixelblaze will call render() instead of 
+  render2D() or render3D(), so it's nice to define a graceful degradation for 1D
+  strips. For many geometric patterns, you'll want to define a projection down a
+  dimension. 
+*/
+export function render(__state__, __globals__, index) {
+  __globals__[9] = index / pixelCount  // Transform index..pixelCount to 0..1
+  // render2D(index, pct, pct)  // Render the diagonal of a matrix
+  // render2D(index, pct, 0)    // Render the top row of a matrix
+  render2D(__state__, __globals__, index, 3 * __globals__[9], 0)   // Render 3 top rows worth to make it denser
+}
+
+// You can also project up a dimension. Think of this as mixing in the z value
+// to x and y in order to compose a stack of matrices.
+export function render3D(__state__, __globals__, index, x, y, z) {
+  __globals__[10] = (x - cos(z / 4 * PI2)) / 2
+  __globals__[11] = (y - sin(z / 4 * PI2)) / 2
+  render2D(__state__, __globals__, index, __globals__[10], __globals__[11])
+}
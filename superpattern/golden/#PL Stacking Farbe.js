@@ -0,0 +1,161 @@
+/*
+It's... 1D Tetris! Sort of.  
+
+Subdivide the strip into 1 to 6 segments of equal length, then fill each segment 
+with color from the edges to the center.  There are several color modes described below,
+and sliders animation speed and fill "block" size.
+
+colorMode 0: User selected solid color
+colorMode 1: Animated Rainbow
+colorMode 2: Color Bands
+
+MIT License
+8/26/2021 ZRanger1
+*/
+
+// initial values for colors
+// h1 is the "stacked" color, h2 the "traveling" color
+__state__[0] = 1;
+__state__[1] = 1;
+__state__[2] = 1;
+__state__[3] = 1;
+__state__[4] = 1;
+__state__[5] = 1;
+
+__state__[6] = 6;    // number of segments
+__state__[7] = 0;            // pixels per segment
+__state__[8] = 0;           // index (offset) of segment's center
+__state__[9] = 0;              // current pixel position
+__state__[10] = 0;          // count of pixels currently "stacked" in a segment
+__state__[11] = 0;               // color animation timer
+
+// Color mode support - various ways of coloring our traveling and stacked pixels.
+// Really just an array of pointers to very short expressions that set the colors.
+// Each expression takes the current pixel index as a parameter and returns a 
+// hue value that can be plugged directly into hsv()
+//
+// By default, start in "rainbow" mode, with 3 segments.
+__state__[12] = 1;    
+__state__[13] = 3;
+__state__[14] = array(__state__[13]);
+__state__[14][0] =  (__state__, __globals__, f) => __state__[0];           
+__state__[14][1] =  (__state__, __globals__, f) => __state__[0]*wave(time(0.9))
+__state__[14][2] =  (__state__, __globals__, f) => __state__[0]+__state__[11]+f/__state__[8];
+
+__state__[12] = 1
+
+// "pixel" block size information
+__globals__[0] = 1;
+__state__[15] = __globals__[0] / 2;
+
+// speed control.  How many milliseconds do we wait between
+// pixel movements?
+__state__[16] = 0;
+__state__[17] = 100
+
+
+// Slider UI
+
+
+
+
+export function hsvPickerColor1(__state__, __globals__, h,s,v) {
+  __state__[0] = h; __state__[1] = s; //v1 = v;
+}
+
+export function hsvPickerColor2(__state__, __globals__, h,s,v) {
+  __state__[3] = h; __state__[4] = s; __state__[5] = v;
+}
+
+
+// Farbe Segment
+//h1 = 0.65 // rot 1 orange 0.999 grün blau
+__state__[1] = 1
+__state__[2] = 0.5
+
+// Farbe Moving
+//h2 = 1
+__state__[4] = 1
+__state__[5] = 1
+
+__state__[0] = 0;
+__state__[1] = 0;
+__state__[2] = 0;
+__state__[3] = 0;
+__state__[4] = 0;
+__state__[5] = 0
+
+// h1 = 0.0669, s1 = 1, v1 = 0.93
+// h2 = 0.0114, s2 = 0.778, v2 = 0.96
+
+export function sliderSpeed(__state__, __globals__, v) {
+  __state__[17] = 250 * (1-v);
+}
+
+
+
+/* export function sliderSegments(v) {
+  var n = 1+floor(v*5);
+  if (divisions != n) {
+    divisions = n;
+    initSegments();
+  }
+} */
+
+
+  
+
+// Precalculate variables to reflect current segment
+// count setting.  Called when pattern starts, and 
+// when the user changes segment size.
+
+function initSegments(__state__, __globals__) {
+  __state__[7] = floor(pixelCount / __state__[6])
+  __state__[8] = floor(__state__[7]);  
+  __state__[9] = 1;
+  __state__[10] = 0;  
+}
+
+initSegments(__state__, __globals__);
+
+export function beforeRender(__state__, __globals__, delta) {
+  __state__[16] += delta; // pixel movement timer
+  __state__[11] = time(0.03);    // color animation timer.
+  
+  // at frame movement time, calculate the current position of
+  // our moving block within a segment. If it's reached the 
+  // current "stack", reset it to the outside edges.
+  //
+  // This calculation only needs to be done for one segment since
+  // all the segments are the same size.  We use modulo arithmetic 
+  // in render() to draw as many duplicates as we need.
+  if (__state__[16] >= __state__[17]) {
+    __state__[9]++;
+    if (__state__[9] > (__state__[8]-__state__[10])) { 
+      __state__[10]+=__globals__[0]
+      if (__state__[10] >= __state__[8]) __state__[10] = 0;
+      __state__[9] = 0;
+    }
+    __state__[16] = 0
+  }
+}
+
+export function render(__state__, __globals__, index) {
+  index = pixelCount -index
+  index = index % __state__[7];            // subdivide strip into "blocks" of user specified size
+  index = __state__[8]-abs(index-__state__[8]); // where is the current pixel in the current segment?
+  
+  // if the pixel has reached the "stacked" region, color it accoring to color mode
+  // (by calling the user-selected coloring function from our array of pointers.)
+  if (index > (__state__[8] - __state__[10])) {
+    hsv(__state__[14][__state__[12]](__state__, __globals__, index),__state__[1],__state__[2]);
+  }
+  // if it's in our traveling block, use the user's "travelling" color
+  else if (abs(index - __state__[9]) < __state__[15]) {
+    hsv(__state__[3],__state__[4],__state__[5]);
+  }
+  // otherwise, it's not (yet) part of one of our colored regions, turn it off.
+  else {
+    rgb(0,0,0);
+  }
+}
\ No newline at end of file
@@ -0,0 +1,11 @@
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = wave(time(0.1))
+}
+
+export function render3D(__state__, __globals__, x, y, z, index) {
+  __globals__[1] = __globals__[0]*(index*1)
+  __globals__[2] = 1
+  __globals__[3] = 1
+  
+  hsv(__globals__[1], __globals__[2], __globals__[3])
+}
\ No newline at end of file
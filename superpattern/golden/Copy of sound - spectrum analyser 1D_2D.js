@@ -0,0 +1,168 @@
+/*
+  Sound - spectrum analyser 1D/2D
+  
+  Output demo: https://youtu.be/sZIZiAt9l4o
+  (You can connect multiple Pixelblaze to a single sensor expansion board.)
+  
+  This pattern uses the sensor expansion board and a 2D LED matrix. It displays
+  a spectrum analyser based on the frequency data from the microphone. This is a
+  real time graph where the low frequencies are plotted on the left hand side,
+  and higher frequencies are on the right.
+  
+  This pattern is meant to be displayed on an LED matrix or other 2D surface 
+  defined in the Mapper tab. Using the computer graphics convention, (x, y) = 
+  (0, 0) is the top left (positive y advances downwards). You will need to set
+  the 'width' variable below to match the width of your matrix.
+  
+  There's also a 1D fallback and a spectrum simulator used when the sensor board
+  is not detected.
+  
+  Generously contributed by ChrisNZ (Chris) from the Pixelblaze forums.
+    https://forum.electromage.com/u/chrisnz
+*/
+
+// Set this to the width of your 2D display, or number of frequency bars to plot
+__globals__[2] = 4
+__globals__[3] = pixelCount / __globals__[2]
+
+// Set the hue, saturation, and value for peak value indicators.
+// E.g. For white peaks, set peakHSV[1] = 0. No peaks, set peakHSV[2] = 0
+__globals__[4] = array(3)  // [h, s, v]
+__globals__[4][0] = 0; __globals__[4][1] = 1; __globals__[4][2] = 1
+
+
+// Get frequency information from the sensor expansion board
+__globals__[0] = array(32)
+// Start with an impossible value to detect if the sensor board is connected
+__globals__[1] = -1 
+
+// Peak values for each bar, in the range 0..`height`
+__globals__[5] = array(__globals__[2])
+// Current frequency values for each bar, in the range 0..`height`
+__globals__[6] = array(__globals__[2])     
+__globals__[7] = 0  // This will accumulate `delta` to drop our peaks by a pixel
+
+// Automatic gain / PI controller. See comments in "sound - blinkfade".
+__globals__[8] = .9       // Aim for a maximum bar of 90% full
+// Approx rolling average of the maximum bar, for feedback into the PIController
+__globals__[9] = 0
+__globals__[10] = makePIController(__state__, __globals__, .25, 1.8, 30, 0, 100)
+
+function makePIController(__state__, __globals__, kp, ki, start, min, max) {
+  var pic = array(5)
+  pic[0] = kp
+  pic[1] = ki
+  pic[2] = start
+  pic[3] = min
+  pic[4] = max
+  return pic
+}
+
+function calcPIController(__state__, __globals__, pic, err) {
+  pic[2] = clamp(pic[2] + err, pic[3], pic[4])
+  return pic[0] * err + pic[1] * pic[2]
+}
+
+
+export function beforeRender(__state__, __globals__, delta) {
+  // Calculate sensitivity based on how far away we are from our target maximum
+  __globals__[11] = max(1, calcPIController(__state__, __globals__, __globals__[10], __globals__[8] - __globals__[9]))
+
+  __globals__[12] = time(1 / 65.536)  // 1 second hue rotation
+  
+  __globals__[7] += delta
+
+  // Drop all the peaks every 100ms
+  if (__globals__[7] > 100) {
+    __globals__[7] = 0
+    for (__globals__[13] = 0; __globals__[13] < __globals__[2]; __globals__[13]++) __globals__[5][__globals__[13]] -= 1
+  }
+
+  if (__globals__[1] == -1) simulateSound(__state__, __globals__) // `light` is >= 0 if the SB is connected
+  
+  __globals__[14] = 0
+  for (__globals__[13] = 0; __globals__[13] < __globals__[2]; __globals__[13]++) {
+    __globals__[15] = log(__globals__[13] / __globals__[2] + 1) // Plot lower bins (log of 2 = bottom 30%)
+    // Determine the portion of the bar filled based on the current sound level.
+    // We use the PIController sensitivity to try and keep this at the targetMax
+    __globals__[16] = __globals__[0][__globals__[15] * 32] * __globals__[11]
+    __globals__[6][__globals__[13]] = floor(min(1, __globals__[16]) * __globals__[3])
+    __globals__[5][__globals__[13]] = max(__globals__[5][__globals__[13]], __globals__[6][__globals__[13]] - 1)
+
+    __globals__[14] = max(__globals__[14], __globals__[16])
+  }
+  __globals__[9] = __globals__[9] - (__globals__[9] / 50) + (__globals__[14] / 50)
+}
+
+export function render2D(__state__, __globals__, index, x, y) {
+  __globals__[17] = floor(x * __globals__[2])  // Converts 0..1 'world units' x into pixel width
+  __globals__[18] = __globals__[3] - 1 - floor(y * __globals__[3]) // Invert so baseline is yPixel == 0
+
+  __globals__[19] = __globals__[12] + x // Cycle the bar color through the rainbow. hsv() 'wraps' h.
+  __globals__[20] = 1
+  __globals__[21] = __globals__[6][__globals__[17]] > __globals__[18]  // Fill bars from 0..fy[xPixel]
+  
+  // If this is a peak pixel, apply the peakHSV color
+  if (__globals__[5][__globals__[17]] == __globals__[18]) {
+    __globals__[19] = __globals__[4][0]; __globals__[20] = __globals__[4][1]; __globals__[21] = __globals__[4][2]
+  }
+  
+  hsv(__globals__[19], __globals__[20], __globals__[21])
+}
+
+// The 1D fallback plots the raw 32-bin spectrum across all pixels in a strip
+export function render(__state__, __globals__, index) {
+  __globals__[19] = __globals__[12] + index/pixelCount // Cycle bar color. Remember, hsv() 'wraps' h.
+  
+  // Spread all 32 bins across the strip and interpolate
+  __globals__[22] = pixelCount / 31
+  __globals__[23] = floor(index / __globals__[22])
+  __globals__[24] = (index % __globals__[22]) / __globals__[22]
+  __globals__[21] = (1 - __globals__[24]) * __globals__[0][__globals__[23]] + __globals__[24] * __globals__[0][__globals__[23] + 1]
+  __globals__[21] *= __globals__[11] // Scale by PI controller's sensitivity
+
+  hsv(__globals__[19], 1, __globals__[21] * __globals__[21])
+}
+
+
+/*
+  Simulate the sensor board variables used in this pattern, if no sensor board
+  is detected. The values and waveforms were chosen to approximate the look when
+  real sound is sensed for a basic 4-on-the-floor loop.
+*/
+__globals__[25] = 120
+__state__[0] = 4 * 60 / __globals__[25] / 65.536
+
+function simulateSound(__state__, __globals__) {
+  __globals__[26] = time(__state__[0]) // 2 seconds per measure @120 BPM
+  __globals__[27] = time(8 * __state__[0]) // 8 measures per phrase
+  for (__globals__[13] = 0; __globals__[13] < 32; __globals__[13]++) __globals__[0][__globals__[13]] = 0
+  
+  __globals__[28] = (-4 * __globals__[26] + 5) % 1 // 4 attacks per measure
+  __globals__[28] *= .02 * pow(__globals__[28], 4)  // Scale magnitude and make concave-up
+  // Splay energy out, most energy at lowest frequency bins
+  for (__globals__[13] = 0; __globals__[13] < 10; __globals__[13]++) __globals__[0][__globals__[13]] += __globals__[28] * (10 - __globals__[13]) / 10
+
+  __globals__[29] = .01 * square(2 * __globals__[26] - .5, .10) // "&" of every beat
+  for (__globals__[13] = 9; __globals__[13] < 14 + random(10); __globals__[13]++) 
+    __globals__[0][__globals__[13]] += __globals__[29] * (.7 + .6 * random(__globals__[13] % 2))
+
+  __globals__[30] = .003 * square(4 * __globals__[26] - .5, .05) // Beats 2 and 4
+  for (__globals__[13] = 20; __globals__[13] < 30; __globals__[13]++) {
+    __globals__[0][__globals__[13]] += __globals__[30] * (.8 + random(.4)) * (__globals__[13] % 3 < 2)
+  }
+
+  __globals__[31] = 4 + floor(16 * wander(__state__, __globals__, __globals__[27]))  // Wandering fundamental synth's freq bin
+  for (__globals__[13] = 4; __globals__[13] < 20; __globals__[13]++)
+    // Excite the fundamental and, 20% of the time, 4 bins up
+    __globals__[0][__globals__[13]] += .005 * (__globals__[31] == __globals__[13] || __globals__[31] == (__globals__[13] - 4) * r(__state__, __globals__, .2))
+}
+
+// Random-ish perlin-esque walk for t in 0..1, outputs 0..1
+// https://www.desmos.com/calculator/enggm6rcrm
+function wander(__state__, __globals__, t) {
+  t *= 49.261 // Selected so t's wraparound will have continuous output
+  return (wave(t / 2) * wave(t / 3) * wave(t / 5) + wave(t / 7)) / 2
+}
+
+function r(__state__, __globals__, p) { return random(1) < p } // Randomly true with probability p
\ No newline at end of file
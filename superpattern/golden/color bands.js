@@ -0,0 +1,32 @@
+/*
+  Color bands
+  
+  Color bands has a chill vibe that comes from applying slower phase shifts to
+  shorter wavelengths.
+  
+  It's also a good pattern to learn about mixing in just the right amount of
+  desaturation (making whites, pinks, mints, etc) as well as modulating your
+  colors, white spots, and dark spots independently.
+*/
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = time(.25)
+  __globals__[1] = time(.15)
+}
+
+export function render(__state__, __globals__, index) {
+  __globals__[2] = index / (pixelCount / 2) // Notice how each hue appears twice
+  
+  // Create the areas where white is mixed in. Start with a wave.
+  __globals__[3] = wave(-index / 3 + __globals__[0])
+  
+  // A little desaturation goes a long way, so it's typical to start from 1 
+  // (saturated) and sharply dip to 0 to make white areas.
+  __globals__[3] = 1 - __globals__[3] * __globals__[3] * __globals__[3] * __globals__[3]
+  
+  // Create the slowly moving dark regions
+  __globals__[4] = wave(index / 2 + __globals__[1]) * wave(index / 5 - __globals__[1]) + wave(index / 7 + __globals__[1])
+  
+  __globals__[4] = __globals__[4] * __globals__[4] * __globals__[4] * __globals__[4]
+  hsv(__globals__[2], __globals__[3], __globals__[4])
+}
@@ -0,0 +1,32 @@
+/*
+  Color fade pulse
+  
+  Pulses travel slowly to the left, while colors travel quickly to the right.
+  Pulses change how colorful they are slowly, close to the pulse moving speed.
+*/
+
+export function beforeRender(__state__, __globals__, delta) {
+  __globals__[0] = time(.01) // For hue movement
+  __globals__[1] = time(.02) // For pulse movement
+  __globals__[2] = time(.1)  // White / desaturation movement
+}
+
+export function render(__state__, __globals__, index) {
+  // When you see a function using time as a `- t1` phase shift, this is moving
+  // to the right.
+  __globals__[3] = index / pixelCount * 2 - __globals__[0]
+
+  /*
+    This creates the pulses themselves. A `+ t2` indicates these will be moving 
+    to the left. The `* 4` makes them more frequent in the strip. In fact, you 
+    an think of this as "having 4 pulses visible at any given time."
+  */
+  __globals__[4] = triangle(index / pixelCount * 4 + __globals__[1]) 
+  __globals__[4] = __globals__[4] * __globals__[4] * __globals__[4] * __globals__[4]
+    
+  // Every few pulses will be whiter (low saturation). Each pulse will very 
+  // slowly alternate between a whitish pulse and deeper saturated hues.
+  __globals__[5] = wave(index / pixelCount / 2 + __globals__[2])
+  
+  hsv(__globals__[3], __globals__[5], __globals__[4])
+}
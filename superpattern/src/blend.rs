@@ -0,0 +1,413 @@
+//! # Blend Modes
+//!
+//! How a layer is blended onto the layers below it. The generated Pixelblaze
+//! functions blend the color of the layer (`r`, `g`, `b`) into the color of
+//! the layers below (`__r`, `__g`, `__b`), all channels from 0 to 1.
+//!
+//! [`BlendMode::blend`] does the same on the host. It is the reference the
+//! generated code is tested against.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::combine::CombineError;
+
+/// Blend mode of a layer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    /// Sum of both colors, saturating at full brightness
+    Add,
+    /// The layer darkens the layers below by its color
+    Subtract,
+    /// Mean of both colors
+    Average,
+    /// The layer shows through the brightness of the layers below
+    Mask,
+    /// Product of both colors, only darkens
+    Multiply,
+    /// Inverted product of the inverted colors, only brightens
+    Screen,
+    /// Multiply where the layers below are dark, screen where they are bright
+    Overlay,
+    /// Brighter of both colors, by channel
+    Lighten,
+    /// Darker of both colors, by channel
+    Darken,
+    /// Absolute difference of both colors
+    Difference,
+    /// Hue of the layer with saturation and brightness of the layers below
+    Hue,
+    /// Layer mixed in by an amount from 0 (only the layers below) to 1 (only
+    /// the layer)
+    Crossfade(f64),
+    /// The layer over the layers below, with its brightness as opacity, so
+    /// black is transparent
+    AlphaOver,
+}
+
+impl BlendMode {
+    /// All blend modes, crossfading half way.
+    pub const ALL: [BlendMode; 13] = [
+        BlendMode::Add,
+        BlendMode::Subtract,
+        BlendMode::Average,
+        BlendMode::Mask,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::Lighten,
+        BlendMode::Darken,
+        BlendMode::Difference,
+        BlendMode::Hue,
+        BlendMode::Crossfade(0.5),
+        BlendMode::AlphaOver,
+    ];
+
+    /// Name in combinations, the first four as used by `superpattern-js`.
+    /// The amount of [`BlendMode::Crossfade`] follows its name, e.g.
+    /// `CROSSFADE 0.3`.
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendMode::Add => "ADD",
+            BlendMode::Subtract => "SUB",
+            BlendMode::Average => "AVG",
+            BlendMode::Mask => "MASK",
+            BlendMode::Multiply => "MULTIPLY",
+            BlendMode::Screen => "SCREEN",
+            BlendMode::Overlay => "OVERLAY",
+            BlendMode::Lighten => "LIGHTEN",
+            BlendMode::Darken => "DARKEN",
+            BlendMode::Difference => "DIFFERENCE",
+            BlendMode::Hue => "HUE",
+            BlendMode::Crossfade(_) => "CROSSFADE",
+            BlendMode::AlphaOver => "ALPHA",
+        }
+    }
+
+    /// Blend the color of a layer onto the color of the layers below.
+    pub fn blend(self, below: [f64; 3], layer: [f64; 3]) -> [f64; 3] {
+        let by_channel = |f: fn(f64, f64) -> f64| {
+            [
+                f(below[0], layer[0]),
+                f(below[1], layer[1]),
+                f(below[2], layer[2]),
+            ]
+        };
+        match self {
+            BlendMode::Add => by_channel(|b, l| (b + l).min(1.0)),
+            BlendMode::Subtract => by_channel(|b, l| (b - l).max(0.0)),
+            BlendMode::Average => by_channel(|b, l| (b + l) / 2.0),
+            BlendMode::Mask => {
+                let brightness = (below[0] + below[1] + below[2]) / 3.0;
+                layer.map(|l| l * brightness)
+            }
+            BlendMode::Multiply => by_channel(|b, l| b * l),
+            BlendMode::Screen => by_channel(|b, l| 1.0 - (1.0 - b) * (1.0 - l)),
+            BlendMode::Overlay => by_channel(|b, l| {
+                if b < 0.5 {
+                    2.0 * b * l
+                } else {
+                    1.0 - 2.0 * (1.0 - b) * (1.0 - l)
+                }
+            }),
+            BlendMode::Lighten => by_channel(f64::max),
+            BlendMode::Darken => by_channel(f64::min),
+            BlendMode::Difference => by_channel(|b, l| (b - l).abs()),
+            BlendMode::Hue => {
+                // A hue is the position of the channels between the lowest
+                // and the highest one, keep those of the layers below
+                let (low, high) = extremes(layer);
+                if high == low {
+                    return below;
+                }
+                let (bottom, top) = extremes(below);
+                let scale = (top - bottom) / (high - low);
+                layer.map(|l| bottom + (l - low) * scale)
+            }
+            BlendMode::Crossfade(amount) => {
+                let amount = amount.clamp(0.0, 1.0);
+                [0, 1, 2].map(|c| below[c] + (layer[c] - below[c]) * amount)
+            }
+            BlendMode::AlphaOver => {
+                let (_, alpha) = extremes(layer);
+                [0, 1, 2].map(|c| layer[c] * alpha + below[c] * (1.0 - alpha))
+            }
+        }
+    }
+
+    /// Name of the generated Pixelblaze function.
+    fn function(self) -> &'static str {
+        match self {
+            BlendMode::Add => "__blendAdd",
+            BlendMode::Subtract => "__blendSubtract",
+            BlendMode::Average => "__blendAverage",
+            BlendMode::Mask => "__blendMask",
+            BlendMode::Multiply => "__blendMultiply",
+            BlendMode::Screen => "__blendScreen",
+            BlendMode::Overlay => "__blendOverlay",
+            BlendMode::Lighten => "__blendLighten",
+            BlendMode::Darken => "__blendDarken",
+            BlendMode::Difference => "__blendDifference",
+            BlendMode::Hue => "__blendHue",
+            BlendMode::Crossfade(_) => "__blendCrossfade",
+            BlendMode::AlphaOver => "__blendAlphaOver",
+        }
+    }
+
+    /// Pixelblaze call blending a color, given as `r, g, b` expressions.
+    pub(crate) fn call(self, color: &str) -> String {
+        match self {
+            BlendMode::Crossfade(amount) => {
+                format!("{}({color}, {})", self.function(), amount.clamp(0.0, 1.0))
+            }
+            _ => format!("{}({color})", self.function()),
+        }
+    }
+
+    /// Pixelblaze declaration of the function of [`Self::call`], the same
+    /// for all amounts of a mode.
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            BlendMode::Add => {
+                "function __blendAdd(r, g, b) {
+  __r = min(1, __r + r)
+  __g = min(1, __g + g)
+  __b = min(1, __b + b)
+}"
+            }
+            BlendMode::Subtract => {
+                "function __blendSubtract(r, g, b) {
+  __r = max(0, __r - r)
+  __g = max(0, __g - g)
+  __b = max(0, __b - b)
+}"
+            }
+            BlendMode::Average => {
+                "function __blendAverage(r, g, b) {
+  __r = (__r + r) / 2
+  __g = (__g + g) / 2
+  __b = (__b + b) / 2
+}"
+            }
+            BlendMode::Mask => {
+                "function __blendMask(r, g, b) {
+  var brightness = (__r + __g + __b) / 3
+  __r = r * brightness
+  __g = g * brightness
+  __b = b * brightness
+}"
+            }
+            BlendMode::Multiply => {
+                "function __blendMultiply(r, g, b) {
+  __r = __r * r
+  __g = __g * g
+  __b = __b * b
+}"
+            }
+            BlendMode::Screen => {
+                "function __blendScreen(r, g, b) {
+  __r = 1 - (1 - __r) * (1 - r)
+  __g = 1 - (1 - __g) * (1 - g)
+  __b = 1 - (1 - __b) * (1 - b)
+}"
+            }
+            BlendMode::Overlay => {
+                "function __overlay(below, layer) {
+  return below < 0.5 ? 2 * below * layer : 1 - 2 * (1 - below) * (1 - layer)
+}
+
+function __blendOverlay(r, g, b) {
+  __r = __overlay(__r, r)
+  __g = __overlay(__g, g)
+  __b = __overlay(__b, b)
+}"
+            }
+            BlendMode::Lighten => {
+                "function __blendLighten(r, g, b) {
+  __r = max(__r, r)
+  __g = max(__g, g)
+  __b = max(__b, b)
+}"
+            }
+            BlendMode::Darken => {
+                "function __blendDarken(r, g, b) {
+  __r = min(__r, r)
+  __g = min(__g, g)
+  __b = min(__b, b)
+}"
+            }
+            BlendMode::Difference => {
+                "function __blendDifference(r, g, b) {
+  __r = abs(__r - r)
+  __g = abs(__g - g)
+  __b = abs(__b - b)
+}"
+            }
+            BlendMode::Hue => {
+                "function __blendHue(r, g, b) {
+  var high = max(r, max(g, b))
+  var low = min(r, min(g, b))
+  var bottom = min(__r, min(__g, __b))
+  var top = max(__r, max(__g, __b))
+  var scale = 0
+  if (high > low) {
+    scale = (top - bottom) / (high - low)
+    __r = bottom + (r - low) * scale
+    __g = bottom + (g - low) * scale
+    __b = bottom + (b - low) * scale
+  }
+}"
+            }
+            BlendMode::Crossfade(_) => {
+                "function __blendCrossfade(r, g, b, amount) {
+  __r = __r + (r - __r) * amount
+  __g = __g + (g - __g) * amount
+  __b = __b + (b - __b) * amount
+}"
+            }
+            BlendMode::AlphaOver => {
+                "function __blendAlphaOver(r, g, b) {
+  var alpha = max(r, max(g, b))
+  __r = r * alpha + __r * (1 - alpha)
+  __g = g * alpha + __g * (1 - alpha)
+  __b = b * alpha + __b * (1 - alpha)
+}"
+            }
+        }
+    }
+}
+
+/// Lowest and highest channel of a color.
+fn extremes(color: [f64; 3]) -> (f64, f64) {
+    (
+        color[0].min(color[1]).min(color[2]),
+        color[0].max(color[1]).max(color[2]),
+    )
+}
+
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlendMode::Crossfade(amount) => write!(f, "{} {amount}", self.name()),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = CombineError;
+
+    /// Parse a name like `ADD`, in any case, or a crossfade with its amount
+    /// from 0 to 1 like `CROSSFADE 0.3`.
+    fn from_str(name: &str) -> Result<Self, CombineError> {
+        let unknown = || CombineError::UnknownBlendMode(name.trim().to_string());
+        let mut words = name.split_whitespace();
+        let mode = words.next().ok_or_else(unknown)?;
+        let mode = BlendMode::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(mode))
+            .ok_or_else(unknown)?;
+
+        let mode = match (mode, words.next()) {
+            (BlendMode::Crossfade(_), Some(amount)) => match amount.parse() {
+                Ok(amount) if (0.0..=1.0).contains(&amount) => BlendMode::Crossfade(amount),
+                _ => return Err(unknown()),
+            },
+            (mode, None) if !matches!(mode, BlendMode::Crossfade(_)) => mode,
+            _ => return Err(unknown()),
+        };
+        match words.next() {
+            Some(_) => Err(unknown()),
+            None => Ok(mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::{assert_color, Sandbox};
+
+    const COLORS: [[f64; 3]; 6] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 1.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [0.2, 0.6, 0.9],
+        [0.8, 0.4, 0.3],
+        [0.5, 0.5, 0.1],
+    ];
+
+    #[test]
+    fn generated_code_matches_reference() {
+        let modes = BlendMode::ALL
+            .into_iter()
+            .chain([BlendMode::Crossfade(0.0), BlendMode::Crossfade(0.3)]);
+        for mode in modes {
+            let sandbox = Sandbox::new(&format!(
+                "var __r = 0\nvar __g = 0\nvar __b = 0\n{}",
+                mode.code()
+            ));
+            for below in COLORS {
+                for layer in COLORS {
+                    let [r, g, b] = below;
+                    let call = mode.call(&format!("{}, {}, {}", layer[0], layer[1], layer[2]));
+                    let actual: Vec<f64> = sandbox.eval(&format!(
+                        "__r = {r}; __g = {g}; __b = {b}; {call}; [__r, __g, __b]"
+                    ));
+                    let expected = mode.blend(below, layer);
+                    let close = actual
+                        .iter()
+                        .zip(expected)
+                        .all(|(a, e)| (a - e).abs() < 1e-9);
+                    assert!(
+                        close,
+                        "{mode} of {layer:?} onto {below:?}: {actual:?} != {expected:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blends_like_the_modes_say() {
+        let red = [1.0, 0.0, 0.0];
+        let blue = [0.0, 0.0, 1.0];
+        let dim = [0.2, 0.6, 0.4];
+        assert_color(BlendMode::Multiply.blend(dim, red), [0.2, 0.0, 0.0]);
+        assert_color(BlendMode::Screen.blend(dim, [0.0; 3]), dim);
+        assert_color(BlendMode::Lighten.blend(dim, red), [1.0, 0.6, 0.4]);
+        assert_color(BlendMode::Darken.blend(dim, red), [0.2, 0.0, 0.0]);
+        // Hue of blue, saturation and value of dim
+        assert_color(BlendMode::Hue.blend(dim, blue), [0.2, 0.2, 0.6]);
+        assert_color(BlendMode::Hue.blend(dim, [0.5; 3]), dim);
+        assert_color(BlendMode::Crossfade(0.0).blend(dim, red), dim);
+        assert_color(BlendMode::Crossfade(1.0).blend(dim, red), red);
+        // Black is transparent, full brightness opaque
+        assert_color(BlendMode::AlphaOver.blend(dim, [0.0; 3]), dim);
+        assert_color(BlendMode::AlphaOver.blend(dim, blue), blue);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("add".parse(), Ok(BlendMode::Add));
+        assert_eq!(" Alpha ".parse(), Ok(BlendMode::AlphaOver));
+        assert_eq!("CROSSFADE 0.3".parse(), Ok(BlendMode::Crossfade(0.3)));
+        assert_eq!(BlendMode::Crossfade(0.3).to_string(), "CROSSFADE 0.3");
+        for name in [
+            "CROSSFADE",
+            "CROSSFADE 2",
+            "CROSSFADE x",
+            "ADD 1",
+            "",
+            "BURN",
+        ] {
+            assert_eq!(
+                name.parse::<BlendMode>(),
+                Err(CombineError::UnknownBlendMode(name.to_string()))
+            );
+        }
+        for mode in BlendMode::ALL {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+    }
+}
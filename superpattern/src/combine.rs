@@ -0,0 +1,591 @@
+//! # Pattern Combination
+//!
+//! Combines transformed patterns into one Pixelblaze pattern. The patterns
+//! are layers: for every pixel each layer renders, the color it sets with
+//! `hsv`, `rgb` or `hsv24` is captured as RGB, and the layers are blended
+//! bottom to top, each with its own blend mode onto the result of the layers
+//! below. The blended color is output with `rgb`.
+//!
+//! Every layer keeps its own `__state__` and `__globals__` arrays. Its
+//! functions are renamed to `__layer<n>_<name>` and its top-level code runs
+//! once at startup. The combined pattern exports:
+//! - `beforeRender`, `render` and, if a layer has them, `render2D` and
+//!   `render3D`. Layers without a renderer for the dimensions Pixelblaze
+//!   renders fall back to their renderer for fewer dimensions.
+//! - The controls of each layer (sliders, pickers, toggles, ...), e.g.
+//!   `sliderSpeed` of layer 1 as `sliderLayer1Speed`.
+//!
+//! The generated code only uses Pixelblaze builtins (no `Math`, strings or
+//! `switch`) and allocates no arrays while rendering.
+
+use std::fmt;
+use std::str::FromStr;
+
+use tree_sitter::Node;
+
+use crate::blend::BlendMode;
+use crate::edit::{self, Edit};
+use crate::scope::{self, Binding, Variables};
+use crate::transform::TransformResult;
+
+/// Pixelblaze renderers with their parameters, by dimensions (1D to 3D).
+const RENDERERS: [(&str, &str); 3] = [
+    ("render", "index"),
+    ("render2D", "index, x, y"),
+    ("render3D", "index, x, y, z"),
+];
+
+/// Pixelblaze functions setting the color of a pixel, captured per layer.
+const COLOR_OUTPUTS: [&str; 3] = ["hsv", "rgb", "hsv24"];
+
+/// Prefixes of exported functions Pixelblaze shows as controls.
+const CONTROLS: [&str; 8] = [
+    "slider",
+    "hsvPicker",
+    "rgbPicker",
+    "toggle",
+    "trigger",
+    "inputNumber",
+    "showNumber",
+    "gauge",
+];
+
+/// Color capture, `{layers}` is replaced by the number of layers.
+const CAPTURE: &str = "// Color of each layer for the current pixel
+var __layer = 0
+var __red = array({layers})
+var __green = array({layers})
+var __blue = array({layers})
+
+// Blended color
+var __r = 0
+var __g = 0
+var __b = 0
+
+// Start rendering a layer, black unless it sets a color
+function __begin(layer) {
+  __layer = layer
+  __red[layer] = 0
+  __green[layer] = 0
+  __blue[layer] = 0
+}
+
+function __capture(r, g, b) {
+  __red[__layer] = r
+  __green[__layer] = g
+  __blue[__layer] = b
+}
+
+// Color output of the layers, captured as RGB
+function __rgb(r, g, b) {
+  __capture(clamp(r, 0, 1), clamp(g, 0, 1), clamp(b, 0, 1))
+}
+
+function __hsv(h, s, v) {
+  h = (h - floor(h)) * 6
+  s = clamp(s, 0, 1)
+  v = clamp(v, 0, 1)
+  var i = floor(h)
+  var f = h - i
+  var p = v * (1 - s)
+  var q = v * (1 - s * f)
+  var t = v * (1 - s * (1 - f))
+  if (i == 0) __capture(v, t, p)
+  else if (i == 1) __capture(q, v, p)
+  else if (i == 2) __capture(p, v, t)
+  else if (i == 3) __capture(p, q, v)
+  else if (i == 4) __capture(t, p, v)
+  else __capture(v, p, q)
+}
+
+// Same color as hsv(), only with less precision on Pixelblaze
+function __hsv24(h, s, v) {
+  __hsv(h, s, v)
+}
+";
+
+/// Combination error types
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombineError {
+    /// Nothing to combine
+    NoPatterns,
+    /// Not exactly one blend mode between each two patterns
+    BlendModeCount { patterns: usize, blend_modes: usize },
+    /// Blend mode name not known
+    UnknownBlendMode(String),
+    /// Combination without a name
+    MissingName,
+}
+
+impl fmt::Display for CombineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CombineError::NoPatterns => f.write_str("no patterns to combine"),
+            CombineError::BlendModeCount {
+                patterns,
+                blend_modes,
+            } => write!(
+                f,
+                "{patterns} patterns need {} blend modes, not {blend_modes}",
+                patterns.saturating_sub(1)
+            ),
+            CombineError::UnknownBlendMode(name) => write!(f, "unknown blend mode {name:?}"),
+            CombineError::MissingName => f.write_str("combination without a name"),
+        }
+    }
+}
+
+impl std::error::Error for CombineError {}
+
+/// Combination of pattern files, as listed in `superpattern/combinations.txt`:
+/// ```text
+/// Lighthouse Sparkle: # Leuchtturm.epe, ADD, blink fade.epe
+/// ```
+/// The name, then the patterns from bottom to top with the blend mode of the
+/// upper pattern between each two.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combination {
+    pub name: String,
+    pub patterns: Vec<String>,
+    pub blend_modes: Vec<BlendMode>,
+}
+
+impl FromStr for Combination {
+    type Err = CombineError;
+
+    fn from_str(line: &str) -> Result<Self, CombineError> {
+        let (name, layers) = line.split_once(':').ok_or(CombineError::MissingName)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(CombineError::MissingName);
+        }
+
+        let mut patterns = Vec::new();
+        let mut blend_modes = Vec::new();
+        for (n, item) in layers.split(',').map(str::trim).enumerate() {
+            match n % 2 {
+                0 => patterns.push(item.to_string()),
+                _ => blend_modes.push(item.parse()?),
+            }
+        }
+        if patterns.iter().any(String::is_empty) {
+            return Err(CombineError::NoPatterns);
+        }
+        check_blend_modes(patterns.len(), blend_modes.len())?;
+
+        Ok(Self {
+            name: name.to_string(),
+            patterns,
+            blend_modes,
+        })
+    }
+}
+
+/// Combine transformed patterns, from the bottom layer to the top one.
+/// `blend_modes[n]` blends pattern `n + 1` onto the patterns below it.
+pub fn combine_patterns(
+    patterns: &[TransformResult],
+    blend_modes: &[BlendMode],
+) -> Result<String, CombineError> {
+    check_blend_modes(patterns.len(), blend_modes.len())?;
+    let layers: Vec<Layer> = patterns
+        .iter()
+        .enumerate()
+        .map(|(n, pattern)| Layer::new(n, pattern))
+        .collect();
+
+    let mut out = String::from("// Superpattern generated by the superpattern crate\n// Layer 0\n");
+    for (n, mode) in blend_modes.iter().enumerate() {
+        out += &format!("// Layer {}, blended with {mode}\n", n + 1);
+    }
+    out += "\n";
+    out += &CAPTURE.replace("{layers}", &layers.len().to_string());
+
+    // Blend functions, each once
+    let mut used: Vec<&str> = Vec::new();
+    for mode in blend_modes {
+        if !used.contains(&mode.code()) {
+            used.push(mode.code());
+            out += &format!("\n{}\n", mode.code());
+        }
+    }
+    out += "\n// Blend the layers from bottom to top and output the color\nfunction __blend() {\n";
+    out += "  __r = __red[0]\n  __g = __green[0]\n  __b = __blue[0]\n";
+    for (n, mode) in blend_modes.iter().enumerate() {
+        let n = n + 1;
+        let call = mode.call(&format!("__red[{n}], __green[{n}], __blue[{n}]"));
+        out += &format!("  {call}\n");
+    }
+    out += "  rgb(__r, __g, __b)\n}\n";
+
+    for layer in &layers {
+        out += &layer.code();
+    }
+
+    out += "\n// Run the top-level code of the layers\n";
+    for layer in &layers {
+        out += &format!("__init{0}(__state{0}, __globals{0})\n", layer.index);
+    }
+
+    out += "\nexport function beforeRender(delta) {\n";
+    for layer in layers.iter().filter(|l| l.exports("beforeRender")) {
+        out += &format!("  {}\n", layer.call("beforeRender", "delta"));
+    }
+    out += "}\n";
+
+    let dimensions = layers
+        .iter()
+        .filter_map(Layer::dimensions)
+        .max()
+        .unwrap_or(0);
+    for (dimension, (renderer, parameters)) in RENDERERS.iter().enumerate().take(dimensions + 1) {
+        out += &format!("\nexport function {renderer}({parameters}) {{\n");
+        for layer in &layers {
+            out += &format!("  __begin({})\n", layer.index);
+            // Fall back to the renderer for the most dimensions up to these
+            let fallback = RENDERERS[..=dimension]
+                .iter()
+                .rev()
+                .find(|(renderer, _)| layer.exports(renderer));
+            if let Some((renderer, parameters)) = fallback {
+                out += &format!("  {}\n", layer.call(renderer, parameters));
+            }
+        }
+        out += "  __blend()\n}\n";
+    }
+
+    for layer in &layers {
+        for export in &layer.exports {
+            let Some(control) = CONTROLS.iter().find(|c| export.name.starts_with(*c)) else {
+                continue;
+            };
+            let parameters = export.parameters.join(", ");
+            out += &format!(
+                "\nexport function {control}Layer{}{}({parameters}) {{\n  {}\n}}\n",
+                layer.index,
+                &export.name[control.len()..],
+                layer.call(&export.name, &parameters),
+            );
+        }
+    }
+
+    Ok(out)
+}
+
+fn check_blend_modes(patterns: usize, blend_modes: usize) -> Result<(), CombineError> {
+    match (patterns, blend_modes) {
+        (0, _) => Err(CombineError::NoPatterns),
+        (patterns, blend_modes) if blend_modes + 1 != patterns => {
+            Err(CombineError::BlendModeCount {
+                patterns,
+                blend_modes,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Exported function of a layer.
+#[derive(Debug)]
+struct Export {
+    name: String,
+    /// Parameters after `__state__` and `__globals__`
+    parameters: Vec<String>,
+}
+
+/// Pattern split up for combination.
+#[derive(Debug)]
+struct Layer {
+    index: usize,
+    state_len: usize,
+    globals_len: usize,
+    /// Top-level code
+    init: String,
+    /// Function declarations, renamed
+    functions: String,
+    exports: Vec<Export>,
+}
+
+impl Layer {
+    fn new(index: usize, pattern: &TransformResult) -> Self {
+        let source = pattern.transformed_pattern.as_str();
+        let tree = scope::parse(source);
+        let variables = Variables::new(&tree, source);
+        let text = |node: Node| &source[node.byte_range()];
+        let mut edits = Vec::new();
+        let mut exports = Vec::new();
+        let mut init = Vec::new();
+        let mut functions = Vec::new();
+
+        for statement in scope::children(tree.root_node()) {
+            let declaration = match statement.kind() {
+                "export_statement" => statement.child_by_field_name("declaration"),
+                _ => Some(statement),
+            };
+            let Some(declaration) = declaration.filter(|&d| is_function_declaration(d)) else {
+                init.push(statement);
+                continue;
+            };
+            functions.push(statement);
+
+            if declaration != statement {
+                // The combined pattern exports wrappers instead
+                edits.push(Edit {
+                    range: statement.start_byte()..declaration.start_byte(),
+                    text: String::new(),
+                });
+                let name = declaration.child_by_field_name("name").map_or("", text);
+                let parameters = declaration
+                    .child_by_field_name("parameters")
+                    .map(scope::children)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|p| !p.is_extra())
+                    .skip(2)
+                    .map(|p| text(p).to_string())
+                    .collect();
+                exports.push(Export {
+                    name: name.to_string(),
+                    parameters,
+                });
+            }
+        }
+
+        for node in scope::descendants(tree.root_node()) {
+            if node.kind() != "identifier" {
+                continue;
+            }
+            let name = text(node);
+            let parent = node.parent();
+            let renamed = match scope::is_binding(node) {
+                true => {
+                    parent.is_some_and(|p| is_function_declaration(p) && scope::is_top_level(p))
+                }
+                false => variables.resolve(node, name) == Binding::Function,
+            };
+            let captured = COLOR_OUTPUTS.contains(&name)
+                && parent.is_some_and(|p| {
+                    p.kind() == "call_expression" && p.child_by_field_name("function") == Some(node)
+                })
+                && variables.resolve(node, name) == Binding::Unknown;
+
+            if renamed {
+                edits.push(Edit {
+                    range: node.byte_range(),
+                    text: format!("__layer{index}_{name}"),
+                });
+            } else if captured {
+                edits.push(Edit {
+                    range: node.byte_range(),
+                    text: format!("__{name}"),
+                });
+            }
+        }
+
+        let mut join = |statements: Vec<Node>, separator| {
+            statements
+                .into_iter()
+                .map(|s| edit::apply(source, &mut edits, s.byte_range()))
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        Self {
+            index,
+            state_len: pattern.state_vars.len(),
+            globals_len: pattern.globals.len(),
+            init: join(init, "\n"),
+            functions: join(functions, "\n\n"),
+            exports,
+        }
+    }
+
+    fn exports(&self, name: &str) -> bool {
+        self.exports.iter().any(|e| e.name == name)
+    }
+
+    /// Most dimensions the layer renders, `None` without renderer.
+    fn dimensions(&self) -> Option<usize> {
+        RENDERERS
+            .iter()
+            .rposition(|(renderer, _)| self.exports(renderer))
+    }
+
+    /// Call of a function of the layer, with its arrays.
+    fn call(&self, name: &str, arguments: &str) -> String {
+        let n = self.index;
+        match arguments.is_empty() {
+            true => format!("__layer{n}_{name}(__state{n}, __globals{n})"),
+            false => format!("__layer{n}_{name}(__state{n}, __globals{n}, {arguments})"),
+        }
+    }
+
+    /// Arrays, top-level code and functions of the layer.
+    fn code(&self) -> String {
+        let n = self.index;
+        let init: Vec<String> = self
+            .init
+            .lines()
+            .map(|line| match line.is_empty() {
+                true => String::new(),
+                false => format!("  {line}"),
+            })
+            .collect();
+        format!(
+            "\n// Layer {n}\nvar __state{n} = array({})\nvar __globals{n} = array({})\n\n\
+             function __init{n}(__state__, __globals__) {{\n{}\n}}\n\n{}\n",
+            self.state_len.max(1),
+            self.globals_len.max(1),
+            init.join("\n"),
+            self.functions,
+        )
+    }
+}
+
+fn is_function_declaration(node: Node) -> bool {
+    node.kind().ends_with("function_declaration")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::{assert_color, Sandbox};
+    use crate::{check_syntax, transform_pattern};
+
+    fn combine(patterns: &[&str], blend_modes: &[BlendMode]) -> Sandbox {
+        let patterns: Vec<_> = patterns.iter().map(|p| transform_pattern(p)).collect();
+        let combined = combine_patterns(&patterns, blend_modes).unwrap();
+        check_syntax(&combined).unwrap();
+        Sandbox::new(&combined)
+    }
+
+    const RED: &str = "export function render(index) { hsv(0, 1, 1) }";
+    const GREEN: &str = "export function render(index) { hsv(1 / 3, 1, 1) }";
+    const GRAY: &str = "export function render(index) { hsv(0.7, 0, 0.5) }";
+
+    #[test]
+    fn blends_each_layer_with_its_mode() {
+        use BlendMode::*;
+
+        let layers = [RED, GREEN, GRAY];
+        assert_color(
+            combine(&layers, &[Add, Subtract]).render("render(0)"),
+            [0.5, 0.5, 0.0],
+        );
+        assert_color(
+            combine(&layers, &[Subtract, Add]).render("render(0)"),
+            [1.0, 0.5, 0.5],
+        );
+        assert_color(
+            combine(&layers, &[Average, Mask]).render("render(0)"),
+            [1.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0],
+        );
+        assert_color(combine(&[GREEN], &[]).render("render(0)"), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn captures_all_color_outputs() {
+        let rgb = "export function render(index) { rgb(0.2, 0.4, 1.5) }";
+        let hsv24 = "export function render(index) { hsv24(2 / 3, 1, 0.5) }";
+        let hsv = "function hsv(h, s, v) { rgb(v, v, v) }\n\
+                   export function render(index) { hsv(0, 1, 0.1) }";
+
+        // Output as is, without converting to HSV and back
+        let sandbox = combine(&[rgb], &[]);
+        assert_eq!(sandbox.render("render(0)"), [0.2, 0.4, 1.0]);
+        assert_color(
+            combine(&[rgb, hsv24], &[BlendMode::Add]).render("render(0)"),
+            [0.2, 0.4, 1.0],
+        );
+        assert_color(
+            combine(&[hsv24, rgb], &[BlendMode::Average]).render("render(0)"),
+            [0.1, 0.2, 0.75],
+        );
+        // A pattern's own hsv() is called, its rgb() captured
+        assert_color(
+            combine(&[hsv, GRAY], &[BlendMode::Subtract]).render("render(0)"),
+            [0.0, 0.0, 0.0],
+        );
+        assert_color(combine(&[hsv], &[]).render("render(0)"), [0.1, 0.1, 0.1]);
+    }
+
+    #[test]
+    fn keeps_layers_apart() {
+        // Same variables and functions in both layers
+        let layer = |speed| {
+            format!(
+                "var v = 0\nfunction step(delta) {{ return delta * {speed} }}\n\
+                 export function beforeRender(delta) {{ v += step(delta) }}\n\
+                 export function render(index) {{ hsv(0, 0, v) }}"
+            )
+        };
+        let sandbox = combine(&[&layer(1), &layer(3)], &[BlendMode::Average]);
+        sandbox.eval::<()>("beforeRender(0.1)");
+        assert_color(sandbox.render("render(0)"), [0.2, 0.2, 0.2]);
+        assert!((sandbox.eval::<f64>("__state1[0]") - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn falls_back_to_fewer_dimensions() {
+        let flat = "export function render(index) { hsv(0, 1, index / 10) }";
+        let plane = "export function render2D(index, x, y) { hsv(0.5, 1, x) }";
+        let patterns = [transform_pattern(flat), transform_pattern(plane)];
+        let combined = combine_patterns(&patterns, &[BlendMode::Add]).unwrap();
+        assert!(combined.contains("export function render2D(index, x, y)"));
+        assert!(!combined.contains("render3D"));
+
+        let sandbox = Sandbox::new(&combined);
+        // Only the flat layer renders in 1D
+        assert_color(sandbox.render("render(5)"), [0.5, 0.0, 0.0]);
+        assert_color(sandbox.render("render2D(5, 0.5, 0)"), [0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn exports_controls_of_layers() {
+        let dimmable = "export var level = 1\nexport function sliderLevel(v) { level = v }\n\
+                        export function render(index) { hsv(0, 1, level) }";
+        let sandbox = combine(&[GREEN, dimmable], &[BlendMode::Add]);
+        assert_color(sandbox.render("render(0)"), [1.0, 1.0, 0.0]);
+        sandbox.eval::<()>("sliderLayer1Level(0.25)");
+        assert_color(sandbox.render("render(0)"), [0.25, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn parses_combinations() {
+        assert_eq!(
+            "Lighthouse Sparkle: # Leuchtturm.epe, add, blink fade.epe,MASK ,x.epe".parse(),
+            Ok(Combination {
+                name: "Lighthouse Sparkle".to_string(),
+                patterns: vec![
+                    "# Leuchtturm.epe".to_string(),
+                    "blink fade.epe".to_string(),
+                    "x.epe".to_string()
+                ],
+                blend_modes: vec![BlendMode::Add, BlendMode::Mask],
+            })
+        );
+        assert_eq!(
+            "a.epe, ADD, b.epe".parse::<Combination>(),
+            Err(CombineError::MissingName)
+        );
+        assert_eq!(
+            "x: a.epe, ADD".parse::<Combination>(),
+            Err(CombineError::BlendModeCount {
+                patterns: 1,
+                blend_modes: 1
+            })
+        );
+        assert_eq!("x: ".parse::<Combination>(), Err(CombineError::NoPatterns));
+        assert_eq!(
+            "x: a.epe, BURN, b.epe".parse::<Combination>(),
+            Err(CombineError::UnknownBlendMode("BURN".to_string()))
+        );
+        assert_eq!(
+            combine_patterns(&[transform_pattern(RED)], &[BlendMode::Add]),
+            Err(CombineError::BlendModeCount {
+                patterns: 1,
+                blend_modes: 1
+            })
+        );
+        assert_eq!(combine_patterns(&[], &[]), Err(CombineError::NoPatterns));
+    }
+}
@@ -0,0 +1,32 @@
+//! # Source Edits
+//!
+//! Replacements of byte ranges of a pattern, applied in one pass so the
+//! ranges of the syntax tree stay valid while edits are collected.
+
+use std::ops::Range;
+
+/// Replacement of a source range.
+#[derive(Debug)]
+pub(crate) struct Edit {
+    pub(crate) range: Range<usize>,
+    pub(crate) text: String,
+}
+
+/// Apply non-overlapping edits to a range of the source. Edits outside of the
+/// range are skipped.
+pub(crate) fn apply(source: &str, edits: &mut [Edit], range: Range<usize>) -> String {
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    let mut result = String::with_capacity(range.len() + edits.len() * 16);
+    let mut copied = range.start;
+    for edit in edits
+        .iter()
+        .filter(|edit| edit.range.start >= range.start && edit.range.end <= range.end)
+    {
+        debug_assert!(edit.range.start >= copied, "overlapping edit {edit:?}");
+        result.push_str(&source[copied..edit.range.start]);
+        result.push_str(&edit.text);
+        copied = edit.range.end;
+    }
+    result.push_str(&source[copied..range.end]);
+    result
+}
@@ -0,0 +1,87 @@
+//! # Pattern Exports
+//!
+//! Reads Pixelblaze pattern exports (`.epe`): JSON with the pattern name, id,
+//! a preview image and the source in `sources.main`.
+
+use std::fmt;
+
+/// Pattern read from an `.epe` export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Epe {
+    pub name: String,
+    /// Pixelblaze pattern id, if exported with one
+    pub id: Option<String>,
+    /// Pattern source (`sources.main`)
+    pub source: String,
+}
+
+/// Export error types
+#[derive(Debug)]
+pub enum EpeError {
+    /// Not JSON
+    Json(json::Error),
+    /// No `sources.main` string
+    MissingSource,
+}
+
+impl fmt::Display for EpeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpeError::Json(e) => write!(f, "invalid pattern export: {e}"),
+            EpeError::MissingSource => f.write_str("pattern export without sources.main"),
+        }
+    }
+}
+
+impl std::error::Error for EpeError {}
+
+impl Epe {
+    /// Read an export. Pixelblaze writes them with a byte order mark, which is
+    /// skipped.
+    pub fn parse(export: &str) -> Result<Self, EpeError> {
+        let export = export.trim_start_matches('\u{feff}');
+        let export = json::parse(export).map_err(EpeError::Json)?;
+        let source = export["sources"]["main"]
+            .as_str()
+            .ok_or(EpeError::MissingSource)?;
+
+        Ok(Self {
+            name: export["name"].as_str().unwrap_or_default().to_string(),
+            id: export["id"].as_str().map(str::to_string),
+            source: source.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exports() {
+        let epe = Epe::parse(
+            "\u{feff}{\"name\": \"Test Pattern\", \"id\": \"test123\", \"sources\": {\"main\": \
+             \"var brightness = 0.5;\\nexport function render(index) {\\n  hsv(0.2, 1, brightness);\\n}\"}, \
+             \"preview\": \"/9j/4AAQ\"}",
+        )
+        .unwrap();
+        assert_eq!(epe.name, "Test Pattern");
+        assert_eq!(epe.id.as_deref(), Some("test123"));
+        assert_eq!(
+            epe.source,
+            "var brightness = 0.5;\nexport function render(index) {\n  hsv(0.2, 1, brightness);\n}"
+        );
+
+        let epe = Epe::parse("{\"sources\": {\"main\": \"export var speed = 0.1\"}}").unwrap();
+        assert_eq!((epe.name.as_str(), epe.id), ("", None));
+    }
+
+    #[test]
+    fn rejects_invalid_exports() {
+        assert!(matches!(Epe::parse("{\"name\": "), Err(EpeError::Json(_))));
+        assert!(matches!(
+            Epe::parse("{\"name\": \"x\", \"sources\": {}}"),
+            Err(EpeError::MissingSource)
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+//! # Superpattern
+//!
+//! Combines Pixelblaze patterns into one pattern (a superpattern) at build
+//! time, the Rust counterpart of `superpattern-js`. Runs on the development
+//! machine and in the firmware's `build.rs`.
+//!
+//! Before patterns can run side by side, their variables are isolated by
+//! [`transform_pattern`]:
+//! - **State variables**: Top-level declarations (`var speed = 0.1`) become
+//!   `__state__[n]`.
+//! - **Globals**: Exported variables (`export var hue`) and variables assigned
+//!   without a declaration become `__globals__[n]`.
+//! - **Functions**: Every function takes `__state__` and `__globals__` as its
+//!   first parameters, calls of the pattern's own functions pass them on.
+//!
+//! Parameters and local variables stay as they are, also where they shadow
+//! state variables or globals, and so do comments and formatting:
+//! ```
+//! let result = superpattern::transform_pattern(
+//!     "var t = 0;\nexport function beforeRender(delta) {\n  t += delta;\n}",
+//! );
+//! assert_eq!(result.state_vars, ["t"]);
+//! assert_eq!(
+//!     result.transformed_pattern,
+//!     "__state__[0] = 0;\nexport function beforeRender(__state__, __globals__, delta) {\n  __state__[0] += delta;\n}",
+//! );
+//! ```
+//!
+//! Functions are passed the arrays also when Pixelblaze calls them back
+//! (`arrayMutate`, `setPixelMapper`), so patterns relying on callbacks of
+//! builtins are not supported.
+
+mod blend; // Blend modes of layers
+mod combine; // Combination of patterns as layers
+mod edit; // Source edits
+mod epe; // Pixelblaze pattern exports
+#[cfg(test)]
+mod sandbox; // Pixelblaze stand-in running patterns in tests
+mod scope; // Variable declarations and name resolution
+mod syntax; // Syntax check of transformed patterns
+mod transform; // Rewriting to __state__ and __globals__
+
+pub use blend::BlendMode;
+pub use combine::{combine_patterns, Combination, CombineError};
+pub use epe::{Epe, EpeError};
+pub use syntax::{check_syntax, SyntaxError};
+pub use transform::{transform_pattern, TransformResult, GLOBALS, STATE};
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    /// Lighthouse patterns, shared with `superpattern-js`
+    const EXAMPLES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../superpattern-js/example/patterns"
+    );
+
+    /// Expected transformations of the examples, rewritten by running the
+    /// tests with `UPDATE_GOLDEN=1`
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+
+    fn examples() -> Vec<std::path::PathBuf> {
+        let mut examples: Vec<_> = fs::read_dir(EXAMPLES)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == "epe"))
+            .collect();
+        examples.sort();
+        assert!(!examples.is_empty());
+        examples
+    }
+
+    #[test]
+    fn transforms_example_patterns() {
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        for example in examples() {
+            let epe = Epe::parse(&fs::read_to_string(&example).unwrap()).unwrap();
+            let result = transform_pattern(&epe.source);
+            if let Err(e) = check_syntax(&result.transformed_pattern) {
+                panic!("{}: {e}", example.display());
+            }
+
+            let name = example.file_stem().unwrap().to_string_lossy();
+            let golden = Path::new(GOLDEN).join(format!("{name}.js"));
+            if update {
+                fs::write(&golden, &result.transformed_pattern).unwrap();
+                continue;
+            }
+            let expected =
+                fs::read_to_string(&golden).unwrap_or_else(|e| panic!("{}: {e}", golden.display()));
+            assert_eq!(result.transformed_pattern, expected, "{}", golden.display());
+        }
+    }
+
+    #[test]
+    fn combines_example_patterns() {
+        let patterns: Vec<_> = examples()
+            .iter()
+            .map(|example| Epe::parse(&fs::read_to_string(example).unwrap()).unwrap())
+            .map(|epe| transform_pattern(&epe.source))
+            .collect();
+        let blend_modes: Vec<_> = BlendMode::ALL
+            .into_iter()
+            .cycle()
+            .take(patterns.len() - 1)
+            .collect();
+        let combined = combine_patterns(&patterns, &blend_modes).unwrap();
+        check_syntax(&combined).unwrap();
+        assert!(combined.contains("export function render2D(index, x, y)"));
+    }
+}
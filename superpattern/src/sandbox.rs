@@ -0,0 +1,94 @@
+//! # Pixelblaze Sandbox
+//!
+//! Runs generated patterns in QuickJS for tests, with stand-ins for the
+//! Pixelblaze builtins they use. `Math` is removed, so code relying on
+//! JavaScript beyond Pixelblaze fails. Time and randomness are fixed.
+
+use rquickjs::{CatchResultExt, Context, FromJs, Runtime};
+
+/// Pixelblaze builtins. The colors set by `hsv` and `rgb` end up in `__out`
+/// as RGB.
+const BUILTINS: &str = "
+var pixelCount = 8
+var PI = Math.PI
+var PI2 = Math.PI * 2
+var __floor = Math.floor
+var __sin = Math.sin
+var __out = [0, 0, 0]
+
+function floor(v) { return __floor(v) }
+function min(a, b) { return a < b ? a : b }
+function max(a, b) { return a > b ? a : b }
+function abs(v) { return v < 0 ? -v : v }
+function clamp(v, low, high) { return min(max(v, low), high) }
+function sin(v) { return __sin(v) }
+function wave(v) { return (1 + __sin(v * PI2)) / 2 }
+function triangle(v) { v = v - __floor(v); return v < 0.5 ? v * 2 : 2 - v * 2 }
+function time(interval) { return 0.25 }
+function random(v) { return v / 2 }
+function array(n) { var a = []; for (var i = 0; i < n; i++) a.push(0); return a }
+
+function rgb(r, g, b) { __out = [clamp(r, 0, 1), clamp(g, 0, 1), clamp(b, 0, 1)] }
+function hsv(h, s, v) {
+  s = clamp(s, 0, 1)
+  v = clamp(v, 0, 1)
+  var h6 = (h - __floor(h)) * 6
+  var c = v * s
+  var x = c * (1 - abs(h6 % 2 - 1))
+  var m = v - c
+  var rgb1 = h6 < 1 ? [c, x, 0] : h6 < 2 ? [x, c, 0] : h6 < 3 ? [0, c, x]
+    : h6 < 4 ? [0, x, c] : h6 < 5 ? [x, 0, c] : [c, 0, x]
+  __out = [rgb1[0] + m, rgb1[1] + m, rgb1[2] + m]
+}
+
+delete globalThis.Math
+";
+
+/// Pattern running in QuickJS.
+pub(crate) struct Sandbox {
+    _runtime: Runtime,
+    context: Context,
+}
+
+impl Sandbox {
+    /// Run the top-level code of a pattern.
+    pub(crate) fn new(pattern: &str) -> Self {
+        let runtime = Runtime::new().unwrap();
+        let context = Context::full(&runtime).unwrap();
+        let sandbox = Self {
+            _runtime: runtime,
+            context,
+        };
+        sandbox.eval::<()>(BUILTINS);
+        // Scripts can't export, Pixelblaze only looks at the names
+        sandbox.eval::<()>(&pattern.replace("export function ", "function "));
+        sandbox
+    }
+
+    /// Evaluate code in the pattern, panics on exceptions.
+    pub(crate) fn eval<T>(&self, code: &str) -> T
+    where
+        T: for<'js> FromJs<'js>,
+    {
+        self.context.with(|ctx| {
+            ctx.eval(code)
+                .catch(&ctx)
+                .unwrap_or_else(|e| panic!("{e}\nin:\n{code}"))
+        })
+    }
+
+    /// Color output by a renderer call like `render(3)`, black if none.
+    pub(crate) fn render(&self, call: &str) -> [f64; 3] {
+        let rgb: Vec<f64> = self.eval(&format!("__out = [0, 0, 0]; {call}; __out"));
+        [rgb[0], rgb[1], rgb[2]]
+    }
+}
+
+/// Assert two colors are equal up to rounding errors.
+pub(crate) fn assert_color(actual: [f64; 3], expected: [f64; 3]) {
+    let close = actual
+        .iter()
+        .zip(expected)
+        .all(|(a, e)| (a - e).abs() < 1e-9);
+    assert!(close, "{actual:?} != {expected:?}");
+}
@@ -0,0 +1,283 @@
+//! # Variables of a Pattern
+//!
+//! Finds the state variables, globals and functions of a pattern and resolves
+//! names to them. Functions are the only scopes: Pixelblaze has no block
+//! scoping, so `let` and `const` are treated like `var`.
+
+use std::collections::{HashMap, HashSet};
+
+use tree_sitter::{Node, Parser, Tree};
+
+/// Parse Pixelblaze JavaScript. Syntax errors end up as error nodes in the
+/// tree, the rest of the pattern is still parsed.
+pub(crate) fn parse(source: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_javascript::LANGUAGE.into())
+        .expect("JavaScript grammar matches the tree-sitter version");
+    parser
+        .parse(source, None)
+        .expect("parsing without timeout or cancellation succeeds")
+}
+
+/// Whether the node is a function, and so a scope of its own.
+pub(crate) fn is_function(node: Node) -> bool {
+    matches!(
+        node.kind(),
+        "function_declaration"
+            | "function_expression"
+            | "generator_function_declaration"
+            | "generator_function"
+            | "arrow_function"
+    )
+}
+
+/// Whether the node is a `var`, `let` or `const` declaration.
+pub(crate) fn is_declaration(node: Node) -> bool {
+    matches!(node.kind(), "variable_declaration" | "lexical_declaration")
+}
+
+/// Whether the node is outside of all functions.
+pub(crate) fn is_top_level(node: Node) -> bool {
+    ancestors(node).all(|n| !is_function(n))
+}
+
+/// Parents of the node, innermost first.
+pub(crate) fn ancestors(node: Node) -> impl Iterator<Item = Node> {
+    std::iter::successors(node.parent(), |n| n.parent())
+}
+
+/// Nodes of the subtree in source order, starting with `node`.
+pub(crate) fn descendants(node: Node) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut cursor = node.walk();
+    loop {
+        nodes.push(cursor.node());
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.node() == node {
+                return nodes;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            cursor.goto_parent();
+        }
+    }
+}
+
+/// Whether an identifier declares a name (variable, parameter or function
+/// name) rather than referencing one.
+pub(crate) fn is_binding(identifier: Node) -> bool {
+    let Some(parent) = identifier.parent() else {
+        return false;
+    };
+    let is_field = |field| parent.child_by_field_name(field) == Some(identifier);
+    match parent.kind() {
+        "variable_declarator" => is_field("name"),
+        "formal_parameters" | "rest_pattern" => true,
+        "assignment_pattern" => is_field("left"),
+        "arrow_function" => is_field("parameter"),
+        kind if is_function(parent) || kind == "class_declaration" => is_field("name"),
+        _ => false,
+    }
+}
+
+/// What a name refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Binding {
+    /// Parameter, variable or function of an enclosing function
+    Local {
+        /// Declared as a function, or initialized with one
+        function: bool,
+    },
+    /// State variable with its index in `__state__`
+    State(usize),
+    /// Global with its index in `__globals__`
+    Global(usize),
+    /// Function declared at the top level of the pattern
+    Function,
+    /// Pixelblaze builtin, or not declared at all
+    Unknown,
+}
+
+/// Names declared in a function.
+#[derive(Debug, Default)]
+struct Scope {
+    names: HashSet<String>,
+    /// Names declared as functions or initialized with one
+    functions: HashSet<String>,
+}
+
+/// Variables and functions of a pattern.
+#[derive(Debug)]
+pub(crate) struct Variables {
+    /// Top-level variable declarations, in source order
+    pub(crate) state_vars: Vec<String>,
+    /// Exported variables, then names assigned without a declaration
+    pub(crate) globals: Vec<String>,
+    /// Functions declared at the top level
+    functions: HashSet<String>,
+    /// Scope of each function, by node id
+    scopes: HashMap<usize, Scope>,
+}
+
+impl Variables {
+    /// Collect the variables of a parsed pattern.
+    pub(crate) fn new(tree: &Tree, source: &str) -> Self {
+        let text = |node: Node| source[node.byte_range()].to_string();
+        let nodes = descendants(tree.root_node());
+        let mut variables = Self {
+            state_vars: Vec::new(),
+            globals: Vec::new(),
+            functions: HashSet::new(),
+            scopes: HashMap::new(),
+        };
+
+        for &node in &nodes {
+            if is_function(node) {
+                variables.scopes.insert(node.id(), Scope::of(node, source));
+            }
+            if !is_top_level(node) {
+                continue;
+            }
+            if is_declaration(node) {
+                let exported = node
+                    .parent()
+                    .is_some_and(|p| p.kind() == "export_statement");
+                let names = match exported {
+                    true => &mut variables.globals,
+                    false => &mut variables.state_vars,
+                };
+                for name in declared_names(node) {
+                    push_unique(names, text(name));
+                }
+            } else if node.kind().ends_with("function_declaration") {
+                if let Some(name) = node.child_by_field_name("name") {
+                    variables.functions.insert(text(name));
+                }
+            }
+        }
+
+        // Exported globals come first, then the ones assigned without declaration
+        for &node in &nodes {
+            let Some(left) = assigned_identifier(node) else {
+                continue;
+            };
+            let name = text(left);
+            if variables.resolve(left, &name) == Binding::Unknown {
+                push_unique(&mut variables.globals, name);
+            }
+        }
+
+        variables
+    }
+
+    /// What the name at the given node refers to.
+    pub(crate) fn resolve(&self, node: Node, name: &str) -> Binding {
+        for scope in ancestors(node).filter_map(|n| self.scopes.get(&n.id())) {
+            if scope.names.contains(name) {
+                return Binding::Local {
+                    function: scope.functions.contains(name),
+                };
+            }
+        }
+        if let Some(n) = self.state_vars.iter().position(|v| v == name) {
+            Binding::State(n)
+        } else if let Some(n) = self.globals.iter().position(|v| v == name) {
+            Binding::Global(n)
+        } else if self.functions.contains(name) {
+            Binding::Function
+        } else {
+            Binding::Unknown
+        }
+    }
+}
+
+impl Scope {
+    /// Parameters, variables and nested functions declared by a function.
+    fn of(function: Node, source: &str) -> Self {
+        let text = |node: Node| source[node.byte_range()].to_string();
+        let mut scope = Self::default();
+
+        // A named function expression can call itself by its name
+        if !function.kind().ends_with("declaration") {
+            if let Some(name) = function.child_by_field_name("name") {
+                scope.functions.insert(text(name));
+            }
+        }
+
+        let parameters = function
+            .child_by_field_name("parameters")
+            .or_else(|| function.child_by_field_name("parameter"));
+        if let Some(parameters) = parameters {
+            for node in descendants(parameters) {
+                if node.kind() == "identifier" && is_binding(node) {
+                    scope.names.insert(text(node));
+                }
+            }
+        }
+
+        if let Some(body) = function.child_by_field_name("body") {
+            let mut pending: Vec<Node> = children(body);
+            while let Some(node) = pending.pop() {
+                if is_function(node) {
+                    // Only the name of a nested declaration belongs to this scope
+                    if node.kind().ends_with("declaration") {
+                        if let Some(name) = node.child_by_field_name("name") {
+                            scope.functions.insert(text(name));
+                        }
+                    }
+                    continue;
+                }
+                if node.kind() == "variable_declarator" {
+                    if let Some(name) = node.child_by_field_name("name") {
+                        if name.kind() == "identifier" {
+                            let value = node.child_by_field_name("value");
+                            match value.is_some_and(is_function) {
+                                true => scope.functions.insert(text(name)),
+                                false => scope.names.insert(text(name)),
+                            };
+                        }
+                    }
+                }
+                pending.extend(children(node));
+            }
+        }
+
+        scope.names.extend(scope.functions.iter().cloned());
+        scope
+    }
+}
+
+/// Names declared by a declaration, destructuring patterns are skipped.
+pub(crate) fn declared_names(declaration: Node) -> impl Iterator<Item = Node> {
+    children(declaration)
+        .into_iter()
+        .filter(|n| n.kind() == "variable_declarator")
+        .filter_map(|n| n.child_by_field_name("name"))
+        .filter(|n| n.kind() == "identifier")
+}
+
+/// Identifier assigned by an assignment like `x = 1` or `x += 1`.
+fn assigned_identifier(node: Node) -> Option<Node> {
+    match node.kind() {
+        "assignment_expression" | "augmented_assignment_expression" => node
+            .child_by_field_name("left")
+            .filter(|left| left.kind() == "identifier"),
+        _ => None,
+    }
+}
+
+/// Named children of a node.
+pub(crate) fn children(node: Node) -> Vec<Node> {
+    node.named_children(&mut node.walk()).collect()
+}
+
+fn push_unique(names: &mut Vec<String>, name: String) {
+    if !names.contains(&name) {
+        names.push(name);
+    }
+}
@@ -0,0 +1,61 @@
+//! # Syntax Check
+//!
+//! Checks that a (transformed) pattern still parses. Pixelblaze accepts a
+//! subset of JavaScript, so this catches broken rewrites, not every pattern
+//! Pixelblaze would reject.
+
+use std::fmt;
+
+use crate::scope;
+
+/// First syntax error of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// Line, starting at 1
+    pub line: usize,
+    /// Column in bytes, starting at 1
+    pub column: usize,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "syntax error at {}:{}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Check that a pattern parses, the first error otherwise.
+pub fn check_syntax(source: &str) -> Result<(), SyntaxError> {
+    let tree = scope::parse(source);
+    match scope::descendants(tree.root_node())
+        .into_iter()
+        .find(|n| n.is_error() || n.is_missing())
+    {
+        Some(node) => Err(SyntaxError {
+            line: node.start_position().row + 1,
+            column: node.start_position().column + 1,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_syntax_errors() {
+        assert_eq!(
+            check_syntax("export function render(index) {\n  hsv(0, 1, 1)\n}"),
+            Ok(())
+        );
+        assert_eq!(
+            check_syntax("var a = 1;\nhsv(0, 1, 1));"),
+            Err(SyntaxError {
+                line: 2,
+                column: 13
+            })
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! # Pattern Transformation
+//!
+//! Rewrites the variables of a pattern to `__state__` and `__globals__`
+//! (see the crate documentation). The source is edited in place, so
+//! formatting and comments are kept and line numbers stay the same except
+//! where a declaration of several variables is split up.
+
+use tree_sitter::Node;
+
+use crate::edit::{self, Edit};
+use crate::scope::{self, Binding, Variables};
+
+/// Array holding the state variables of a pattern
+pub const STATE: &str = "__state__";
+
+/// Array holding the globals of a pattern
+pub const GLOBALS: &str = "__globals__";
+
+/// Value of state variables and globals declared without one, as Pixelblaze
+/// has no `undefined`
+const UNINITIALIZED: &str = "0";
+
+/// Transformed pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformResult {
+    /// State variables, by index in `__state__`
+    pub state_vars: Vec<String>,
+    /// Exported variables and variables assigned without declaration, by
+    /// index in `__globals__`
+    pub globals: Vec<String>,
+    /// Source of the transformed pattern
+    pub transformed_pattern: String,
+}
+
+/// Transform the source of a Pixelblaze pattern.
+///
+/// Parts of the pattern with syntax errors are kept as they are.
+pub fn transform_pattern(source: &str) -> TransformResult {
+    let tree = scope::parse(source);
+    let variables = Variables::new(&tree, source);
+    let text = |node: Node| &source[node.byte_range()];
+    let mut edits = Vec::new();
+
+    for node in scope::descendants(tree.root_node()) {
+        match node.kind() {
+            _ if scope::is_declaration(node) && scope::is_top_level(node) => {
+                declaration_edits(node, source, &variables, &mut edits)
+            }
+            _ if scope::is_function(node) => parameter_edits(node, source, &mut edits),
+            "identifier" if !scope::is_binding(node) => {
+                if let Some(array) = array_reference(variables.resolve(node, text(node))) {
+                    edits.push(Edit {
+                        range: node.byte_range(),
+                        text: array,
+                    });
+                }
+            }
+            // `{ speed }` is short for `{ speed: speed }`
+            "shorthand_property_identifier" => {
+                if let Some(array) = array_reference(variables.resolve(node, text(node))) {
+                    edits.push(Edit {
+                        range: node.byte_range(),
+                        text: format!("{}: {array}", text(node)),
+                    });
+                }
+            }
+            "call_expression" => call_edits(node, source, &variables, &mut edits),
+            _ => {}
+        }
+    }
+
+    TransformResult {
+        transformed_pattern: edit::apply(source, &mut edits, 0..source.len()),
+        state_vars: variables.state_vars,
+        globals: variables.globals,
+    }
+}
+
+/// `__state__[n]` or `__globals__[n]` for state variables and globals.
+fn array_reference(binding: Binding) -> Option<String> {
+    match binding {
+        Binding::State(n) => Some(format!("{STATE}[{n}]")),
+        Binding::Global(n) => Some(format!("{GLOBALS}[{n}]")),
+        _ => None,
+    }
+}
+
+/// Turn a top-level declaration into assignments, one per variable:
+/// `var a, b = 1;` becomes `__state__[0] = 0;` and `__state__[1] = 1;` on
+/// lines of their own (separated by commas in a `for` loop initializer).
+/// Exported variables are assigned to `__globals__` instead.
+fn declaration_edits(
+    declaration: Node,
+    source: &str,
+    variables: &Variables,
+    edits: &mut Vec<Edit>,
+) {
+    let declarators: Vec<Node> = scope::children(declaration)
+        .into_iter()
+        .filter(|n| n.kind() == "variable_declarator")
+        .collect();
+    // Destructuring is not supported by Pixelblaze, leave it alone
+    if declarators.len() != scope::declared_names(declaration).count() {
+        return;
+    }
+
+    let parent = declaration.parent();
+    let start = match parent.filter(|p| p.kind() == "export_statement") {
+        Some(export) => export.start_byte(),
+        None => declaration.start_byte(),
+    };
+    let separator = match parent.is_some_and(|p| p.kind() == "for_statement") {
+        true => ", ".to_string(),
+        false => format!(";\n{}", indentation(source, start)),
+    };
+
+    let mut from = start;
+    for (n, declarator) in declarators.iter().enumerate() {
+        let Some(name) = declarator.child_by_field_name("name") else {
+            continue;
+        };
+        let Some(array) = array_reference(variables.resolve(name, &source[name.byte_range()]))
+        else {
+            continue;
+        };
+
+        let mut text = match n {
+            0 => array,
+            _ => format!("{separator}{array}"),
+        };
+        if declarator.child_by_field_name("value").is_none() {
+            text.push_str(" = ");
+            text.push_str(UNINITIALIZED);
+        }
+        edits.push(Edit {
+            range: from..name.end_byte(),
+            text,
+        });
+        from = declarator.end_byte();
+    }
+}
+
+/// Prepend `__state__` and `__globals__` to the parameters of a function.
+fn parameter_edits(function: Node, source: &str, edits: &mut Vec<Edit>) {
+    if let Some(parameters) = function.child_by_field_name("parameters") {
+        edits.push(prepend_arguments(parameters));
+    } else if let Some(parameter) = function.child_by_field_name("parameter") {
+        // `x => ...` needs parentheses for more than one parameter
+        edits.push(Edit {
+            range: parameter.byte_range(),
+            text: format!("({STATE}, {GLOBALS}, {})", &source[parameter.byte_range()]),
+        });
+    }
+}
+
+/// Pass `__state__` and `__globals__` on to functions of the pattern, called
+/// directly, through a variable holding them or out of an array
+/// (`modes[mode](x)`) in a state variable or global.
+fn call_edits(call: Node, source: &str, variables: &Variables, edits: &mut Vec<Edit>) {
+    let Some(mut function) = call.child_by_field_name("function") else {
+        return;
+    };
+    let Some(arguments) = call
+        .child_by_field_name("arguments")
+        .filter(|a| a.kind() == "arguments")
+    else {
+        return;
+    };
+
+    let mut subscripted = false;
+    while function.kind() == "subscript_expression" {
+        let Some(object) = function.child_by_field_name("object") else {
+            return;
+        };
+        function = object;
+        subscripted = true;
+    }
+    if function.kind() != "identifier" {
+        return;
+    }
+
+    match variables.resolve(function, &source[function.byte_range()]) {
+        Binding::State(_) | Binding::Global(_) => edits.push(prepend_arguments(arguments)),
+        Binding::Local { function: true } | Binding::Function if !subscripted => {
+            edits.push(prepend_arguments(arguments))
+        }
+        _ => {}
+    }
+}
+
+/// Insert `__state__, __globals__` after the opening parenthesis of a
+/// parameter or argument list.
+fn prepend_arguments(list: Node) -> Edit {
+    let empty = scope::children(list).iter().all(|n| n.is_extra());
+    let at = list.start_byte() + 1;
+    Edit {
+        range: at..at,
+        text: match empty {
+            true => format!("{STATE}, {GLOBALS}"),
+            false => format!("{STATE}, {GLOBALS}, "),
+        },
+    }
+}
+
+/// Whitespace in front of `at` if it is the first thing on its line.
+fn indentation(source: &str, at: usize) -> &str {
+    let line_start = source[..at].rfind('\n').map_or(0, |n| n + 1);
+    let indentation = &source[line_start..at];
+    match indentation.trim().is_empty() {
+        true => indentation,
+        false => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(source: &str) -> String {
+        transform_pattern(source).transformed_pattern
+    }
+
+    /// Cases of `superpattern-js/test/transform.test.js`. Deviations from the
+    /// JavaScript transformer are marked.
+    #[test]
+    fn matches_superpattern_js() {
+        let cases = [
+            // Variable detection
+            ("var brightness = 0.5;", "__state__[0] = 0.5;"),
+            (
+                "var brightness = 0.5;\nvar hue = 0.3;",
+                "__state__[0] = 0.5;\n__state__[1] = 0.3;",
+            ),
+            ("currentHue = time(0.1);", "__globals__[0] = time(0.1);"),
+            (
+                "brightness = 0.8;\ncurrentHue = time(0.1);",
+                "__globals__[0] = 0.8;\n__globals__[1] = time(0.1);",
+            ),
+            // Function parameters
+            (
+                "export function render(index) {\n  hsv(0, 1, 0.5);\n}",
+                "export function render(__state__, __globals__, index) {\n  hsv(0, 1, 0.5);\n}",
+            ),
+            (
+                "export function beforeRender(delta) {\n  // do something\n}",
+                "export function beforeRender(__state__, __globals__, delta) {\n  // do something\n}",
+            ),
+            (
+                "function render(index, x, y) {\n  return index;\n}",
+                "function render(__state__, __globals__, index, x, y) {\n  return index;\n}",
+            ),
+            // References
+            (
+                "var brightness = 0.5;\nexport function render(index) {\n  brightness *= 0.99;\n  \
+                 hsv(0, 1, brightness);\n}",
+                "__state__[0] = 0.5;\nexport function render(__state__, __globals__, index) {\n  \
+                 __state__[0] *= 0.99;\n  hsv(0, 1, __state__[0]);\n}",
+            ),
+            (
+                "currentHue = 0;\nexport function render(index) {\n  currentHue += 0.01;\n  \
+                 hsv(currentHue, 1, 0.5);\n}",
+                "__globals__[0] = 0;\nexport function render(__state__, __globals__, index) {\n  \
+                 __globals__[0] += 0.01;\n  hsv(__globals__[0], 1, 0.5);\n}",
+            ),
+            (
+                "var brightness = 0.5;\nhueShift = 0;\nexport function render(index) {\n  \
+                 brightness *= 0.99;\n  hueShift += 0.01;\n  hsv(hueShift, 1, brightness);\n}",
+                "__state__[0] = 0.5;\n__globals__[0] = 0;\n\
+                 export function render(__state__, __globals__, index) {\n  __state__[0] *= 0.99;\n  \
+                 __globals__[0] += 0.01;\n  hsv(__globals__[0], 1, __state__[0]);\n}",
+            ),
+            // Locals
+            (
+                "export function render(index) {\n  var localVar = index * 2;\n  \
+                 var brightness = 0.5;\n  return localVar;\n}",
+                "export function render(__state__, __globals__, index) {\n  \
+                 var localVar = index * 2;\n  var brightness = 0.5;\n  return localVar;\n}",
+            ),
+            (
+                "export function render(index, x, y) {\n  index = index * 2;\n  x = x + 1;\n  \
+                 return index + x;\n}",
+                "export function render(__state__, __globals__, index, x, y) {\n  \
+                 index = index * 2;\n  x = x + 1;\n  return index + x;\n}",
+            ),
+            // Shadowing
+            (
+                "var brightness = 0.5;\nexport function render(brightness) {\n  \
+                 brightness = 0.8;\n  return brightness;\n}",
+                "__state__[0] = 0.5;\nexport function render(__state__, __globals__, brightness) {\n  \
+                 brightness = 0.8;\n  return brightness;\n}",
+            ),
+            (
+                "hueValue = 0.3;\nexport function render(index) {\n  var hueValue = 0.6;\n  \
+                 hueValue = 0.9;\n  return hueValue;\n}",
+                "__globals__[0] = 0.3;\nexport function render(__state__, __globals__, index) {\n  \
+                 var hueValue = 0.6;\n  hueValue = 0.9;\n  return hueValue;\n}",
+            ),
+            (
+                "var state = 42;\nglobalVar = 100;\nexport function render(state) {\n  \
+                 var globalVar = 200;\n  state = 300;\n  globalVar = 400;\n}\n\
+                 export function other() {\n  state = 500;\n  globalVar = 600;\n}",
+                "__state__[0] = 42;\n__globals__[0] = 100;\n\
+                 export function render(__state__, __globals__, state) {\n  var globalVar = 200;\n  \
+                 state = 300;\n  globalVar = 400;\n}\nexport function other(__state__, __globals__) {\n  \
+                 __state__[0] = 500;\n  __globals__[0] = 600;\n}",
+            ),
+            // Realistic patterns
+            (
+                "var brightness = 0.8;\nexport function render(index) {\n  brightness *= 0.99;\n  \
+                 if (brightness < 0.1) brightness = 1.0;\n  hsv(0, 1, brightness);\n}",
+                "__state__[0] = 0.8;\nexport function render(__state__, __globals__, index) {\n  \
+                 __state__[0] *= 0.99;\n  if (__state__[0] < 0.1) __state__[0] = 1.0;\n  \
+                 hsv(0, 1, __state__[0]);\n}",
+            ),
+            (
+                "var values = array(pixelCount);\ni = 0;\nexport function beforeRender(delta) {\n  \
+                 for (i = 0; i < pixelCount; i++) {\n    values[i] -= 0.01;\n    \
+                 if (values[i] <= 0) {\n      values[i] = random(1);\n    }\n  }\n}\n\
+                 export function render(index) {\n  hsv(time(0.1), 1, values[index]);\n}",
+                "__state__[0] = array(pixelCount);\n__globals__[0] = 0;\n\
+                 export function beforeRender(__state__, __globals__, delta) {\n  \
+                 for (__globals__[0] = 0; __globals__[0] < pixelCount; __globals__[0]++) {\n    \
+                 __state__[0][__globals__[0]] -= 0.01;\n    \
+                 if (__state__[0][__globals__[0]] <= 0) {\n      \
+                 __state__[0][__globals__[0]] = random(1);\n    }\n  }\n}\n\
+                 export function render(__state__, __globals__, index) {\n  \
+                 hsv(time(0.1), 1, __state__[0][index]);\n}",
+            ),
+            (
+                "export var brightness = 0.5;\nexport function sliderBrightness(value) {\n  \
+                 brightness = value;\n}\nexport function render(index) {\n  \
+                 hsv(time(0.1), 1, brightness);\n}",
+                "__globals__[0] = 0.5;\nexport function sliderBrightness(__state__, __globals__, value) {\n  \
+                 __globals__[0] = value;\n}\nexport function render(__state__, __globals__, index) {\n  \
+                 hsv(time(0.1), 1, __globals__[0]);\n}",
+            ),
+            // Edge cases; uninitialized variables are 0, not `undefined`
+            (
+                "var a, b = 5, c;",
+                "__state__[0] = 0;\n__state__[1] = 5;\n__state__[2] = 0;",
+            ),
+            (
+                "export function render(index) {\n  hsv(1, 2, 3);\n}",
+                "export function render(__state__, __globals__, index) {\n  hsv(1, 2, 3);\n}",
+            ),
+            (
+                "var brightness = 0.5;\nexport function render(index) {\n  if (index > 0) {\n    \
+                 var localBrightness = brightness * 0.5;\n    brightness = localBrightness;\n  }\n}",
+                "__state__[0] = 0.5;\nexport function render(__state__, __globals__, index) {\n  \
+                 if (index > 0) {\n    var localBrightness = __state__[0] * 0.5;\n    \
+                 __state__[0] = localBrightness;\n  }\n}",
+            ),
+            // Comments, the leading one is kept
+            (
+                "// Pattern: Rainbow Wave\nvar speed = 0.1;\n/* Global hue offset */\nhueOffset = 0;\n\n\
+                 export function beforeRender(delta) {\n  hueOffset += delta * speed;\n}\n\n\
+                 export function render(index) {\n  hsv((hueOffset + index / pixelCount) % 1, 1, 1);\n}",
+                "// Pattern: Rainbow Wave\n__state__[0] = 0.1;\n/* Global hue offset */\n__globals__[0] = 0;\n\n\
+                 export function beforeRender(__state__, __globals__, delta) {\n  \
+                 __globals__[0] += delta * __state__[0];\n}\n\n\
+                 export function render(__state__, __globals__, index) {\n  \
+                 hsv((__globals__[0] + index / pixelCount) % 1, 1, 1);\n}",
+            ),
+            // Arrays and objects
+            (
+                "var colors = [1, 0, 0];\ncurrentIndex = 0;\nexport function render(index) {\n  \
+                 colors[currentIndex] = 0.5;\n  return colors[0];\n}",
+                "__state__[0] = [1, 0, 0];\n__globals__[0] = 0;\n\
+                 export function render(__state__, __globals__, index) {\n  \
+                 __state__[0][__globals__[0]] = 0.5;\n  return __state__[0][0];\n}",
+            ),
+            (
+                "config = { speed: 0.1, brightness: 0.8 };\nexport function render(index) {\n  \
+                 config.speed = 0.2;\n  hsv(0, 1, config.brightness);\n}",
+                "__globals__[0] = { speed: 0.1, brightness: 0.8 };\n\
+                 export function render(__state__, __globals__, index) {\n  \
+                 __globals__[0].speed = 0.2;\n  hsv(0, 1, __globals__[0].brightness);\n}",
+            ),
+            // Functions; calls through variables pass the arrays on as well
+            (
+                "var getValue = function(x) { return x * 2; };\nexport function render(index) {\n  \
+                 var result = getValue(index);\n  hsv(0, 1, result);\n}",
+                "__state__[0] = function(__state__, __globals__, x) { return x * 2; };\n\
+                 export function render(__state__, __globals__, index) {\n  \
+                 var result = __state__[0](__state__, __globals__, index);\n  hsv(0, 1, result);\n}",
+            ),
+            (
+                "export function render(index) {\n  function helper(value) {\n    \
+                 return value * 2;\n  }\n  var result = helper(index);\n  hsv(0, 1, result);\n}",
+                "export function render(__state__, __globals__, index) {\n  \
+                 function helper(__state__, __globals__, value) {\n    return value * 2;\n  }\n  \
+                 var result = helper(__state__, __globals__, index);\n  hsv(0, 1, result);\n}",
+            ),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(transform(source), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn transforms_state_variables() {
+        let result = transform_pattern(
+            "var a = 1;\nvar b = a * 2;\nexport function render(index) {\n  hsv(a, 1, b);\n}",
+        );
+        assert_eq!(result.state_vars, ["a", "b"]);
+        assert!(result.globals.is_empty());
+        assert_eq!(
+            result.transformed_pattern,
+            "__state__[0] = 1;\n__state__[1] = __state__[0] * 2;\n\
+             export function render(__state__, __globals__, index) {\n  \
+             hsv(__state__[0], 1, __state__[1]);\n}"
+        );
+    }
+
+    #[test]
+    fn splits_declarations() {
+        let result =
+            transform_pattern("  var a, b = 5, c; // three\nfor (var i = 0, j; i < 3; i++) {}");
+        assert_eq!(result.state_vars, ["a", "b", "c", "i", "j"]);
+        assert_eq!(
+            result.transformed_pattern,
+            "  __state__[0] = 0;\n  __state__[1] = 5;\n  __state__[2] = 0; // three\n\
+             for (__state__[3] = 0, __state__[4] = 0; __state__[3] < 3; __state__[3]++) {}"
+        );
+    }
+
+    #[test]
+    fn transforms_globals() {
+        let result = transform_pattern(
+            "export var speed = 0.1\nexport function sliderSpeed(v) {\n  speed = v\n  hue += v\n}\n\
+             export function beforeRender(delta) {\n  t1 = time(speed)\n}",
+        );
+        assert!(result.state_vars.is_empty());
+        assert_eq!(result.globals, ["speed", "hue", "t1"]);
+        assert_eq!(
+            result.transformed_pattern,
+            "__globals__[0] = 0.1\nexport function sliderSpeed(__state__, __globals__, v) {\n  \
+             __globals__[0] = v\n  __globals__[1] += v\n}\n\
+             export function beforeRender(__state__, __globals__, delta) {\n  \
+             __globals__[2] = time(__globals__[0])\n}"
+        );
+    }
+
+    #[test]
+    fn keeps_locals_and_parameters() {
+        let result = transform_pattern(
+            "var x = 1;\nvar v = 2;\nfunction f(x) {\n  var v = x;\n  y = v;\n  return x + v;\n}",
+        );
+        assert_eq!(result.state_vars, ["x", "v"]);
+        assert_eq!(result.globals, ["y"]);
+        assert_eq!(
+            result.transformed_pattern,
+            "__state__[0] = 1;\n__state__[1] = 2;\nfunction f(__state__, __globals__, x) {\n  \
+             var v = x;\n  __globals__[0] = v;\n  return x + v;\n}"
+        );
+    }
+
+    #[test]
+    fn passes_state_to_functions_of_the_pattern() {
+        assert_eq!(
+            transform(
+                "var wave = (x) => sin(x);\nvar half = x => x / 2;\nfunction helper() { return 1; }\n\
+                 export function render(index) {\n  function inner(i) { return i; }\n  \
+                 var local = function() { return 2; };\n  \
+                 hsv(wave(index) + half(helper()) + inner(index) + local(), 1, abs(index));\n}"
+            ),
+            "__state__[0] = (__state__, __globals__, x) => sin(x);\n\
+             __state__[1] = (__state__, __globals__, x) => x / 2;\n\
+             function helper(__state__, __globals__) { return 1; }\n\
+             export function render(__state__, __globals__, index) {\n  \
+             function inner(__state__, __globals__, i) { return i; }\n  \
+             var local = function(__state__, __globals__) { return 2; };\n  \
+             hsv(__state__[0](__state__, __globals__, index) + \
+             __state__[1](__state__, __globals__, helper(__state__, __globals__)) + \
+             inner(__state__, __globals__, index) + local(__state__, __globals__), 1, abs(index));\n}"
+        );
+    }
+
+    #[test]
+    fn passes_state_to_functions_in_arrays() {
+        assert_eq!(
+            transform(
+                "var modes = [(x) => x, function(x) { return 1 - x; }];\n\
+                 export function render(index) {\n  var local = [0];\n  \
+                 hsv(modes[0](index) + local[0], 1, 1);\n}"
+            ),
+            "__state__[0] = [(__state__, __globals__, x) => x, \
+             function(__state__, __globals__, x) { return 1 - x; }];\n\
+             export function render(__state__, __globals__, index) {\n  var local = [0];\n  \
+             hsv(__state__[0][0](__state__, __globals__, index) + local[0], 1, 1);\n}"
+        );
+    }
+
+    #[test]
+    fn keeps_properties_and_comments() {
+        assert_eq!(
+            transform(
+                "var speed = 1; /* state */\n// Render\nfunction f(o) {\n  \
+                 return { speed: o.speed, s: speed, speed }; // shorthand\n}"
+            ),
+            "__state__[0] = 1; /* state */\n// Render\nfunction f(__state__, __globals__, o) {\n  \
+             return { speed: o.speed, s: __state__[0], speed: __state__[0] }; // shorthand\n}"
+        );
+    }
+
+    #[test]
+    fn keeps_shadowing_by_nested_functions() {
+        assert_eq!(
+            transform(
+                "var a = 1;\nfunction outer() {\n  var a = 2;\n  \
+                 function inner() { return a; }\n  return inner() + b;\n}\nvar b = 3;"
+            ),
+            "__state__[0] = 1;\nfunction outer(__state__, __globals__) {\n  var a = 2;\n  \
+             function inner(__state__, __globals__) { return a; }\n  \
+             return inner(__state__, __globals__) + __state__[1];\n}\n__state__[1] = 3;"
+        );
+    }
+}